@@ -0,0 +1,7 @@
+DROP TABLE status;
+DROP TABLE contest_register_lists;
+DROP TABLE contests;
+DROP TABLE problems;
+DROP TABLE test_cases;
+DROP TABLE regions;
+DROP TABLE users;
@@ -0,0 +1,88 @@
+-- the tables from before migrations were kept, existing databases already have them
+CREATE TABLE IF NOT EXISTS users (
+    id SERIAL PRIMARY KEY,
+    username TEXT NOT NULL UNIQUE,
+    email TEXT,
+    mobile TEXT,
+    job_number TEXT,
+    role TEXT NOT NULL,
+    salt VARCHAR NOT NULL,
+    register_time TIMESTAMP NOT NULL DEFAULT NOW(),
+    hash BYTEA NOT NULL,
+    school TEXT
+);
+
+CREATE TABLE IF NOT EXISTS regions (
+    name TEXT PRIMARY KEY,
+    need_pass BOOL NOT NULL,
+    salt VARCHAR,
+    hash BYTEA,
+    self_type TEXT NOT NULL,
+    judge_type TEXT
+);
+
+CREATE TABLE IF NOT EXISTS test_cases (
+    name TEXT PRIMARY KEY,
+    is_spj BOOL NOT NULL,
+    count INT4 NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS problems (
+    id INT4 NOT NULL,
+    region TEXT NOT NULL REFERENCES regions(name) ON DELETE CASCADE,
+    title TEXT NOT NULL,
+    description TEXT,
+    input_explain TEXT,
+    output_explain TEXT,
+    input_examples TEXT[] NOT NULL,
+    output_examples TEXT[] NOT NULL,
+    hint TEXT,
+    tags TEXT[] NOT NULL,
+    sources TEXT[] NOT NULL,
+    difficulty TEXT NOT NULL,
+    submit_times INT4 NOT NULL DEFAULT 0,
+    accept_times INT4 NOT NULL DEFAULT 0,
+    default_max_cpu_time INT4 NOT NULL,
+    default_max_memory INT4 NOT NULL,
+    test_case TEXT REFERENCES test_cases(name) ON DELETE SET NULL,
+    max_score INT4 NOT NULL,
+    opaque_output BOOL NOT NULL DEFAULT false,
+    PRIMARY KEY (region, id)
+);
+
+CREATE TABLE IF NOT EXISTS contests (
+    region TEXT PRIMARY KEY REFERENCES regions(name) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    state TEXT NOT NULL,
+    start_time TIMESTAMP NOT NULL,
+    end_time TIMESTAMP NOT NULL,
+    seal_before_end INT4,
+    register_end_time TIMESTAMP NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS contest_register_lists (
+    contest_region TEXT NOT NULL REFERENCES contests(region) ON DELETE CASCADE,
+    user_id INT4 NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    is_unrated BOOL NOT NULL DEFAULT false,
+    register_after_end BOOL NOT NULL DEFAULT false,
+    PRIMARY KEY (contest_region, user_id)
+);
+
+CREATE TABLE IF NOT EXISTS status (
+    id UUID PRIMARY KEY,
+    owner_id INT4 NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    problem_id INT4 NOT NULL,
+    problem_region TEXT NOT NULL REFERENCES regions(name) ON DELETE CASCADE,
+    state TEXT NOT NULL,
+    judge_type TEXT NOT NULL,
+    result TEXT,
+    score FLOAT8,
+    setting_data TEXT NOT NULL,
+    result_data TEXT,
+    err_reason TEXT,
+    submit_time TIMESTAMP NOT NULL,
+    start_pend_time TIMESTAMP,
+    finish_time TIMESTAMP,
+    language TEXT NOT NULL,
+    host_name TEXT
+);
@@ -0,0 +1,2 @@
+DROP TABLE rejudge_items;
+DROP TABLE rejudges;
@@ -0,0 +1,14 @@
+CREATE TABLE rejudges (
+    id UUID PRIMARY KEY,
+    target TEXT NOT NULL,
+    operator_id INT4 NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    create_time TIMESTAMP NOT NULL
+);
+
+CREATE TABLE rejudge_items (
+    rejudge_id UUID NOT NULL REFERENCES rejudges(id) ON DELETE CASCADE,
+    status_id UUID NOT NULL REFERENCES status(id) ON DELETE CASCADE,
+    old_result TEXT,
+    old_score FLOAT8,
+    PRIMARY KEY (rejudge_id, status_id)
+);
@@ -0,0 +1 @@
+ALTER TABLE status ADD COLUMN retry_times INT4 NOT NULL DEFAULT 0;
@@ -0,0 +1 @@
+DROP TABLE judge_server_rejections;
@@ -0,0 +1,8 @@
+CREATE TABLE judge_server_rejections (
+    id SERIAL PRIMARY KEY,
+    service_url TEXT,
+    hostname TEXT,
+    remote_addr TEXT,
+    reason TEXT NOT NULL,
+    create_time TIMESTAMP NOT NULL
+);
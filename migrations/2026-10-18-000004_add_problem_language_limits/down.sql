@@ -0,0 +1,2 @@
+ALTER TABLE problems DROP COLUMN language_limits;
+ALTER TABLE problems DROP COLUMN allowed_languages;
@@ -0,0 +1,2 @@
+ALTER TABLE problems ADD COLUMN allowed_languages TEXT[] NULL;
+ALTER TABLE problems ADD COLUMN language_limits TEXT NULL;
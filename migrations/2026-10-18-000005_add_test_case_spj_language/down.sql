@@ -0,0 +1 @@
+ALTER TABLE test_cases DROP COLUMN spj_language;
@@ -0,0 +1,2 @@
+ALTER TABLE test_cases ADD COLUMN spj_language TEXT NULL;
+UPDATE test_cases SET spj_language = 'c' WHERE is_spj;
@@ -0,0 +1 @@
+ALTER TABLE test_cases ADD COLUMN is_interactive BOOL NOT NULL DEFAULT false;
@@ -0,0 +1,2 @@
+ALTER TABLE problems DROP COLUMN comparison_epsilon;
+ALTER TABLE problems DROP COLUMN comparison_mode;
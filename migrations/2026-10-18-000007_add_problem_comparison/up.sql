@@ -0,0 +1,2 @@
+ALTER TABLE problems ADD COLUMN comparison_mode TEXT NULL;
+ALTER TABLE problems ADD COLUMN comparison_epsilon FLOAT8 NULL;
@@ -0,0 +1,5 @@
+DROP INDEX status_verdict_idx;
+ALTER TABLE status DROP COLUMN max_memory;
+ALTER TABLE status DROP COLUMN max_time;
+ALTER TABLE status DROP COLUMN verdict_test_case;
+ALTER TABLE status DROP COLUMN verdict;
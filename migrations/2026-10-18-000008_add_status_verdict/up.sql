@@ -0,0 +1,43 @@
+ALTER TABLE status ADD COLUMN verdict TEXT NULL;
+ALTER TABLE status ADD COLUMN verdict_test_case TEXT NULL;
+ALTER TABLE status ADD COLUMN max_time INT4 NULL;
+ALTER TABLE status ADD COLUMN max_memory INT4 NULL;
+
+CREATE INDEX status_verdict_idx ON status (verdict);
+
+-- judged statuses get the verdict of their first failing test case
+WITH tests AS (
+    SELECT status.id, t.value AS test, t.ordinality
+    FROM status, json_array_elements(status.result_data::json -> 'data') WITH ORDINALITY AS t(value, ordinality)
+    WHERE status.state = 'Finished'
+        AND status.result_data IS NOT NULL
+        AND json_typeof(status.result_data::json -> 'data') = 'array'
+), first_failures AS (
+    SELECT DISTINCT ON (id) id, test ->> 'test_case' AS test_case, (test ->> 'result')::INT4 AS code
+    FROM tests
+    WHERE (test ->> 'result')::INT4 <> 0
+    ORDER BY id, ordinality
+), costs AS (
+    SELECT id, MAX((test ->> 'cpu_time')::INT4) AS max_time, MAX((test ->> 'memory')::INT4) AS max_memory
+    FROM tests
+    GROUP BY id
+)
+UPDATE status SET
+    verdict = CASE
+        WHEN first_failures.id IS NULL THEN 'AC'
+        WHEN first_failures.code = -2 THEN 'PE'
+        WHEN first_failures.code = -1 THEN 'WA'
+        WHEN first_failures.code IN (1, 2) THEN 'TLE'
+        WHEN first_failures.code = 3 THEN 'MLE'
+        WHEN first_failures.code = 4 THEN 'RE'
+        ELSE 'SE'
+    END,
+    verdict_test_case = first_failures.test_case,
+    max_time = costs.max_time,
+    max_memory = costs.max_memory
+FROM costs LEFT JOIN first_failures ON first_failures.id = costs.id
+WHERE status.id = costs.id;
+
+-- the rest failed before running any test case
+UPDATE status SET verdict = CASE result WHEN 'CompileError' THEN 'CE' WHEN 'Accepted' THEN 'AC' ELSE 'SE' END
+WHERE state = 'Finished' AND verdict IS NULL AND result IS NOT NULL;
@@ -0,0 +1 @@
+ALTER TABLE status DROP COLUMN judge_server_url;
@@ -0,0 +1,2 @@
+-- host names aren't unique across judge servers, the service url is
+ALTER TABLE status ADD COLUMN judge_server_url TEXT NULL;
@@ -0,0 +1 @@
+ALTER TABLE rejudge_items DROP COLUMN old_verdict;
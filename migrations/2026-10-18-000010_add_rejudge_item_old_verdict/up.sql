@@ -0,0 +1,2 @@
+-- a verdict can change while result and score stay, like WA to TLE
+ALTER TABLE rejudge_items ADD COLUMN old_verdict TEXT NULL;
@@ -0,0 +1,223 @@
+//! A stand-in for a QDUOJ-style JudgeServer, for end-to-end tests.
+//!
+//! It registers itself with heartbeats, answers `/ping`, and answers `/judge`
+//! with scripted verdicts instead of running anything. Configured by env:
+//!
+//! - `MOCK_JUDGE_BIND`: address to listen on, `127.0.0.1:12358` by default
+//! - `MOCK_JUDGE_SERVICE_URL`: url reported in heartbeats, `http://<bind>` by default
+//! - `MOCK_JUDGE_BACKEND_URL`: the online judge to send heartbeats to, `http://127.0.0.1:8080` by default
+//! - `MOCK_JUDGE_TOKEN`: sent and expected in `X-Judge-Server-Token`, `mock_token` by default.
+//!   The online judge checks it against the sha256 of its `JUDGE_SERVER_SECRET`.
+//! - `MOCK_JUDGE_SCRIPT`: json file mapping test case ids to verdicts,
+//!   e.g. `{ "default": ["SUCCESS"], "test_cases": { "a_plus_b": ["SUCCESS", "WRONG_ANSWER"] } }`
+//!
+//! A submission can also carry its own verdicts in a line like
+//! `MOCK_VERDICT: SUCCESS WRONG_ANSWER`, one word for each test case. Besides the
+//! result names understood by the judge manager, a single `CompileError`,
+//! `SPJCompileError`, `HTTP_500`, `MALFORMED` or `HANG` changes the whole response.
+
+#[macro_use] extern crate log;
+#[macro_use] extern crate serde_derive;
+
+use actix_web::{ web, App, HttpRequest, HttpResponse, HttpServer };
+use actix_web::client::Client;
+use serde_json::json;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::sync::atomic::{ AtomicI32, Ordering };
+use std::time::Duration;
+
+const VERDICT_DIRECTIVE: &str = "MOCK_VERDICT:";
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct Script {
+    #[serde(default)]
+    default: Vec<String>,
+    #[serde(default)]
+    test_cases: HashMap<String, Vec<String>>,
+}
+
+struct MockState {
+    token: String,
+    script: Script,
+    running_tasks: AtomicI32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct JudgeRequest {
+    src: String,
+    test_case_id: Option<String>,
+    test_case: Option<Vec<serde_json::Value>>,
+    output: bool,
+}
+
+fn result_code(verdict: &str) -> Option<i32> {
+    match verdict {
+        "WRONG_ANSWER" => Some(-1),
+        "SUCCESS" => Some(0),
+        "CPU_TIME_LIMIT_EXCEEDED" => Some(1),
+        "REAL_TIME_LIMIT_EXCEEDED" => Some(2),
+        "MEMORY_LIMIT_EXCEEDED" => Some(3),
+        "RUNTIME_ERROR" => Some(4),
+        "SYSTEM_ERROR" => Some(5),
+        _ => None,
+    }
+}
+
+fn get_verdicts(state: &MockState, req: &JudgeRequest) -> Vec<String> {
+    let directive = req.src.lines()
+        .filter_map(|line| line.find(VERDICT_DIRECTIVE).map(|pos| &line[pos + VERDICT_DIRECTIVE.len()..]))
+        .next();
+    if let Some(directive) = directive {
+        return directive.split_whitespace().map(|verdict| verdict.to_owned()).collect();
+    }
+
+    let scripted = req.test_case_id.as_ref().and_then(|id| state.script.test_cases.get(id));
+    match scripted {
+        Some(verdicts) => verdicts.clone(),
+        None if !state.script.default.is_empty() => state.script.default.clone(),
+        // one verdict for each inline test case, or a single test case
+        None => vec!["SUCCESS".to_owned(); req.test_case.as_ref().map(|cases| cases.len()).unwrap_or(1).max(1)],
+    }
+}
+
+fn invalid_token() -> HttpResponse {
+    HttpResponse::Ok().json(json!({ "err": "InvalidToken", "data": "Invalid token" }))
+}
+
+fn check_token(state: &MockState, req: &HttpRequest) -> bool {
+    req.headers().get("X-Judge-Server-Token")
+        .and_then(|token| token.to_str().ok())
+        .map(|token| token == state.token)
+        .unwrap_or(false)
+}
+
+async fn ping(state: web::Data<MockState>, req: HttpRequest) -> HttpResponse {
+    if !check_token(&state, &req) { return invalid_token(); }
+
+    HttpResponse::Ok().json(json!({
+        "err": null,
+        "data": {
+            "judger_version": "mock",
+            "hostname": "mock_judge_server",
+            "running_task_number": state.running_tasks.load(Ordering::SeqCst),
+            "action": "pong",
+        },
+    }))
+}
+
+async fn judge(state: web::Data<MockState>, req: HttpRequest, body: web::Bytes) -> HttpResponse {
+    if !check_token(&state, &req) { return invalid_token(); }
+
+    let judge_request: JudgeRequest = match serde_json::from_slice(&body) {
+        Ok(judge_request) => judge_request,
+        Err(e) => {
+            return HttpResponse::Ok().json(json!({ "err": "JudgeClientError", "data": e.to_string() }));
+        },
+    };
+
+    let verdicts = get_verdicts(&state, &judge_request);
+    info!("judging {:?} with {:?}", judge_request.test_case_id, verdicts);
+
+    match verdicts.first().map(|verdict| verdict.as_str()) {
+        Some("CompileError") | Some("SPJCompileError") => {
+            return HttpResponse::Ok().json(json!({
+                "err": verdicts[0],
+                "data": "mock.c:1:1: error: scripted compile error",
+            }));
+        },
+        Some("HTTP_500") => { return HttpResponse::InternalServerError().finish(); },
+        Some("MALFORMED") => { return HttpResponse::Ok().body("this is not a judge result"); },
+        Some("HANG") => {
+            state.running_tasks.fetch_add(1, Ordering::SeqCst);
+            actix_rt::time::delay_for(Duration::from_secs(3600)).await;
+            state.running_tasks.fetch_sub(1, Ordering::SeqCst);
+        },
+        _ => (),
+    }
+
+    let data: Vec<serde_json::Value> = verdicts.iter()
+        .enumerate()
+        .map(|(index, verdict)| {
+            let result = result_code(verdict).unwrap_or(5);
+            json!({
+                "cpu_time": 1,
+                "real_time": 2,
+                "memory": 1024 * 1024,
+                "signal": 0,
+                "exit_code": if result == 4 { 1 } else { 0 },
+                "error": 0,
+                "result": result,
+                "test_case": (index + 1).to_string(),
+                "output_md5": null,
+                // inline test cases are run by `cat`
+                "output": if judge_request.output {
+                    Some(judge_request.test_case.as_ref()
+                        .and_then(|cases| cases.get(index))
+                        .and_then(|case| case["input"].as_str())
+                        .unwrap_or(""))
+                } else { None },
+            })
+        })
+        .collect();
+
+    HttpResponse::Ok().json(json!({ "err": null, "data": data }))
+}
+
+async fn send_heartbeats(backend_url: String, service_url: String, token: String) {
+    let client = Client::new();
+    loop {
+        let response = client
+            .post(format!("{}/judge_server/heartbeat", backend_url))
+            .set_header("X-Judge-Server-Token", token.clone())
+            .send_json(&json!({
+                "judger_version": "mock",
+                "hostname": "mock_judge_server",
+                "cpu_core": 1,
+                "memory": 0.0,
+                "cpu": 0.0,
+                "service_url": service_url,
+            }))
+            .await;
+        if let Err(e) = response {
+            info!("heartbeat to {} failed: {}", backend_url, e);
+        }
+        actix_rt::time::delay_for(Duration::from_secs(1)).await;
+    }
+}
+
+#[actix_web::main]
+async fn main() -> io::Result<()> {
+    dotenv::dotenv().ok();
+    env_logger::init();
+
+    let bind = env::var("MOCK_JUDGE_BIND").unwrap_or("127.0.0.1:12358".to_owned());
+    let service_url = env::var("MOCK_JUDGE_SERVICE_URL").unwrap_or(format!("http://{}", bind));
+    let backend_url = env::var("MOCK_JUDGE_BACKEND_URL").unwrap_or("http://127.0.0.1:8080".to_owned());
+    let token = env::var("MOCK_JUDGE_TOKEN").unwrap_or("mock_token".to_owned());
+    let script = match env::var("MOCK_JUDGE_SCRIPT") {
+        Ok(path) => serde_json::from_slice(&fs::read(&path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+        Err(_) => Script::default(),
+    };
+
+    let state = web::Data::new(MockState {
+        token: token.clone(),
+        script: script,
+        running_tasks: AtomicI32::new(0),
+    });
+
+    actix_rt::spawn(send_heartbeats(backend_url, service_url, token));
+
+    HttpServer::new(move || {
+        App::new()
+            .app_data(state.clone())
+            .service(web::resource("/ping").route(web::post().to(ping)))
+            .service(web::resource("/judge").route(web::post().to(judge)))
+    })
+    .bind(bind)?
+    .run()
+    .await
+}
@@ -2,7 +2,7 @@ use crate::{
     utils::time::get_cur_naive_date_time,
     database::*,
     errors::{ServiceError, ServiceResult},
-    status::model::Status,
+    status::model::{ Status, Verdict },
     contest::model::Contest,
 };
 use diesel::prelude::*;
@@ -23,6 +23,8 @@ pub struct ACMSolutionPreview {
     pub try_times: i32,
     pub state: String,
     pub solve_time: i32,
+    /// verdict of the latest counted submission, hidden while sealed
+    pub verdict: Option<Verdict>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, juniper::GraphQLObject)]
@@ -111,7 +113,8 @@ impl Handler<GetACMRankMessage> for DbExecutor {
                                 problem_id: problem_id,
                                 try_times: 0,
                                 solve_time: 0,
-                                state: String::from("Untried")
+                                state: String::from("Untried"),
+                                verdict: None,
                             }
                         )
                     }
@@ -138,8 +141,13 @@ impl Handler<GetACMRankMessage> for DbExecutor {
                 }
 
                 let cur_time = get_cur_naive_date_time();    
-                // if effective submit
-                if status.result == Some(String::from("Accepted")) || status.result == Some(String::from("Unaccepted")) {
+                // compile and system errors don't count
+                let verdict = status.verdict.as_ref().and_then(|verdict| verdict.parse::<Verdict>().ok());
+                let is_effective = match verdict {
+                    None | Some(Verdict::CE) | Some(Verdict::SE) => false,
+                    Some(_) => true,
+                };
+                if is_effective {
                     if personal_colume.solution_previews[solution_index].state != String::from("Accepted") {
                         personal_colume.solution_previews[solution_index].try_times += 1;
                         // if set seal time, check
@@ -154,14 +162,15 @@ impl Handler<GetACMRankMessage> for DbExecutor {
                         }
                         // not in sealed time or sealed time didn't set
                         if personal_colume.solution_previews[solution_index].state != String::from("Sealed") {
-                            if status.result == Some(String::from("Accepted")) {
+                            personal_colume.solution_previews[solution_index].verdict = verdict;
+                            if verdict == Some(Verdict::AC) {
                                 personal_colume.solution_previews[solution_index].try_times -= 1;
                                 personal_colume.solution_previews[solution_index].state = String::from("Accepted");
                                 personal_colume.solution_previews[solution_index].solve_time = (status.submit_time - contest_info.start_time).num_seconds() as i32;
                                 personal_colume.total_penalty += 20*60*personal_colume.solution_previews[solution_index].try_times + personal_colume.solution_previews[solution_index].solve_time;
                                 personal_colume.total_accepted += 1;
                             }
-                            if status.result == Some(String::from("Unaccepted")) {
+                            if verdict != Some(Verdict::AC) {
                                 personal_colume.solution_previews[solution_index].state = String::from("Unaccepted");
                             }
                         }
@@ -1,11 +1,14 @@
 pub mod mutations;
 pub mod queries;
+pub mod subscriptions;
+pub mod ws;
 
 pub mod schema {
     use juniper::RootNode;
     use super::mutations::*;
     use super::queries::*;
     
+    // subscriptions have their own schema, see `subscriptions` and `ws`
     pub type Schema = RootNode<'static, QueryRoot, MutationRoot>;
     
     pub fn create_schema() -> Schema {
@@ -69,6 +72,8 @@ pub async fn graphql(
 }
 
 pub(super) fn route(cfg: &mut web::ServiceConfig) {
-    cfg.service(web::resource("/graphql").route(web::post().to(graphql)))
+    cfg.service(web::resource("/graphql")
+            .route(web::post().to(graphql))
+            .route(web::get().to(ws::graphql_ws)))
         .service(web::resource("/graphiql").route(web::get().to(graphiql)));
 }
\ No newline at end of file
@@ -2,7 +2,13 @@ pub struct MutationRoot;
 // use juniper::FieldResult;
 use futures::executor;
 use super::Context;
-use crate::judge_server::service::submit::{ submit_service, SubmitResult };
+use uuid::Uuid;
+use crate::judge_server::service::{
+    submit::{ submit_service, SubmitResult },
+    run::start_run_service,
+    fleet::{ control_judge_server_service, FleetAction },
+    info::OutJudgeServerInfo,
+};
 use crate::region::service::new::NewRegionMessage;
 use crate::contest::{
     service::{
@@ -26,6 +32,7 @@ use crate::test_case::{
         delete::{ delete_test_case_service, DeleteTestCaseMessage },
     },
 };
+use crate::status::service::rejudge::{ rejudge_service, RejudgeTarget, RejudgeResult };
 use crate::utils::model::DeleteResult;
 use crate::errors::ServiceResult;
 
@@ -54,6 +61,21 @@ impl MutationRoot {
             context.id.clone()))
     }
 
+    /// Queues a custom input run, poll `runResult` with the returned id for its result.
+    fn run(
+        context: &Context,
+        src: String,
+        language: String,
+        stdin: String,
+    ) -> ServiceResult<Uuid> {
+        start_run_service(
+            context.jm.clone(),
+            language,
+            src,
+            stdin,
+            context.id.clone())
+    }
+
     fn new_contest(
         context: &Context,
         region: String,
@@ -143,6 +165,10 @@ impl MutationRoot {
         test_case: Option<String>,
         max_score: i32,
         opaque_output: bool,
+        allowed_languages: Option<Vec<String>>,
+        language_limits: Option<String>,
+        comparison_mode: Option<String>,
+        comparison_epsilon: Option<f64>,
     ) -> ServiceResult<OutProblem> {
         executor::block_on(new_problem_service(
             context.db.clone(),
@@ -164,6 +190,10 @@ impl MutationRoot {
                 test_case: test_case,
                 max_score: max_score,
                 opaque_output: opaque_output,
+                allowed_languages: allowed_languages,
+                language_limits: language_limits,
+                comparison_mode: comparison_mode,
+                comparison_epsilon: comparison_epsilon,
             },
             context.id.clone(),
         ))
@@ -189,6 +219,10 @@ impl MutationRoot {
         new_test_case: Option<String>,
         new_max_score: Option<i32>,
         new_opaque_output: Option<bool>,
+        new_allowed_languages: Option<Vec<String>>,
+        new_language_limits: Option<String>,
+        new_comparison_mode: Option<String>,
+        new_comparison_epsilon: Option<f64>,
     ) -> ServiceResult<OutProblem> {
         executor::block_on(update_problem_service(
             context.db.clone(),
@@ -211,6 +245,10 @@ impl MutationRoot {
                 new_test_case: new_test_case,
                 new_max_score: new_max_score,
                 new_opaque_output: new_opaque_output,
+                new_allowed_languages: new_allowed_languages,
+                new_language_limits: new_language_limits,
+                new_comparison_mode: new_comparison_mode,
+                new_comparison_epsilon: new_comparison_epsilon,
             },
             context.id.clone(),
         ))
@@ -243,4 +281,94 @@ impl MutationRoot {
             context.id.clone(),
         ))
     }
-}
\ No newline at end of file
+
+    fn rejudge_status(
+        context: &Context,
+        id: Uuid,
+    ) -> ServiceResult<RejudgeResult> {
+        executor::block_on(rejudge_service(
+            context.db.clone(),
+            context.jm.clone(),
+            RejudgeTarget::Status(id),
+            context.id.clone(),
+        ))
+    }
+
+    fn rejudge_problem(
+        context: &Context,
+        region: String,
+        problem_id: i32,
+    ) -> ServiceResult<RejudgeResult> {
+        executor::block_on(rejudge_service(
+            context.db.clone(),
+            context.jm.clone(),
+            RejudgeTarget::Problem(region, problem_id),
+            context.id.clone(),
+        ))
+    }
+
+    fn rejudge_region(
+        context: &Context,
+        region: String,
+    ) -> ServiceResult<RejudgeResult> {
+        executor::block_on(rejudge_service(
+            context.db.clone(),
+            context.jm.clone(),
+            RejudgeTarget::Region(region),
+            context.id.clone(),
+        ))
+    }
+
+    fn drain_judge_server(
+        context: &Context,
+        service_url: String,
+    ) -> ServiceResult<OutJudgeServerInfo> {
+        executor::block_on(control_judge_server_service(
+            context.db.clone(),
+            context.jm.clone(),
+            service_url,
+            FleetAction::Drain,
+            context.id.clone(),
+        ))
+    }
+
+    fn disable_judge_server(
+        context: &Context,
+        service_url: String,
+    ) -> ServiceResult<OutJudgeServerInfo> {
+        executor::block_on(control_judge_server_service(
+            context.db.clone(),
+            context.jm.clone(),
+            service_url,
+            FleetAction::Disable,
+            context.id.clone(),
+        ))
+    }
+
+    fn enable_judge_server(
+        context: &Context,
+        service_url: String,
+    ) -> ServiceResult<OutJudgeServerInfo> {
+        executor::block_on(control_judge_server_service(
+            context.db.clone(),
+            context.jm.clone(),
+            service_url,
+            FleetAction::Enable,
+            context.id.clone(),
+        ))
+    }
+
+    fn set_judge_server_max_tasks(
+        context: &Context,
+        service_url: String,
+        max_tasks: Option<i32>,
+    ) -> ServiceResult<OutJudgeServerInfo> {
+        executor::block_on(control_judge_server_service(
+            context.db.clone(),
+            context.jm.clone(),
+            service_url,
+            FleetAction::SetMaxTasks(max_tasks),
+            context.id.clone(),
+        ))
+    }
+}
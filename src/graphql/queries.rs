@@ -26,11 +26,16 @@ use crate::{
     },
     judge_server::service::{
         info::{ OutJudgeServerInfo,server_info as server_info_service },
+        rejection::{ GetRejectionsMessage, get_rejections_service, JudgeServerRejection },
+        language::{ OutLanguage, get_languages_service },
+        run::{ poll_run_service, RunPoll },
     },
     status::service::{
         catalog::{ StatusCatalog, get_status_catalog_service },
         get::{ GetStatusMessage, get_status_service, DetailedStatus },
+        rejudge::{ GetRejudgeReportMessage, get_rejudge_report_service, RejudgeReport },
     },
+    status::model::Verdict,
     contest::rank::acm::{ get_acm_rank_service, GetACMRankMessage, ACMRank },
     contest::service::{
         get::{ get_contest_service, GetContestForm },
@@ -96,6 +101,13 @@ impl QueryRoot {
         executor::block_on(get_status_service(context.db.clone(), GetStatusMessage{ id:id }, context.id.clone()))
     }
 
+    fn rejudge_report(
+        context: &Context,
+        id: Uuid,
+    ) -> ServiceResult<RejudgeReport> {
+        executor::block_on(get_rejudge_report_service(context.db.clone(), GetRejudgeReportMessage{ id: id }, context.id.clone()))
+    }
+
     fn user_catalog(
         context: &Context,
         id: Option<i32>,
@@ -128,6 +140,7 @@ impl QueryRoot {
         user_id: Option<i32>,
         username: Option<String>,
         language: Option<String>,
+        verdict: Option<Verdict>,
         page_number: i32,
     ) -> ServiceResult<StatusCatalog> {
         executor::block_on(get_status_catalog_service(
@@ -139,6 +152,7 @@ impl QueryRoot {
             user_id,
             username,
             language,
+            verdict.map(|verdict| verdict.as_str().to_owned()),
             page_number,
             context.id.clone())
         )
@@ -203,4 +217,22 @@ impl QueryRoot {
     ) -> ServiceResult<Vec<OutJudgeServerInfo>> {
         executor::block_on(server_info_service(context.id.clone()))
     }
+
+    fn languages() -> ServiceResult<Vec<OutLanguage>> {
+        executor::block_on(get_languages_service())
+    }
+
+    fn run_result(
+        context: &Context,
+        id: Uuid,
+    ) -> ServiceResult<RunPoll> {
+        poll_run_service(id, context.id.clone())
+    }
+
+    fn judge_server_rejections(
+        context: &Context,
+        count: i32,
+    ) -> ServiceResult<Vec<JudgeServerRejection>> {
+        executor::block_on(get_rejections_service(context.db.clone(), GetRejectionsMessage{ count: count }, context.id.clone()))
+    }
 }
\ No newline at end of file
@@ -0,0 +1,222 @@
+use juniper::{ EmptyMutation, RootNode };
+use juniper::Context as JuniperContext;
+use juniper::parser::{ Lexer, Token };
+use std::cell::RefCell;
+use uuid::Uuid;
+use crate::{
+    judge_manager::utils::feed::StatusChange,
+    judge_manager::utils::progress::ProgressEvent,
+    status::model::Verdict,
+};
+
+/// The feed one subscription asked for, found by running it once without an event.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FeedRequest {
+    StatusChanges { region: Option<String> },
+    JudgeProgress { status_id: Uuid },
+}
+
+#[derive(Debug, Clone)]
+pub enum FeedEvent {
+    StatusChange(StatusChange),
+    Progress(ProgressEvent),
+}
+
+pub struct SubscriptionContext {
+    /// `None` while the subscription is looked at before any event
+    pub event: Option<FeedEvent>,
+    pub requests: RefCell<Vec<FeedRequest>>,
+}
+
+impl JuniperContext for SubscriptionContext {}
+
+impl SubscriptionContext {
+    pub fn new(event: Option<FeedEvent>) -> Self {
+        SubscriptionContext {
+            event: event,
+            requests: RefCell::new(Vec::new()),
+        }
+    }
+}
+
+/// One step of judging, named like the events of `/status/progress`.
+#[derive(Debug, Clone, Serialize, juniper::GraphQLObject)]
+pub struct JudgeProgress {
+    pub event: String,
+    pub host_name: Option<String>,
+    pub test_case: Option<String>,
+    /// counts from 1
+    pub index: Option<i32>,
+    pub total: Option<i32>,
+    pub verdict: Option<Verdict>,
+    /// in ms
+    pub cpu_time: Option<i32>,
+    /// in bytes
+    pub memory: Option<i32>,
+}
+
+impl From<ProgressEvent> for JudgeProgress {
+    fn from(event: ProgressEvent) -> Self {
+        let mut progress = JudgeProgress {
+            event: event.name().to_owned(),
+            host_name: None,
+            test_case: None,
+            index: None,
+            total: None,
+            verdict: None,
+            cpu_time: None,
+            memory: None,
+        };
+        match event {
+            ProgressEvent::Waiting | ProgressEvent::Compiling => (),
+            ProgressEvent::Pending { host_name } => { progress.host_name = Some(host_name); },
+            ProgressEvent::Running { test_case, index, total } => {
+                progress.test_case = Some(test_case);
+                progress.index = Some(index);
+                progress.total = Some(total);
+            },
+            ProgressEvent::TestCaseFinished { test_case, index, total, verdict, cpu_time, memory } => {
+                progress.test_case = Some(test_case);
+                progress.index = Some(index);
+                progress.total = Some(total);
+                progress.verdict = Some(verdict);
+                progress.cpu_time = Some(cpu_time);
+                progress.memory = Some(memory);
+            },
+            ProgressEvent::Finished { verdict } => { progress.verdict = Some(verdict); },
+        }
+        progress
+    }
+}
+
+pub struct SubscriptionRoot;
+
+#[juniper::object(Context = SubscriptionContext)]
+/// The subscriptions served over websocket at /graphql, with the graphql-ws protocol.
+/// A subscription gets its field once for each event.
+impl SubscriptionRoot {
+
+    /// Changes of your own statuses, or the statuses of a region as they finish.
+    /// Verdicts are hidden while the contest of the region is sealed.
+    fn status_changes(context: &SubscriptionContext, region: Option<String>) -> Option<StatusChange> {
+        match &context.event {
+            None => {
+                context.requests.borrow_mut().push(FeedRequest::StatusChanges { region: region });
+                None
+            },
+            Some(FeedEvent::StatusChange(change)) => Some(change.clone()),
+            Some(_) => None,
+        }
+    }
+
+    /// Each step of judging one status, ending with its verdict.
+    fn judge_progress(context: &SubscriptionContext, status_id: Uuid) -> Option<JudgeProgress> {
+        match &context.event {
+            None => {
+                context.requests.borrow_mut().push(FeedRequest::JudgeProgress { status_id: status_id });
+                None
+            },
+            Some(FeedEvent::Progress(event)) => Some(JudgeProgress::from(event.clone())),
+            Some(_) => None,
+        }
+    }
+}
+
+pub type SubscriptionSchema = RootNode<'static, SubscriptionRoot, EmptyMutation<SubscriptionContext>>;
+
+pub fn create_subscription_schema() -> SubscriptionSchema {
+    SubscriptionSchema::new(SubscriptionRoot, EmptyMutation::new())
+}
+
+/// juniper 0.14 refuses to execute subscriptions,
+/// so their operations are turned into queries of `SubscriptionRoot`.
+pub fn as_query(document: &str) -> Result<String, String> {
+    let mut query = String::with_capacity(document.len());
+    let mut copied = 0;
+    let mut depth = 0;
+    // object values in arguments are not selection sets
+    let mut paren_depth = 0;
+    let mut at_definition = true;
+
+    for token in Lexer::new(document) {
+        let token = token.map_err(|e| format!("Syntax error: {}", e.item))?;
+        if at_definition {
+            match token.item {
+                Token::Name("subscription") => {
+                    let start = token.start.index();
+                    query.push_str(&document[copied..start]);
+                    query.push_str("query");
+                    copied = token.end.index();
+                },
+                Token::Name("fragment") | Token::EndOfFile => (),
+                _ => { return Err("Only subscriptions are served over websocket.".to_owned()); },
+            }
+            at_definition = false;
+        }
+        match token.item {
+            Token::ParenOpen => { paren_depth += 1; },
+            Token::ParenClose => { paren_depth -= 1; },
+            Token::CurlyOpen if paren_depth == 0 => { depth += 1; },
+            Token::CurlyClose if paren_depth == 0 => {
+                depth -= 1;
+                at_definition = depth == 0;
+            },
+            Token::EndOfFile => { break; },
+            _ => (),
+        }
+    }
+
+    query.push_str(&document[copied..]);
+    Ok(query)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use juniper::http::GraphQLRequest;
+
+    #[test]
+    fn subscriptions_become_queries() {
+        assert_eq!(
+            as_query("subscription Watch($id: Uuid!) { judgeProgress(statusId: $id) { event } }"),
+            Ok("query Watch($id: Uuid!) { judgeProgress(statusId: $id) { event } }".to_owned()),
+        );
+        assert_eq!(
+            as_query("subscription { statusChanges { id } } fragment F on JudgeProgress { event }"),
+            Ok("query { statusChanges { id } } fragment F on JudgeProgress { event }".to_owned()),
+        );
+    }
+
+    #[test]
+    fn other_operations_are_refused() {
+        assert!(as_query("query { statusChanges { id } }").is_err());
+        assert!(as_query("mutation { statusChanges { id } }").is_err());
+        assert!(as_query("{ statusChanges { id } }").is_err());
+        assert!(as_query("subscription { a { b } } subscription { c }").is_ok());
+        assert!(as_query("subscription { a { b } } query { c }").is_err());
+        assert!(as_query("subscription { \"").is_err());
+    }
+
+    #[test]
+    fn braces_in_arguments_are_not_selection_sets() {
+        let document = "subscription { statusChanges(filter: { region: \"a\" }) { id } } query { id }";
+        assert!(as_query(document).is_err());
+    }
+
+    #[test]
+    fn the_field_asked_for_is_recorded_without_an_event() {
+        let schema = create_subscription_schema();
+        let status_id = Uuid::new_v4();
+        let query = as_query(&format!("subscription {{ judgeProgress(statusId: \"{}\") {{ event }} }}", status_id)).unwrap();
+        let request = GraphQLRequest::new(query, None, None);
+
+        let context = SubscriptionContext::new(None);
+        assert!(request.execute(&schema, &context).is_ok());
+        assert_eq!(context.requests.into_inner(), vec![FeedRequest::JudgeProgress { status_id: status_id }]);
+
+        let context = SubscriptionContext::new(Some(FeedEvent::Progress(ProgressEvent::Compiling)));
+        let response = serde_json::to_value(request.execute(&schema, &context)).unwrap();
+        assert_eq!(response["data"]["judgeProgress"]["event"], ProgressEvent::Compiling.name());
+        assert!(context.requests.into_inner().is_empty());
+    }
+}
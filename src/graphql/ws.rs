@@ -0,0 +1,243 @@
+//! graphql-ws, the protocol of subscriptions-transport-ws, on the websocket codec of actix-http.
+
+use actix_codec::{ Decoder, Encoder };
+use actix_http::ws::{ self, Frame, Message };
+use actix_identity::Identity;
+use actix_web::{ web, Error, HttpRequest, HttpResponse };
+use actix_web::web::{ Bytes, BytesMut };
+use futures::channel::mpsc::{ unbounded, UnboundedSender };
+use futures::future::{ abortable, AbortHandle };
+use futures::stream::{ BoxStream, StreamExt };
+use juniper::{ InputValue, IntoFieldError };
+use juniper::http::GraphQLRequest;
+use serde_json::json;
+use std::collections::HashMap;
+use std::rc::Rc;
+use crate::{
+    database::*,
+    errors::ServiceError,
+    status::service::progress::{
+        watch_progress_service, WatchProgressMessage,
+        watch_status_feed_service, WatchStatusFeedMessage,
+    },
+};
+use super::subscriptions::*;
+
+const PROTOCOL: &str = "graphql-ws";
+
+#[derive(Debug, Deserialize)]
+struct ClientMessage {
+    #[serde(rename = "type")]
+    kind: String,
+    id: Option<String>,
+    payload: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StartPayload {
+    query: String,
+    #[serde(rename = "operationName")]
+    operation_name: Option<String>,
+    variables: Option<InputValue>,
+}
+
+/// Encodes messages into the response body, which ends when every clone is dropped.
+#[derive(Clone)]
+struct Outbox(UnboundedSender<Bytes>);
+
+impl Outbox {
+    fn send(&self, message: Message) {
+        let mut buf = BytesMut::new();
+        match ws::Codec::new().encode(message, &mut buf) {
+            Ok(()) => { let _ = self.0.unbounded_send(buf.freeze()); },
+            Err(e) => error!("Error encoding websocket message: {}", e),
+        }
+    }
+
+    fn send_json(&self, value: serde_json::Value) {
+        self.send(Message::Text(value.to_string()));
+    }
+
+    fn send_error(&self, id: &str, message: &str) {
+        self.send_json(json!({ "type": "error", "id": id, "payload": [{ "message": message }] }));
+    }
+
+    fn send_service_error(&self, id: &str, e: ServiceError) {
+        let e = e.into_field_error();
+        self.send_json(json!({
+            "type": "error",
+            "id": id,
+            "payload": [{ "message": e.message(), "extensions": e.extensions() }],
+        }));
+    }
+}
+
+pub async fn graphql_ws(
+    db: web::Data<DBState>,
+    req: HttpRequest,
+    payload: web::Payload,
+    id: Identity,
+) -> Result<HttpResponse, Error> {
+    let mut response = ws::handshake(req.head())?;
+    let asks_protocol = req.headers().get("sec-websocket-protocol")
+        .and_then(|protocols| protocols.to_str().ok())
+        .map_or(false, |protocols| protocols.split(',').any(|protocol| protocol.trim() == PROTOCOL));
+    if asks_protocol {
+        response.header("sec-websocket-protocol", PROTOCOL);
+    }
+
+    let (sender, receiver) = unbounded();
+    actix_rt::spawn(serve(db, id, payload, Outbox(sender)));
+    Ok(response.streaming(receiver.map(Ok::<_, Error>)))
+}
+
+async fn serve(db: web::Data<DBState>, id: Identity, mut payload: web::Payload, outbox: Outbox) {
+    let schema = Rc::new(create_subscription_schema());
+    let mut codec = ws::Codec::new();
+    let mut buf = BytesMut::new();
+    let mut subscriptions: HashMap<String, AbortHandle> = HashMap::new();
+
+    'read: while let Some(chunk) = payload.next().await {
+        match chunk {
+            Ok(chunk) => buf.extend_from_slice(&chunk),
+            Err(_) => break,
+        }
+        loop {
+            let frame = match codec.decode(&mut buf) {
+                Ok(Some(frame)) => frame,
+                Ok(None) => break,
+                Err(e) => {
+                    info!("closing graphql websocket: {}", e);
+                    break 'read;
+                },
+            };
+            match frame {
+                Frame::Text(text) => {
+                    let message = match serde_json::from_slice::<ClientMessage>(&text) {
+                        Ok(message) => message,
+                        Err(_) => {
+                            outbox.send_json(json!({ "type": "connection_error", "payload": { "message": "Malformed message." } }));
+                            continue;
+                        },
+                    };
+                    match message.kind.as_str() {
+                        "connection_init" => {
+                            outbox.send_json(json!({ "type": "connection_ack" }));
+                            outbox.send_json(json!({ "type": "ka" }));
+                        },
+                        "start" => {
+                            let sub_id = message.id.unwrap_or_default();
+                            if subscriptions.contains_key(&sub_id) {
+                                outbox.send_error(&sub_id, "Subscription id is taken.");
+                                continue;
+                            }
+                            let start = match message.payload.map(serde_json::from_value::<StartPayload>) {
+                                Some(Ok(start)) => start,
+                                _ => {
+                                    outbox.send_error(&sub_id, "Malformed subscription.");
+                                    continue;
+                                },
+                            };
+                            let handle = start_subscription(
+                                db.clone(),
+                                id.clone(),
+                                schema.clone(),
+                                sub_id.clone(),
+                                start,
+                                outbox.clone(),
+                            ).await;
+                            if let Some(handle) = handle {
+                                subscriptions.insert(sub_id, handle);
+                            }
+                        },
+                        "stop" => {
+                            let sub_id = message.id.unwrap_or_default();
+                            if let Some(handle) = subscriptions.remove(&sub_id) {
+                                handle.abort();
+                                outbox.send_json(json!({ "type": "complete", "id": sub_id }));
+                            }
+                        },
+                        "connection_terminate" => {
+                            outbox.send(Message::Close(None));
+                            break 'read;
+                        },
+                        _ => {
+                            outbox.send_json(json!({ "type": "connection_error", "payload": { "message": "Unknown message type." } }));
+                        },
+                    }
+                },
+                Frame::Ping(bytes) => outbox.send(Message::Pong(bytes)),
+                Frame::Close(reason) => {
+                    outbox.send(Message::Close(reason));
+                    break 'read;
+                },
+                Frame::Binary(_) | Frame::Continuation(_) | Frame::Pong(_) => (),
+            }
+        }
+    }
+
+    for (_, handle) in subscriptions {
+        handle.abort();
+    }
+}
+
+/// Runs the subscription for each event of the feed it asked for, `None` if it can't be served.
+async fn start_subscription(
+    db: web::Data<DBState>,
+    id: Identity,
+    schema: Rc<SubscriptionSchema>,
+    sub_id: String,
+    start: StartPayload,
+    outbox: Outbox,
+) -> Option<AbortHandle> {
+    let query = match as_query(&start.query) {
+        Ok(query) => query,
+        Err(msg) => {
+            outbox.send_error(&sub_id, &msg);
+            return None;
+        },
+    };
+    let request = GraphQLRequest::new(query, start.operation_name, start.variables);
+
+    let context = SubscriptionContext::new(None);
+    let response = request.execute(&schema, &context);
+    if !response.is_ok() {
+        outbox.send_json(json!({ "type": "error", "id": sub_id, "payload": response }));
+        return None;
+    }
+    let mut requests = context.requests.into_inner();
+    if requests.len() != 1 {
+        outbox.send_error(&sub_id, "A subscription has exactly one field.");
+        return None;
+    }
+
+    let events: Result<BoxStream<'static, FeedEvent>, ServiceError> = match requests.remove(0) {
+        FeedRequest::StatusChanges { region } => watch_status_feed_service(
+            db,
+            WatchStatusFeedMessage { region: region },
+            id,
+        ).await.map(|changes| changes.map(FeedEvent::StatusChange).boxed()),
+        FeedRequest::JudgeProgress { status_id } => watch_progress_service(
+            db,
+            WatchProgressMessage { id: status_id },
+        ).await.map(|events| events.map(FeedEvent::Progress).boxed()),
+    };
+    let mut events = match events {
+        Ok(events) => events,
+        Err(e) => {
+            outbox.send_service_error(&sub_id, e);
+            return None;
+        },
+    };
+
+    let (task, handle) = abortable(async move {
+        while let Some(event) = events.next().await {
+            let context = SubscriptionContext::new(Some(event));
+            let response = request.execute(&schema, &context);
+            outbox.send_json(json!({ "type": "data", "id": sub_id, "payload": response }));
+        }
+        outbox.send_json(json!({ "type": "complete", "id": sub_id }));
+    });
+    actix_rt::spawn(async move { let _ = task.await; });
+    Some(handle)
+}
@@ -0,0 +1,179 @@
+use std::ffi::CString;
+use std::fs;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{ Path, PathBuf };
+use std::ptr;
+use crate::statics::{ LOCAL_JUDGE_INSECURE, LOCAL_JUDGE_RUN_UID, LOCAL_JUDGE_RUN_GID, LOCAL_JUDGE_VISIBLE_PATHS };
+
+/// The empty directory a jail's root is mounted on, inside the work directory.
+const ROOT_DIR: &str = ".root";
+const DEVICES: [&str; 4] = ["/dev/null", "/dev/zero", "/dev/random", "/dev/urandom"];
+
+/// Refuses to judge untrusted code with nothing between it and the server.
+pub fn check_jail() -> Result<(), String> {
+    if *LOCAL_JUDGE_INSECURE {
+        warn!("LOCAL_JUDGE_INSECURE is set, submissions run unsandboxed as the server user");
+        return Ok(());
+    }
+    if unsafe { libc::geteuid() } != 0 {
+        return Err("The local judge backend needs root to mount the sandbox and drop to LOCAL_JUDGE_RUN_UID, \
+            set LOCAL_JUDGE_INSECURE=1 to run it unsandboxed for development.".to_owned());
+    }
+    if cfg!(not(target_arch = "x86_64")) {
+        return Err("The seccomp rules of the local judge backend are only implemented for x86_64, \
+            set LOCAL_JUDGE_INSECURE=1 to run it unsandboxed for development.".to_owned());
+    }
+    if *LOCAL_JUDGE_RUN_UID == 0 || *LOCAL_JUDGE_RUN_GID == 0 {
+        return Err("LOCAL_JUDGE_RUN_UID and LOCAL_JUDGE_RUN_GID must not be root.".to_owned());
+    }
+    Ok(())
+}
+
+/// Lets the user submissions run as write into the work directory, for compilers.
+pub fn hand_over(work_dir: &Path) -> Result<(), String> {
+    if *LOCAL_JUDGE_INSECURE {
+        return Ok(());
+    }
+    let path = c_path(work_dir)?;
+    if unsafe { libc::chown(path.as_ptr(), *LOCAL_JUDGE_RUN_UID, *LOCAL_JUDGE_RUN_GID) } != 0 {
+        return Err(format!("Error handing over {:?}: {}", work_dir, io::Error::last_os_error()));
+    }
+    let root = work_dir.join(ROOT_DIR);
+    fs::create_dir_all(&root).map_err(|e| format!("Error creating {:?}: {}", root, e))
+}
+
+struct Bind {
+    source: CString,
+    target: CString,
+    is_file: bool,
+    /// submounts of the work directory include the jail itself
+    recursive: bool,
+    read_only: bool,
+}
+
+/// A private mount namespace where only the visible paths and the work directory exist,
+/// entered by an unprivileged user. Everything is allocated before the fork,
+/// `enter` only makes system calls.
+pub struct Jail {
+    root: CString,
+    work_dir: CString,
+    /// created inside the root before anything is mounted on them, parents first
+    dirs: Vec<CString>,
+    binds: Vec<Bind>,
+    uid: libc::uid_t,
+    gid: libc::gid_t,
+}
+
+fn c_path(path: &Path) -> Result<CString, String> {
+    CString::new(path.as_os_str().as_bytes()).map_err(|_| format!("Path {:?} has a nul byte.", path))
+}
+
+impl Jail {
+    /// `None` if sandboxing is turned off.
+    pub fn new(work_dir: &Path, writable: bool) -> Result<Option<Jail>, String> {
+        if *LOCAL_JUDGE_INSECURE {
+            return Ok(None);
+        }
+        let root = work_dir.join(ROOT_DIR);
+        let mut sources: Vec<(PathBuf, bool, bool)> = LOCAL_JUDGE_VISIBLE_PATHS.iter()
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute() && path.is_dir())
+            .map(|path| (path, false, true))
+            .collect();
+        sources.extend(DEVICES.iter().map(PathBuf::from).filter(|path| path.exists()).map(|path| (path, true, false)));
+        sources.push((work_dir.to_owned(), false, !writable));
+        let work_dir_target = root.join(work_dir.strip_prefix("/").unwrap_or(work_dir));
+
+        let mut dirs: Vec<PathBuf> = Vec::new();
+        let mut binds = Vec::new();
+        for (source, is_file, read_only) in sources {
+            let target = root.join(source.strip_prefix("/").unwrap_or(&source));
+            let parents: Vec<&Path> = target.ancestors().skip(if is_file { 1 } else { 0 })
+                .take_while(|dir| *dir != root)
+                .collect();
+            for dir in parents.into_iter().rev() {
+                if !dirs.iter().any(|known| known == dir) {
+                    dirs.push(dir.to_owned());
+                }
+            }
+            binds.push(Bind {
+                source: c_path(&source)?,
+                target: c_path(&target)?,
+                is_file: is_file,
+                recursive: !is_file && target != work_dir_target,
+                read_only: read_only,
+            });
+        }
+
+        Ok(Some(Jail {
+            root: c_path(&root)?,
+            work_dir: c_path(work_dir)?,
+            dirs: dirs.iter().map(|dir| c_path(dir)).collect::<Result<Vec<CString>, String>>()?,
+            binds: binds,
+            uid: *LOCAL_JUDGE_RUN_UID,
+            gid: *LOCAL_JUDGE_RUN_GID,
+        }))
+    }
+
+    /// Moves the calling process into the jail, only to be called between fork and exec.
+    pub fn enter(&self) -> io::Result<()> {
+        fn check(ret: libc::c_int) -> io::Result<()> {
+            if ret != 0 { Err(io::Error::last_os_error()) } else { Ok(()) }
+        }
+        let none = ptr::null::<libc::c_char>();
+        unsafe {
+            check(libc::unshare(libc::CLONE_NEWNS))?;
+            // nothing mounted here leaks back to the host
+            check(libc::mount(none, b"/\0".as_ptr() as _, none, libc::MS_REC | libc::MS_PRIVATE, ptr::null()))?;
+            check(libc::mount(
+                b"tmpfs\0".as_ptr() as _,
+                self.root.as_ptr(),
+                b"tmpfs\0".as_ptr() as _,
+                libc::MS_NOSUID | libc::MS_NODEV,
+                b"size=1m,mode=755\0".as_ptr() as _,
+            ))?;
+            for dir in &self.dirs {
+                if libc::mkdir(dir.as_ptr(), 0o755) != 0 {
+                    let e = io::Error::last_os_error();
+                    if e.raw_os_error() != Some(libc::EEXIST) {
+                        return Err(e);
+                    }
+                }
+            }
+            for bind in &self.binds {
+                if bind.is_file {
+                    let fd = libc::open(bind.target.as_ptr(), libc::O_CREAT | libc::O_WRONLY, 0o644);
+                    if fd < 0 {
+                        return Err(io::Error::last_os_error());
+                    }
+                    libc::close(fd);
+                }
+                let flags = if bind.recursive { libc::MS_BIND | libc::MS_REC } else { libc::MS_BIND };
+                check(libc::mount(bind.source.as_ptr(), bind.target.as_ptr(), none, flags, ptr::null()))?;
+                if bind.read_only {
+                    check(libc::mount(
+                        none,
+                        bind.target.as_ptr(),
+                        none,
+                        libc::MS_REMOUNT | libc::MS_BIND | libc::MS_RDONLY | libc::MS_NOSUID,
+                        ptr::null(),
+                    ))?;
+                }
+            }
+            check(libc::mount(
+                none,
+                self.root.as_ptr(),
+                none,
+                libc::MS_REMOUNT | libc::MS_RDONLY | libc::MS_NOSUID | libc::MS_NODEV,
+                ptr::null(),
+            ))?;
+            check(libc::chroot(self.root.as_ptr()))?;
+            check(libc::chdir(self.work_dir.as_ptr()))?;
+            check(libc::setgroups(0, ptr::null()))?;
+            check(libc::setgid(self.gid))?;
+            check(libc::setuid(self.uid))?;
+        }
+        Ok(())
+    }
+}
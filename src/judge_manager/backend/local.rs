@@ -0,0 +1,666 @@
+use actix_web::{ web, error::BlockingError };
+use futures::future::LocalBoxFuture;
+use std::fs;
+use std::path::{ Path, PathBuf };
+use std::process::Command;
+use std::sync::atomic::{ AtomicI32, Ordering };
+use uuid::Uuid;
+use crate::statics::{ LOCAL_JUDGE_MAX_TASKS, LOCAL_JUDGE_WORK_DIR, JUDGE_PROGRESS };
+use crate::judge_manager::utils::progress::ProgressEvent;
+use crate::judge_server::model::JudgeSetting;
+use crate::judge_server::config::SpjCompileConfig;
+use crate::judge_manager::model::JudgeResponse;
+use crate::status::model::{ JudgeResult, JudgeResultData, ErrResult };
+use crate::utils::encryption::encode::get_stripped_md5_output;
+use crate::test_case::utils::archive::{ VERSION_FILE, get_archive_path };
+use super::{ JudgeBackend, JudgeSlot };
+use super::jail::hand_over;
+use super::sandbox::{ run, run_interactive, Confinement, Party, RunLimits, RunOutcome, RunResult };
+
+const LOCAL_SERVER_URL: &str = "local";
+// same codes as `result_mapper` and `err_mapper`
+const PRESENTATION_ERROR: i32 = -2;
+const WRONG_ANSWER: i32 = -1;
+const SUCCESS: i32 = 0;
+const SYSTEM_ERROR: i32 = 5;
+const SPJ_ERROR: i32 = -11;
+const INTERACTOR_ERROR: i32 = -12;
+const INTERACTOR_REJECTED: i32 = -13;
+// exit codes of testlib checkers and interactors
+const TESTLIB_WA: i32 = 1;
+const TESTLIB_PE: i32 = 2;
+const TESTLIB_POINTS: i32 = 7;
+
+/// Judges in child processes of this server, so no judge server is needed.
+pub struct LocalBackend {
+    running_tasks: AtomicI32,
+}
+
+impl LocalBackend {
+    pub fn new() -> Self {
+        LocalBackend { running_tasks: AtomicI32::new(0) }
+    }
+}
+
+impl JudgeBackend for LocalBackend {
+    fn name(&self) -> &'static str { "local" }
+
+    fn supports_interactive(&self) -> bool { true }
+
+    fn acquire(&self) -> Option<JudgeSlot> {
+        let mut cur = self.running_tasks.load(Ordering::SeqCst);
+        loop {
+            if cur >= *LOCAL_JUDGE_MAX_TASKS { return None; }
+            match self.running_tasks.compare_exchange(cur, cur + 1, Ordering::SeqCst, Ordering::SeqCst) {
+                Ok(_) => break,
+                Err(actual) => { cur = actual; },
+            }
+        }
+        Some(JudgeSlot {
+            server_url: LOCAL_SERVER_URL.to_owned(),
+            server_token: String::new(),
+            host_name: "localhost".to_owned(),
+        })
+    }
+
+    fn release(&self, _server_url: &str) {
+        self.running_tasks.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    fn judge(&self, task_id: Uuid, _slot: JudgeSlot, judge_setting: String) -> LocalBoxFuture<'static, Result<JudgeResponse, String>> {
+        Box::pin(async move {
+            match web::block(move || judge_locally(task_id, judge_setting)).await {
+                Ok(response) => Ok(response),
+                Err(BlockingError::Error(msg)) => Err(msg),
+                Err(BlockingError::Canceled) => Err("Local judge was canceled.".to_owned()),
+            }
+        })
+    }
+}
+
+struct LocalTestCase {
+    name: String,
+    input_path: PathBuf,
+    /// the expected output, handed to checkers as the answer
+    answer_path: Option<PathBuf>,
+    stripped_output_md5: Option<String>,
+}
+
+fn fill_template(template: &str, values: &[(&str, String)]) -> String {
+    values.iter().fold(template.to_owned(), |command, (key, value)| command.replace(key, value))
+}
+
+fn path_string(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+fn read_lossy(path: &Path) -> String {
+    fs::read(path).map(|bytes| String::from_utf8_lossy(&bytes).into_owned()).unwrap_or_default()
+}
+
+fn compile_env(run_env: &[String], work_dir: &Path) -> Vec<String> {
+    let mut env = run_env.to_vec();
+    env.push(format!("PATH={}", std::env::var("PATH").unwrap_or("/usr/local/bin:/usr/bin:/bin".to_owned())));
+    // there is no /tmp in the sandbox
+    env.push(format!("TMPDIR={}", path_string(work_dir)));
+    env
+}
+
+pub fn judge_locally(task_id: Uuid, judge_setting: String) -> Result<JudgeResponse, String> {
+    let setting: JudgeSetting = serde_json::from_str(&judge_setting)
+        .map_err(|e| format!("Malformed judge setting: {}", e))?;
+
+    let work_dir = Path::new(&*LOCAL_JUDGE_WORK_DIR).join(Uuid::new_v4().to_string());
+    fs::create_dir_all(&work_dir).map_err(|e| format!("Error creating {:?}: {}", work_dir, e))?;
+    let result = fs::canonicalize(&work_dir)
+        .map_err(|e| format!("Error resolving {:?}: {}", work_dir, e))
+        .and_then(|work_dir| hand_over(&work_dir).map(|_| work_dir))
+        .and_then(|work_dir| judge_in(task_id, &setting, &work_dir));
+    if let Err(e) = fs::remove_dir_all(&work_dir) {
+        info!("Error removing {:?}: {}", work_dir, e);
+    }
+
+    result
+}
+
+fn judge_in(task_id: Uuid, setting: &JudgeSetting, work_dir: &Path) -> Result<JudgeResponse, String> {
+    let lang = &setting.language_config;
+    JUDGE_PROGRESS.publish(task_id, ProgressEvent::Compiling);
+
+    // compile
+    let src_path = work_dir.join(&lang.compile.src_name);
+    let exe_path = work_dir.join(&lang.compile.exe_name);
+    fs::write(&src_path, &setting.src).map_err(|e| format!("Error writing source: {}", e))?;
+    let compile_command = fill_template(&lang.compile.compile_command, &[
+        ("{src_path}", path_string(&src_path)),
+        ("{exe_path}", path_string(&exe_path)),
+        ("{exe_dir}", path_string(work_dir)),
+    ]);
+    if let Some(msg) = compile(&compile_command, &lang.run.env, work_dir, RunLimits {
+        max_cpu_time: lang.compile.max_cpu_time,
+        max_real_time: lang.compile.max_real_time,
+        max_memory: lang.compile.max_memory as i64,
+        memory_limit_check_only: false,
+    }, &Confinement {
+        isolated: true,
+        writable: true,
+        seccomp_rule: None,
+    })? {
+        // contestants don't need to see where they were compiled
+        return Ok(JudgeResponse::Failed(ErrResult {
+            err: Some("CompileError".to_owned()),
+            data: msg.replace(&(path_string(work_dir) + "/"), ""),
+        }));
+    }
+
+    // compile spj
+    let spj_exe_path = match (&setting.spj_src, &setting.spj_compile_config, &setting.spj_config) {
+        (Some(spj_src), Some(spj_compile_config), Some(_)) => {
+            let spj_version = setting.spj_version.clone().unwrap_or_default();
+            let (spj_exe_path, compile_msg) = compile_spj(spj_src, spj_compile_config, &spj_version, work_dir)?;
+            if let Some(msg) = compile_msg {
+                return Ok(JudgeResponse::Failed(ErrResult {
+                    err: Some("SPJCompileError".to_owned()),
+                    data: msg,
+                }));
+            }
+            Some(spj_exe_path)
+        },
+        _ => None,
+    };
+
+    // compile interactor
+    let interactor_exe_path = match (&setting.interactor_src, &setting.interactor_compile_config, &setting.interactor_config) {
+        (Some(interactor_src), Some(interactor_compile_config), Some(_)) => {
+            let interactor_version = setting.interactor_version.clone().unwrap_or_default();
+            let (interactor_exe_path, compile_msg) = compile_spj(
+                interactor_src,
+                interactor_compile_config,
+                &format!("interactor-{}", interactor_version),
+                work_dir,
+            )?;
+            if let Some(msg) = compile_msg {
+                return Ok(JudgeResponse::Failed(ErrResult {
+                    err: Some("InteractorCompileError".to_owned()),
+                    data: msg,
+                }));
+            }
+            Some(interactor_exe_path)
+        },
+        _ => None,
+    };
+
+    let run_command = fill_template(&lang.run.command, &[
+        ("{exe_path}", path_string(&exe_path)),
+        ("{exe_dir}", path_string(work_dir)),
+        ("{max_memory}", (setting.max_memory / 1024).to_string()),
+    ]);
+    let run_limits = RunLimits {
+        max_cpu_time: setting.max_cpu_time,
+        max_real_time: setting.max_cpu_time * 3,
+        max_memory: setting.max_memory as i64,
+        memory_limit_check_only: lang.run.memory_limit_check_only == 1,
+    };
+    let run_confinement = Confinement {
+        isolated: true,
+        writable: false,
+        seccomp_rule: lang.run.seccomp_rule.clone(),
+    };
+
+    let test_cases = load_test_cases(setting, work_dir)?;
+    let total = test_cases.len();
+    let mut data = Vec::new();
+    for (index, test_case) in test_cases.into_iter().enumerate() {
+        JUDGE_PROGRESS.publish(task_id, ProgressEvent::Running {
+            test_case: test_case.name.clone(),
+            index: index as i32 + 1,
+            total: total as i32,
+        });
+        let user_output_path = work_dir.join(format!("{}.out", test_case.name));
+        let mut score = None;
+        let (outcome, mut result, mut error) = match (&interactor_exe_path, &setting.interactor_config) {
+            // the interactor writes its report where the program's output would be
+            (Some(interactor_exe_path), Some(interactor_config)) => run_with_interactor(
+                &run_command,
+                &lang.run.env,
+                &interactor_config.command,
+                interactor_exe_path,
+                &test_case,
+                &user_output_path,
+                work_dir,
+                &run_limits,
+                &run_confinement,
+            )?,
+            _ => {
+                let outcome = run(
+                    &run_command,
+                    &lang.run.env,
+                    work_dir,
+                    Some(&test_case.input_path),
+                    &user_output_path,
+                    &work_dir.join("user.err"),
+                    &run_limits,
+                    &run_confinement,
+                )?;
+                let result = outcome.result as i32;
+                (outcome, result, 0)
+            },
+        };
+
+        let user_output = read_lossy(&user_output_path);
+        let output_md5 = get_stripped_md5_output(&user_output);
+        if outcome.result == RunResult::Success && interactor_exe_path.is_none() {
+            match (&spj_exe_path, &setting.spj_config) {
+                (Some(spj_exe_path), Some(spj_config)) => {
+                    let spj_outcome = run_spj(
+                        &spj_config.command,
+                        spj_exe_path,
+                        &test_case,
+                        &user_output_path,
+                        work_dir,
+                        &run_limits,
+                    )?;
+                    let (spj_result, spj_error, spj_score) = get_checker_verdict(&spj_outcome, work_dir);
+                    result = spj_result;
+                    error = spj_error;
+                    score = spj_score;
+                },
+                _ => {
+                    if test_case.stripped_output_md5.as_ref() != Some(&output_md5) {
+                        result = WRONG_ANSWER;
+                    }
+                },
+            }
+        }
+
+        data.push(JudgeResultData {
+            cpu_time: outcome.cpu_time,
+            real_time: outcome.real_time,
+            memory: outcome.memory,
+            signal: outcome.signal,
+            exit_code: outcome.exit_code,
+            error: error,
+            result: result,
+            test_case: test_case.name,
+            output_md5: Some(output_md5),
+            output: if setting.output { Some(user_output) } else { None },
+            score: score,
+            stderr: if setting.stderr { Some(read_lossy(&work_dir.join("user.err"))) } else { None },
+        });
+        JUDGE_PROGRESS.publish(task_id, ProgressEvent::test_case_finished(&data[index], index, total));
+    }
+
+    Ok(JudgeResponse::Finished(JudgeResult {
+        err: None,
+        data: data,
+        subtasks: None,
+    }))
+}
+
+/// Returns where the special judge is compiled to, and the compiler's message if it failed.
+fn compile_spj(
+    spj_src: &str,
+    spj_compile_config: &SpjCompileConfig,
+    spj_version: &str,
+    work_dir: &Path,
+) -> Result<(PathBuf, Option<String>), String> {
+    let spj_src_path = work_dir.join(spj_compile_config.src_name.replace("{spj_version}", spj_version));
+    let spj_exe_path = work_dir.join(spj_compile_config.exe_name.replace("{spj_version}", spj_version));
+    fs::write(&spj_src_path, spj_src).map_err(|e| format!("Error writing spj source: {}", e))?;
+    let spj_compile_command = fill_template(&spj_compile_config.compile_command, &[
+        ("{src_path}", path_string(&spj_src_path)),
+        ("{exe_path}", path_string(&spj_exe_path)),
+        ("{exe_dir}", path_string(work_dir)),
+    ]);
+    let compile_msg = compile(&spj_compile_command, &[], work_dir, RunLimits {
+        max_cpu_time: spj_compile_config.max_cpu_time,
+        max_real_time: spj_compile_config.max_real_time,
+        max_memory: spj_compile_config.max_memory as i64,
+        memory_limit_check_only: false,
+    }, &Confinement::default())?;
+
+    Ok((spj_exe_path, compile_msg))
+}
+
+/// Compiles a special judge in a scratch directory, to reject broken ones when they are uploaded.
+pub fn check_spj(spj_src: &str, spj_compile_config: &SpjCompileConfig, spj_version: &str) -> Result<(), String> {
+    let work_dir = Path::new(&*LOCAL_JUDGE_WORK_DIR).join(Uuid::new_v4().to_string());
+    fs::create_dir_all(&work_dir).map_err(|e| format!("Error creating {:?}: {}", work_dir, e))?;
+    let result = fs::canonicalize(&work_dir)
+        .map_err(|e| format!("Error resolving {:?}: {}", work_dir, e))
+        .and_then(|work_dir| compile_spj(spj_src, spj_compile_config, spj_version, &work_dir));
+    if let Err(e) = fs::remove_dir_all(&work_dir) {
+        info!("Error removing {:?}: {}", work_dir, e);
+    }
+
+    match result? {
+        (_, Some(msg)) => Err(format!("Special judge doesn't compile:\n{}", msg)),
+        (_, None) => Ok(()),
+    }
+}
+
+/// Returns the compiler's message if the compilation failed.
+fn compile(
+    command: &str,
+    run_env: &[String],
+    work_dir: &Path,
+    limits: RunLimits,
+    confinement: &Confinement,
+) -> Result<Option<String>, String> {
+    let compiler_output_path = work_dir.join("compiler.out");
+    let compiler_error_path = work_dir.join("compiler.err");
+    let outcome = run(
+        command,
+        &compile_env(run_env, work_dir),
+        work_dir,
+        None,
+        &compiler_output_path,
+        &compiler_error_path,
+        &limits,
+        confinement,
+    )?;
+
+    if outcome.result == RunResult::Success {
+        Ok(None)
+    } else {
+        let mut msg = read_lossy(&compiler_error_path);
+        msg.push_str(&read_lossy(&compiler_output_path));
+        if msg.is_empty() {
+            msg = format!("{:?}", outcome.result);
+        }
+        Ok(Some(msg))
+    }
+}
+
+/// The templates are shared with QDUOJ judge servers, which don't know `{ans_file_path}`,
+/// so the answer testlib expects as third argument is appended unless the template places it.
+fn fill_checker_template(command: &str, exe_path: &Path, test_case: &LocalTestCase, user_output_path: &Path) -> String {
+    let command = if command.contains("{ans_file_path}") {
+        command.to_owned()
+    } else {
+        format!("{} {{ans_file_path}}", command)
+    };
+    fill_template(&command, &[
+        ("{exe_path}", path_string(exe_path)),
+        ("{in_file_path}", path_string(&test_case.input_path)),
+        ("{user_out_file_path}", path_string(user_output_path)),
+        ("{ans_file_path}", answer_path_string(test_case)),
+    ])
+}
+
+fn run_spj(
+    command: &str,
+    spj_exe_path: &Path,
+    test_case: &LocalTestCase,
+    user_output_path: &Path,
+    work_dir: &Path,
+    run_limits: &RunLimits,
+) -> Result<RunOutcome, String> {
+    let spj_command = fill_checker_template(command, spj_exe_path, test_case, user_output_path);
+    run(
+        &spj_command,
+        &[],
+        work_dir,
+        None,
+        &work_dir.join("spj.out"),
+        &work_dir.join("spj.err"),
+        &RunLimits {
+            max_cpu_time: run_limits.max_cpu_time * 3,
+            max_real_time: run_limits.max_real_time * 3,
+            max_memory: 1024 * 1024 * 1024,
+            memory_limit_check_only: false,
+        },
+        &Confinement::default(),
+    )
+}
+
+/// Returns the program's outcome with the result and error codes. Errors tell
+/// a broken interactor and one that rejected the program apart from the program's own failures.
+fn run_with_interactor(
+    run_command: &str,
+    run_env: &[String],
+    interactor_command: &str,
+    interactor_exe_path: &Path,
+    test_case: &LocalTestCase,
+    interactor_output_path: &Path,
+    work_dir: &Path,
+    run_limits: &RunLimits,
+    run_confinement: &Confinement,
+) -> Result<(RunOutcome, i32, i32), String> {
+    let interactor_command = fill_checker_template(interactor_command, interactor_exe_path, test_case, interactor_output_path);
+    let interactor_limits = RunLimits {
+        max_cpu_time: run_limits.max_cpu_time * 3,
+        max_real_time: run_limits.max_real_time * 3,
+        max_memory: 1024 * 1024 * 1024,
+        memory_limit_check_only: false,
+    };
+    let (outcome, interactor_outcome) = run_interactive(
+        &Party {
+            command: run_command,
+            env: run_env,
+            error: &work_dir.join("user.err"),
+            limits: run_limits,
+            confinement: run_confinement,
+        },
+        &Party {
+            command: &interactor_command,
+            env: &[],
+            error: &work_dir.join("interactor.err"),
+            limits: &interactor_limits,
+            confinement: &Confinement::default(),
+        },
+        work_dir,
+    )?;
+
+    let rejection = match (interactor_outcome.result, interactor_outcome.signal, interactor_outcome.exit_code) {
+        (RunResult::RuntimeError, 0, TESTLIB_WA) => Some(WRONG_ANSWER),
+        (RunResult::RuntimeError, 0, TESTLIB_PE) => Some(PRESENTATION_ERROR),
+        _ => None,
+    };
+    let (result, error) = match (outcome.result, rejection) {
+        // the interactor only sees the pipe close when the program runs out of resources
+        (RunResult::CpuTimeLimitExceeded, _)
+        | (RunResult::RealTimeLimitExceeded, _)
+        | (RunResult::MemoryLimitExceeded, _) => (outcome.result as i32, 0),
+        // a program writing to an interactor that already gave up dies of SIGPIPE
+        (_, Some(rejection)) => (rejection, INTERACTOR_REJECTED),
+        _ if interactor_outcome.result != RunResult::Success => (SYSTEM_ERROR, INTERACTOR_ERROR),
+        _ => (outcome.result as i32, 0),
+    };
+
+    Ok((outcome, result, error))
+}
+
+/// Reads a checker's verdict the way testlib reports it: by the exit code,
+/// with `points <share>` written out for partial scores. The share is of one test case, from 0 to 1.
+fn get_checker_verdict(spj_outcome: &RunOutcome, work_dir: &Path) -> (i32, i32, Option<f64>) {
+    match (spj_outcome.result, spj_outcome.signal, spj_outcome.exit_code) {
+        (RunResult::Success, _, _) => (SUCCESS, 0, None),
+        (RunResult::RuntimeError, 0, TESTLIB_WA) => (WRONG_ANSWER, 0, None),
+        (RunResult::RuntimeError, 0, TESTLIB_PE) => (PRESENTATION_ERROR, 0, None),
+        (RunResult::RuntimeError, 0, TESTLIB_POINTS) => {
+            let report = read_lossy(&work_dir.join("spj.err")) + "\n" + &read_lossy(&work_dir.join("spj.out"));
+            match parse_points(&report) {
+                Some(points) if points >= 1.0 => (SUCCESS, 0, Some(1.0)),
+                Some(points) => (WRONG_ANSWER, 0, Some(points.max(0.0))),
+                None => (SYSTEM_ERROR, SPJ_ERROR, None),
+            }
+        },
+        _ => (SYSTEM_ERROR, SPJ_ERROR, None),
+    }
+}
+
+fn parse_points(report: &str) -> Option<f64> {
+    report.lines()
+        .filter_map(|line| {
+            let mut words = line.split_whitespace();
+            match words.next() {
+                Some("points") => words.next()?.parse::<f64>().ok(),
+                _ => None,
+            }
+        })
+        .find(|points| points.is_finite())
+}
+
+fn answer_path_string(test_case: &LocalTestCase) -> String {
+    test_case.answer_path.as_ref().map(|path| path_string(path)).unwrap_or("/dev/null".to_owned())
+}
+
+/// Unpacks the archive of an older version into the work directory.
+fn unpack_archive(test_case_id: &str, version: &str, work_dir: &Path) -> Result<PathBuf, String> {
+    let archive_path = get_archive_path(version)
+        .ok_or(format!("Test case {} has no archive of version {}.", test_case_id, version))?;
+    let dir = work_dir.join("test_case");
+    fs::create_dir_all(&dir).map_err(|e| format!("Error creating {:?}: {}", dir, e))?;
+    let status = Command::new("tar")
+        .arg("-xzf").arg(&archive_path)
+        .arg("-C").arg(&dir)
+        .status()
+        .map_err(|e| format!("Failed to run tar: {}", e))?;
+    if !status.success() {
+        return Err(format!("Error unpacking test case {} of version {}.", test_case_id, version));
+    }
+    Ok(dir)
+}
+
+fn load_test_cases(setting: &JudgeSetting, work_dir: &Path) -> Result<Vec<LocalTestCase>, String> {
+    if let Some(test_case_id) = &setting.test_case_id {
+        let mut test_case_dir = Path::new("data/test_case").join(test_case_id);
+        // the data may have been replaced since the task was queued, its archive is kept
+        if let Some(version) = &setting.test_case_version {
+            let local_version = fs::read_to_string(test_case_dir.join(VERSION_FILE)).unwrap_or_default();
+            if local_version.trim() != version {
+                test_case_dir = unpack_archive(test_case_id, version, work_dir)?;
+            }
+        }
+        let info: serde_json::Value = serde_json::from_slice(
+            &fs::read(test_case_dir.join("info")).map_err(|e| format!("Error reading test case info: {}", e))?
+        ).map_err(|e| format!("Malformed test case info: {}", e))?;
+
+        let mut test_cases = info["test_cases"].as_object()
+            .ok_or("Malformed test case info: no test_cases.".to_owned())?
+            .iter()
+            .map(|(name, test_case)| Ok(LocalTestCase {
+                name: name.clone(),
+                input_path: fs::canonicalize(test_case_dir.join(
+                    test_case["input_name"].as_str().ok_or(format!("Test case {} has no input_name.", name))?
+                )).map_err(|e| format!("Error resolving input of test case {}: {}", name, e))?,
+                answer_path: test_case["output_name"].as_str()
+                    .and_then(|output_name| fs::canonicalize(test_case_dir.join(output_name)).ok()),
+                stripped_output_md5: test_case["stripped_output_md5"].as_str().map(|md5| md5.to_owned()),
+            }))
+            .collect::<Result<Vec<LocalTestCase>, String>>()?;
+        test_cases.sort_by_key(|test_case| test_case.name.parse::<i32>().unwrap_or(i32::MAX));
+        return Ok(test_cases);
+    }
+
+    let mut test_cases = Vec::new();
+    for (index, test_case) in setting.test_case.clone().unwrap_or_default().into_iter().enumerate() {
+        let name = (index + 1).to_string();
+        let input_path = work_dir.join(format!("{}.in", name));
+        fs::write(&input_path, &test_case.input).map_err(|e| format!("Error writing input: {}", e))?;
+        let answer_path = work_dir.join(format!("{}.ans", name));
+        fs::write(&answer_path, &test_case.output).map_err(|e| format!("Error writing answer: {}", e))?;
+        test_cases.push(LocalTestCase {
+            name: name,
+            input_path: input_path,
+            answer_path: Some(answer_path),
+            stripped_output_md5: Some(get_stripped_md5_output(&test_case.output)),
+        });
+    }
+    Ok(test_cases)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn c_setting(src: &str, seccomp_rule: Option<&str>, input: &str, output: &str) -> String {
+        serde_json::json!({
+            "language_config": {
+                "compile": {
+                    "src_name": "main.c",
+                    "exe_name": "main",
+                    "max_cpu_time": 3000,
+                    "max_real_time": 10000,
+                    "max_memory": 268435456,
+                    "compile_command": "/usr/bin/gcc -O2 -w {src_path} -o {exe_path}"
+                },
+                "run": {
+                    "command": "{exe_path}",
+                    "seccomp_rule": seccomp_rule,
+                    "env": [],
+                    "memory_limit_check_only": 0
+                }
+            },
+            "src": src,
+            "max_cpu_time": 1000,
+            "max_memory": 134217728,
+            "test_case": [{ "input": input, "output": output }],
+            "output": true
+        }).to_string()
+    }
+
+    fn judge_one(src: &str, seccomp_rule: Option<&str>, input: &str, output: &str) -> JudgeResultData {
+        match judge_locally(Uuid::new_v4(), c_setting(src, seccomp_rule, input, output)) {
+            Ok(JudgeResponse::Finished(mut result)) => result.data.remove(0),
+            Ok(JudgeResponse::Failed(e)) => panic!("{:?}: {}", e.err, e.data),
+            Err(msg) => panic!("{}", msg),
+        }
+    }
+
+    #[test]
+    fn parses_testlib_points() {
+        assert_eq!(parse_points("points 0.25"), Some(0.25));
+        assert_eq!(parse_points("ok\npoints 1 of 4 right\n"), Some(1.0));
+        assert_eq!(parse_points("points nan\npoints 0.5"), Some(0.5));
+        assert_eq!(parse_points("wrong answer"), None);
+        assert_eq!(parse_points("points"), None);
+    }
+
+    // the sandbox needs root and gcc
+    #[test]
+    #[ignore]
+    fn judges_in_the_sandbox() {
+        let data = judge_one(
+            "#include <stdio.h>\nint main() { int a, b; scanf(\"%d %d\", &a, &b); printf(\"%d\\n\", a + b); return 0; }",
+            Some("c_cpp"),
+            "1 2",
+            "3",
+        );
+        assert_eq!(data.result, SUCCESS);
+    }
+
+    #[test]
+    #[ignore]
+    fn hides_the_server_and_drops_privileges() {
+        let server_file = fs::canonicalize("Cargo.toml").unwrap();
+        let src = format!(
+            "#include <stdio.h>\n#include <unistd.h>\nint main() {{ printf(\"%d %s\\n\", (int)getuid(), fopen(\"{}\", \"r\") ? \"seen\" : \"hidden\"); return 0; }}",
+            path_string(&server_file),
+        );
+        // getuid is beyond c_cpp
+        let data = judge_one(&src, None, "", "");
+        assert_eq!(data.output.as_deref().map(str::trim), Some("65534 hidden"));
+    }
+
+    #[test]
+    #[ignore]
+    fn kills_what_the_seccomp_rule_forbids() {
+        let data = judge_one("#include <unistd.h>\nint main() { fork(); return 0; }", Some("c_cpp"), "", "");
+        assert_eq!(data.result, RunResult::RuntimeError as i32);
+        assert_eq!(data.signal, libc::SIGSYS);
+    }
+
+    #[test]
+    #[ignore]
+    fn keeps_programs_from_writing_files() {
+        let data = judge_one(
+            "#include <stdio.h>\nint main() { FILE *f = fopen(\"main\", \"w\"); return f != NULL; }",
+            Some("c_cpp"),
+            "",
+            "",
+        );
+        assert_eq!(data.signal, libc::SIGSYS);
+    }
+}
@@ -0,0 +1,50 @@
+pub mod remote;
+pub mod local;
+pub mod sandbox;
+pub mod jail;
+pub mod seccomp;
+
+use futures::future::LocalBoxFuture;
+use uuid::Uuid;
+use crate::judge_manager::model::JudgeResponse;
+
+/// Where a task is going to be judged, taken by `JudgeBackend::acquire`.
+#[derive(Debug, Clone)]
+pub struct JudgeSlot {
+    pub server_url: String,
+    pub server_token: String,
+    pub host_name: String,
+}
+
+/// Something that can run a judge setting and tell the verdict.
+pub trait JudgeBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+    /// Takes a free slot, `None` if the backend is fully loaded.
+    fn acquire(&self) -> Option<JudgeSlot>;
+    /// Gives back the slot taken for a finished or abandoned task.
+    fn release(&self, server_url: &str);
+    /// Should publish the progress of `task_id` it can tell on the way.
+    /// QDUOJ judge servers ignore interactors and would run the program on the bare input.
+    fn supports_interactive(&self) -> bool { false }
+    fn judge(&self, task_id: Uuid, slot: JudgeSlot, judge_setting: String) -> LocalBoxFuture<'static, Result<JudgeResponse, String>>;
+}
+
+pub fn get_judge_backend(name: &str) -> Result<Box<dyn JudgeBackend>, String> {
+    match name {
+        "remote" => Ok(Box::new(remote::RemoteBackend)),
+        "local" => Ok(Box::new(local::LocalBackend::new())),
+        _ => Err(format!("Unknown judge backend \"{}\", expected remote or local.", name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_local_backend_judges_interactive_problems() {
+        assert!(!get_judge_backend("remote").unwrap().supports_interactive());
+        assert!(get_judge_backend("local").unwrap().supports_interactive());
+        assert!(get_judge_backend("docker").is_err());
+    }
+}
@@ -0,0 +1,60 @@
+use futures::future::LocalBoxFuture;
+use uuid::Uuid;
+use crate::statics::{ JUDGE_SERVER_INFOS, JUDGE_PROGRESS };
+use crate::judge_manager::model::JudgeResponse;
+use crate::judge_server::model::JudgeSetting;
+use crate::judge_manager::utils::{
+    chooser::choose_judge_server,
+    client::send_judge_request,
+    progress::ProgressEvent,
+};
+use super::{ JudgeBackend, JudgeSlot };
+
+/// Judge servers which have registered themselves with heartbeats.
+pub struct RemoteBackend;
+
+impl JudgeBackend for RemoteBackend {
+    fn name(&self) -> &'static str { "remote" }
+
+    fn acquire(&self) -> Option<JudgeSlot> {
+        let (server_url, server_token) = choose_judge_server()?;
+        let mut lock = JUDGE_SERVER_INFOS.write().unwrap();
+        // the server may have gone away after being chosen
+        let server_info = lock.get_mut(&server_url)?;
+        server_info.task_number += 1;
+        Some(JudgeSlot {
+            server_url: server_url,
+            server_token: server_token,
+            host_name: server_info.hostname.clone(),
+        })
+    }
+
+    fn release(&self, server_url: &str) {
+        let mut lock = JUDGE_SERVER_INFOS.write().unwrap();
+        if let Some(server_info) = lock.get_mut(server_url) {
+            // disabling a server already gave its slots back
+            server_info.task_number = (server_info.task_number - 1).max(0);
+        }
+    }
+
+    fn judge(&self, task_id: Uuid, slot: JudgeSlot, judge_setting: String) -> LocalBoxFuture<'static, Result<JudgeResponse, String>> {
+        Box::pin(async move {
+            // queued before interactive problems were refused, it would be misjudged
+            let is_interactive = serde_json::from_str::<JudgeSetting>(&judge_setting)
+                .map(|setting| setting.interactor_src.is_some())
+                .unwrap_or(false);
+            if is_interactive {
+                return Err("Remote judge servers can't run interactive problems.".to_owned());
+            }
+
+            let response = send_judge_request(slot.server_token, slot.server_url, judge_setting).await;
+            // judge servers only answer once everything is done
+            if let Ok(JudgeResponse::Finished(judge_result)) = &response {
+                for (index, data) in judge_result.data.iter().enumerate() {
+                    JUDGE_PROGRESS.publish(task_id, ProgressEvent::test_case_finished(data, index, judge_result.data.len()));
+                }
+            }
+            response
+        })
+    }
+}
@@ -0,0 +1,426 @@
+use std::ffi::CString;
+use std::fs::File;
+use std::io;
+use std::os::unix::process::CommandExt;
+use std::path::Path;
+use std::process::{ Command, Stdio };
+use std::thread;
+use std::ptr;
+use std::time::{ Duration, Instant };
+use super::jail::Jail;
+use super::seccomp::SeccompFilter;
+
+// anything bigger than this written by the program is treated as a runtime error
+const MAX_OUTPUT_SIZE: u64 = 256 * 1024 * 1024;
+const STACK_SIZE: u64 = 128 * 1024 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct RunLimits {
+    /// in milliseconds
+    pub max_cpu_time: i32,
+    /// in milliseconds
+    pub max_real_time: i32,
+    /// in bytes, -1 means unlimited
+    pub max_memory: i64,
+    /// only compare the peak memory afterwards, for runtimes that reserve a lot of address space
+    pub memory_limit_check_only: bool,
+}
+
+/// Same codes as `result_mapper` expects from a judge server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunResult {
+    Success = 0,
+    CpuTimeLimitExceeded = 1,
+    RealTimeLimitExceeded = 2,
+    MemoryLimitExceeded = 3,
+    RuntimeError = 4,
+}
+
+#[derive(Debug, Clone)]
+pub struct RunOutcome {
+    pub cpu_time: i32,
+    pub real_time: i32,
+    pub memory: i32,
+    pub signal: i32,
+    pub exit_code: i32,
+    pub result: RunResult,
+}
+
+fn set_rlimit(resource: libc::c_int, value: u64) -> io::Result<()> {
+    let limit = libc::rlimit {
+        rlim_cur: value as libc::rlim_t,
+        rlim_max: value as libc::rlim_t,
+    };
+    if unsafe { libc::setrlimit(resource as _, &limit) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+fn split_env(env: &[String]) -> Vec<(String, String)> {
+    env.iter()
+        .filter_map(|pair| {
+            let mut parts = pair.splitn(2, '=');
+            Some((parts.next()?.to_owned(), parts.next()?.to_owned()))
+        })
+        .collect()
+}
+
+/// What keeps an untrusted program away from the server, beyond the rlimits.
+/// Checkers and interactors come from admins and run unconfined.
+#[derive(Debug, Clone, Default)]
+pub struct Confinement {
+    /// in a `Jail` as the unprivileged user
+    pub isolated: bool,
+    /// compilers write into the work directory, programs only read it
+    pub writable: bool,
+    /// only applied to isolated programs
+    pub seccomp_rule: Option<String>,
+}
+
+/// A program executed by hand once its seccomp filter is in place,
+/// since the filter only lets the path allocated here be executed.
+struct Exec {
+    program: CString,
+    _strings: Vec<CString>,
+    argv: Vec<*const libc::c_char>,
+    envp: Vec<*const libc::c_char>,
+    filter: SeccompFilter,
+}
+
+// the pointers are into `program` and `_strings`, and only read by the forked child
+unsafe impl Send for Exec {}
+unsafe impl Sync for Exec {}
+
+fn c_string(value: &str) -> Result<CString, String> {
+    CString::new(value).map_err(|_| format!("\"{}\" has a nul byte.", value))
+}
+
+impl Exec {
+    fn new(parts: &[&str], env: &[String], seccomp_rule: &str) -> Result<Self, String> {
+        if !parts[0].contains('/') {
+            return Err(format!("\"{}\" needs a path to run under a seccomp rule.", parts[0]));
+        }
+        let program = c_string(parts[0])?;
+        let args = parts.iter().map(|arg| c_string(arg)).collect::<Result<Vec<CString>, String>>()?;
+        let vars = env.iter().map(|var| c_string(var)).collect::<Result<Vec<CString>, String>>()?;
+        let argv = args.iter().map(|arg| arg.as_ptr()).chain(std::iter::once(ptr::null())).collect();
+        let envp = vars.iter().map(|var| var.as_ptr()).chain(std::iter::once(ptr::null())).collect();
+        let filter = SeccompFilter::new(seccomp_rule, program.as_ptr())?;
+        Ok(Exec {
+            program: program,
+            _strings: args.into_iter().chain(vars).collect(),
+            argv: argv,
+            envp: envp,
+            filter: filter,
+        })
+    }
+}
+
+/// Builds `command` with rlimits applied, its own process group and its confinement,
+/// leaving stdio to the caller.
+fn prepare(
+    command: &str,
+    env: &[String],
+    work_dir: &Path,
+    limits: &RunLimits,
+    confinement: &Confinement,
+) -> Result<Command, String> {
+    let parts: Vec<&str> = command.split_whitespace().collect();
+    if parts.is_empty() {
+        return Err("Empty command.".to_owned());
+    }
+
+    let cpu_seconds = (limits.max_cpu_time as u64 + 999) / 1000 + 1;
+    let address_space = if limits.max_memory > 0 && !limits.memory_limit_check_only {
+        Some(limits.max_memory as u64 * 2)
+    } else {
+        None
+    };
+    let jail = if confinement.isolated { Jail::new(work_dir, confinement.writable)? } else { None };
+    let exec = match (&confinement.seccomp_rule, &jail) {
+        (Some(seccomp_rule), Some(_)) => Some(Exec::new(&parts, env, seccomp_rule)?),
+        _ => None,
+    };
+
+    let mut cmd = Command::new(parts[0]);
+    cmd.args(&parts[1..])
+        .env_clear()
+        .envs(split_env(env))
+        .current_dir(work_dir);
+    unsafe {
+        cmd.pre_exec(move || {
+            // own process group, so a timeout can take the children down as well
+            if libc::setpgid(0, 0) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            set_rlimit(libc::RLIMIT_CPU as libc::c_int, cpu_seconds)?;
+            set_rlimit(libc::RLIMIT_FSIZE as libc::c_int, MAX_OUTPUT_SIZE)?;
+            set_rlimit(libc::RLIMIT_STACK as libc::c_int, STACK_SIZE)?;
+            set_rlimit(libc::RLIMIT_CORE as libc::c_int, 0)?;
+            if let Some(address_space) = address_space {
+                set_rlimit(libc::RLIMIT_AS as libc::c_int, address_space)?;
+            }
+            if let Some(jail) = &jail {
+                jail.enter()?;
+            }
+            if let Some(exec) = &exec {
+                exec.filter.apply()?;
+                libc::execve(exec.program.as_ptr(), exec.argv.as_ptr(), exec.envp.as_ptr());
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+
+    Ok(cmd)
+}
+
+/// A child that is killed with its whole process group once its wall time since `start_time` runs out.
+struct Watched<'a> {
+    pid: libc::pid_t,
+    command: &'a str,
+    limits: &'a RunLimits,
+    is_killed: bool,
+}
+
+impl<'a> Watched<'a> {
+    fn new(pid: libc::pid_t, command: &'a str, limits: &'a RunLimits) -> Self {
+        Watched { pid: pid, command: command, limits: limits, is_killed: false }
+    }
+
+    /// The outcome once the child exited.
+    fn poll(&mut self, start_time: Instant) -> Result<Option<RunOutcome>, String> {
+        let mut wait_status: libc::c_int = 0;
+        let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+        let ret = unsafe { libc::wait4(self.pid, &mut wait_status, libc::WNOHANG, &mut usage) };
+        if ret == self.pid {
+            return Ok(Some(self.outcome(wait_status, &usage, start_time.elapsed().as_millis() as i32)));
+        }
+        if ret < 0 {
+            return Err(format!("Error waiting for \"{}\": {}", self.command, io::Error::last_os_error()));
+        }
+        if !self.is_killed && start_time.elapsed() > Duration::from_millis(self.limits.max_real_time as u64) {
+            unsafe { libc::kill(-self.pid, libc::SIGKILL); }
+            self.is_killed = true;
+        }
+        Ok(None)
+    }
+
+    /// Kills and reaps a child nobody is going to wait for anymore.
+    fn abandon(&self) {
+        unsafe {
+            libc::kill(-self.pid, libc::SIGKILL);
+            libc::waitpid(self.pid, ptr::null_mut(), 0);
+        }
+    }
+
+    fn outcome(&self, wait_status: libc::c_int, usage: &libc::rusage, real_time: i32) -> RunOutcome {
+        let limits = self.limits;
+        let cpu_time = (usage.ru_utime.tv_sec * 1000 + usage.ru_utime.tv_usec / 1000
+            + usage.ru_stime.tv_sec * 1000 + usage.ru_stime.tv_usec / 1000) as i32;
+        // ru_maxrss is in kilobytes
+        let memory = (usage.ru_maxrss as i64 * 1024).min(i32::MAX as i64) as i32;
+        // these are unsafe fns in older libc versions
+        #[allow(unused_unsafe)]
+        let (signal, exit_code) = unsafe {
+            if libc::WIFSIGNALED(wait_status) {
+                (libc::WTERMSIG(wait_status), 0)
+            } else {
+                (0, libc::WEXITSTATUS(wait_status))
+            }
+        };
+
+        let result = if self.is_killed {
+            RunResult::RealTimeLimitExceeded
+        } else if cpu_time > limits.max_cpu_time || signal == libc::SIGXCPU {
+            RunResult::CpuTimeLimitExceeded
+        } else if limits.max_memory > 0 && memory as i64 > limits.max_memory {
+            RunResult::MemoryLimitExceeded
+        } else if signal != 0 || exit_code != 0 {
+            RunResult::RuntimeError
+        } else {
+            RunResult::Success
+        };
+
+        RunOutcome {
+            cpu_time: cpu_time,
+            real_time: real_time,
+            memory: memory,
+            signal: signal,
+            exit_code: exit_code,
+            result: result,
+        }
+    }
+}
+
+/// Waits until the child exits or the wall time since `start_time` runs out,
+/// in which case its whole process group is killed.
+fn wait(pid: libc::pid_t, command: &str, start_time: Instant, limits: &RunLimits) -> Result<RunOutcome, String> {
+    let mut child = Watched::new(pid, command, limits);
+    loop {
+        if let Some(outcome) = child.poll(start_time)? {
+            return Ok(outcome);
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+/// Runs `command` as a child process with rlimits applied, and waits until it exits
+/// or the wall time runs out, in which case the whole process group is killed.
+pub fn run(
+    command: &str,
+    env: &[String],
+    work_dir: &Path,
+    input: Option<&Path>,
+    output: &Path,
+    error: &Path,
+    limits: &RunLimits,
+    confinement: &Confinement,
+) -> Result<RunOutcome, String> {
+    let stdin = match input {
+        Some(path) => Stdio::from(File::open(path).map_err(|e| format!("Error opening {:?}: {}", path, e))?),
+        None => Stdio::null(),
+    };
+    let stdout = File::create(output).map_err(|e| format!("Error creating {:?}: {}", output, e))?;
+    let stderr = File::create(error).map_err(|e| format!("Error creating {:?}: {}", error, e))?;
+
+    let mut cmd = prepare(command, env, work_dir, limits, confinement)?;
+    cmd.stdin(stdin)
+        .stdout(Stdio::from(stdout))
+        .stderr(Stdio::from(stderr));
+
+    let start_time = Instant::now();
+    let child = cmd.spawn().map_err(|e| format!("Error running \"{}\": {}", command, e))?;
+    wait(child.id() as libc::pid_t, command, start_time, limits)
+}
+
+/// One side of an interactive run.
+pub struct Party<'a> {
+    pub command: &'a str,
+    pub env: &'a [String],
+    pub error: &'a Path,
+    pub limits: &'a RunLimits,
+    pub confinement: &'a Confinement,
+}
+
+/// Runs the contestant's program and an interactor with each one's stdout piped
+/// into the other's stdin, and waits for both of them.
+pub fn run_interactive(user: &Party, interactor: &Party, work_dir: &Path) -> Result<(RunOutcome, RunOutcome), String> {
+    let user_stderr = File::create(user.error).map_err(|e| format!("Error creating {:?}: {}", user.error, e))?;
+    let interactor_stderr = File::create(interactor.error)
+        .map_err(|e| format!("Error creating {:?}: {}", interactor.error, e))?;
+
+    let mut user_cmd = prepare(user.command, user.env, work_dir, user.limits, user.confinement)?;
+    let mut interactor_cmd = prepare(interactor.command, interactor.env, work_dir, interactor.limits, interactor.confinement)?;
+
+    let start_time = Instant::now();
+    let mut user_child = user_cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::from(user_stderr))
+        .spawn()
+        .map_err(|e| format!("Error running \"{}\": {}", user.command, e))?;
+    let user_pid = user_child.id() as libc::pid_t;
+
+    let interactor_child = interactor_cmd
+        .stdin(Stdio::from(user_child.stdout.take().unwrap()))
+        .stdout(Stdio::from(user_child.stdin.take().unwrap()))
+        .stderr(Stdio::from(interactor_stderr))
+        .spawn();
+    // only the children may hold the pipes, or nobody ever sees the other side hang up
+    drop(interactor_cmd);
+    let interactor_child = match interactor_child {
+        Ok(child) => child,
+        Err(e) => {
+            unsafe { libc::kill(-user_pid, libc::SIGKILL); }
+            let _ = wait(user_pid, user.command, start_time, user.limits);
+            return Err(format!("Error running \"{}\": {}", interactor.command, e));
+        },
+    };
+
+    // each one's wall time is enforced while the other is still running
+    let mut children = [
+        Watched::new(user_pid, user.command, user.limits),
+        Watched::new(interactor_child.id() as libc::pid_t, interactor.command, interactor.limits),
+    ];
+    let mut outcomes: [Option<RunOutcome>; 2] = [None, None];
+    loop {
+        let polled = children.iter_mut().zip(outcomes.iter_mut())
+            .filter(|(_, outcome)| outcome.is_none())
+            .try_for_each(|(child, outcome)| child.poll(start_time).map(|polled| { *outcome = polled; }));
+        if let Err(e) = polled {
+            for (child, outcome) in children.iter().zip(outcomes.iter()) {
+                if outcome.is_none() {
+                    child.abandon();
+                }
+            }
+            return Err(e);
+        }
+        if let [Some(user_outcome), Some(interactor_outcome)] = &outcomes {
+            return Ok((user_outcome.clone(), interactor_outcome.clone()));
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(max_real_time: i32) -> RunLimits {
+        RunLimits {
+            max_cpu_time: 1000,
+            max_real_time: max_real_time,
+            max_memory: -1,
+            memory_limit_check_only: false,
+        }
+    }
+
+    #[test]
+    fn kills_a_hanging_interactor_while_the_program_waits_for_it() {
+        let work_dir = std::env::temp_dir();
+        let user_limits = limits(5000);
+        let interactor_limits = limits(200);
+        let (outcome, interactor_outcome) = run_interactive(
+            &Party {
+                command: "/bin/cat",
+                env: &[],
+                error: Path::new("/dev/null"),
+                limits: &user_limits,
+                confinement: &Confinement::default(),
+            },
+            &Party {
+                command: "/bin/sleep 10",
+                env: &[],
+                error: Path::new("/dev/null"),
+                limits: &interactor_limits,
+                confinement: &Confinement::default(),
+            },
+            &work_dir,
+        ).unwrap();
+
+        assert_eq!(interactor_outcome.result, RunResult::RealTimeLimitExceeded);
+        // cat sees the pipe close as soon as the interactor is gone
+        assert_eq!(outcome.result, RunResult::Success);
+        assert!(outcome.real_time < 2000);
+    }
+
+    #[test]
+    fn kills_a_program_past_its_wall_time() {
+        let work_dir = std::env::temp_dir();
+        let outcome = run(
+            "/bin/sleep 10",
+            &[],
+            &work_dir,
+            None,
+            Path::new("/dev/null"),
+            Path::new("/dev/null"),
+            &limits(200),
+            &Confinement::default(),
+        ).unwrap();
+
+        assert_eq!(outcome.result, RunResult::RealTimeLimitExceeded);
+        assert_eq!(outcome.signal, libc::SIGKILL);
+    }
+}
@@ -0,0 +1,225 @@
+//! The seccomp rules of the QDUOJ judger, which language configs name in `seccomp_rule`,
+//! as classic BPF programs.
+
+use std::io;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct SockFilter {
+    code: u16,
+    jt: u8,
+    jf: u8,
+    k: u32,
+}
+
+#[repr(C)]
+struct SockFprog {
+    len: libc::c_ushort,
+    filter: *const SockFilter,
+}
+
+const BPF_LD_W_ABS: u16 = 0x20;
+const BPF_JEQ_K: u16 = 0x15;
+const BPF_JGE_K: u16 = 0x35;
+const BPF_JSET_K: u16 = 0x45;
+const BPF_RET_K: u16 = 0x06;
+
+const SECCOMP_RET_KILL_PROCESS: u32 = 0x8000_0000;
+const SECCOMP_RET_ALLOW: u32 = 0x7fff_0000;
+
+// offsets into struct seccomp_data
+const NR_OFFSET: u32 = 0;
+const ARCH_OFFSET: u32 = 4;
+const ARGS_OFFSET: u32 = 16;
+
+#[cfg(target_arch = "x86_64")]
+mod syscalls {
+    pub const AUDIT_ARCH: u32 = 0xc000_003e;
+    pub const X32_SYSCALL_BIT: u32 = 0x4000_0000;
+
+    pub const OPEN: u32 = 2;
+    pub const OPENAT: u32 = 257;
+    pub const EXECVE: u32 = 59;
+
+    /// What a C or C++ program needs to read its input and write its output.
+    pub const C_CPP: &[u32] = &[
+        0, // read
+        1, // write
+        3, // close
+        5, // fstat
+        8, // lseek
+        9, // mmap
+        10, // mprotect
+        11, // munmap
+        12, // brk
+        13, // rt_sigaction
+        14, // rt_sigprocmask
+        16, // ioctl
+        17, // pread64
+        19, // readv
+        20, // writev
+        21, // access
+        25, // mremap
+        28, // madvise
+        60, // exit
+        63, // uname
+        89, // readlink
+        96, // gettimeofday
+        99, // sysinfo
+        158, // arch_prctl
+        201, // time
+        202, // futex
+        218, // set_tid_address
+        228, // clock_gettime
+        231, // exit_group
+        262, // newfstatat
+        267, // readlinkat
+        269, // faccessat
+        273, // set_robust_list
+        302, // prlimit64
+        318, // getrandom
+        332, // statx
+        334, // rseq
+        439, // faccessat2
+    ];
+
+    /// `c_cpp_file_io` may also redirect its standard streams to files.
+    pub const FILE_IO: &[u32] = &[
+        32, // dup
+        33, // dup2
+        292, // dup3
+    ];
+
+    /// What `general` takes away from interpreters: new processes, signals to others,
+    /// sockets and other executables.
+    pub const GENERAL_DENIED: &[u32] = &[
+        41, // socket
+        56, // clone
+        57, // fork
+        58, // vfork
+        62, // kill
+        101, // ptrace
+        200, // tkill
+        234, // tgkill
+        322, // execveat
+        435, // clone3
+    ];
+}
+
+/// A filter that only lets the program execute `exe` once, and then what its rule allows.
+pub struct SeccompFilter {
+    program: Vec<SockFilter>,
+}
+
+fn load(offset: u32) -> SockFilter {
+    SockFilter { code: BPF_LD_W_ABS, jt: 0, jf: 0, k: offset }
+}
+
+fn jump(code: u16, k: u32, jt: u8, jf: u8) -> SockFilter {
+    SockFilter { code: code, jt: jt, jf: jf, k: k }
+}
+
+fn ret(action: u32) -> SockFilter {
+    SockFilter { code: BPF_RET_K, jt: 0, jf: 0, k: action }
+}
+
+/// Runs `body`, which must return, for system call `nr`, and falls through otherwise.
+fn on_syscall(nr: u32, body: Vec<SockFilter>) -> Vec<SockFilter> {
+    let mut block = vec![load(NR_OFFSET), jump(BPF_JEQ_K, nr, 0, body.len() as u8)];
+    block.extend(body);
+    block
+}
+
+/// Allows the call only if argument `index` is `value`.
+fn arg_is(index: u32, value: u64) -> Vec<SockFilter> {
+    // arguments are 64 bits, compared one little-endian half at a time
+    vec![
+        load(ARGS_OFFSET + index * 8),
+        jump(BPF_JEQ_K, value as u32, 0, 3),
+        load(ARGS_OFFSET + index * 8 + 4),
+        jump(BPF_JEQ_K, (value >> 32) as u32, 0, 1),
+        ret(SECCOMP_RET_ALLOW),
+        ret(SECCOMP_RET_KILL_PROCESS),
+    ]
+}
+
+/// Allows the call only if none of `mask` is set in argument `index`.
+fn arg_lacks(index: u32, mask: u32) -> Vec<SockFilter> {
+    vec![
+        load(ARGS_OFFSET + index * 8),
+        jump(BPF_JSET_K, mask, 1, 0),
+        ret(SECCOMP_RET_ALLOW),
+        ret(SECCOMP_RET_KILL_PROCESS),
+    ]
+}
+
+impl SeccompFilter {
+    /// `exe` must stay where it is until the program is executed.
+    #[cfg(target_arch = "x86_64")]
+    pub fn new(rule: &str, exe: *const libc::c_char) -> Result<Self, String> {
+        use self::syscalls::*;
+
+        let write_flags = (libc::O_WRONLY | libc::O_RDWR | libc::O_CREAT | libc::O_TRUNC | libc::O_APPEND) as u32;
+        let mut program = vec![
+            load(ARCH_OFFSET),
+            jump(BPF_JEQ_K, AUDIT_ARCH, 1, 0),
+            ret(SECCOMP_RET_KILL_PROCESS),
+            // x32 calls would slip past the numbers below
+            load(NR_OFFSET),
+            jump(BPF_JGE_K, X32_SYSCALL_BIT, 0, 1),
+            ret(SECCOMP_RET_KILL_PROCESS),
+        ];
+        program.extend(on_syscall(EXECVE, arg_is(0, exe as u64)));
+
+        let default_action = match rule {
+            "c_cpp" | "c_cpp_file_io" => {
+                let allowed = if rule == "c_cpp" { C_CPP.to_vec() } else { [C_CPP, FILE_IO].concat() };
+                for nr in allowed {
+                    program.extend(on_syscall(nr, vec![ret(SECCOMP_RET_ALLOW)]));
+                }
+                if rule == "c_cpp" {
+                    program.extend(on_syscall(OPEN, arg_lacks(1, write_flags)));
+                    program.extend(on_syscall(OPENAT, arg_lacks(2, write_flags)));
+                } else {
+                    program.extend(on_syscall(OPEN, vec![ret(SECCOMP_RET_ALLOW)]));
+                    program.extend(on_syscall(OPENAT, vec![ret(SECCOMP_RET_ALLOW)]));
+                }
+                SECCOMP_RET_KILL_PROCESS
+            },
+            "general" => {
+                for &nr in GENERAL_DENIED {
+                    program.extend(on_syscall(nr, vec![ret(SECCOMP_RET_KILL_PROCESS)]));
+                }
+                program.extend(on_syscall(OPEN, arg_lacks(1, write_flags)));
+                program.extend(on_syscall(OPENAT, arg_lacks(2, write_flags)));
+                SECCOMP_RET_ALLOW
+            },
+            _ => return Err(format!("Unknown seccomp rule \"{}\".", rule)),
+        };
+        program.push(ret(default_action));
+
+        Ok(SeccompFilter { program: program })
+    }
+
+    #[cfg(not(target_arch = "x86_64"))]
+    pub fn new(_rule: &str, _exe: *const libc::c_char) -> Result<Self, String> {
+        Err("Seccomp rules are only implemented for x86_64.".to_owned())
+    }
+
+    /// Only to be called between fork and exec, everything executed afterwards is filtered.
+    pub fn apply(&self) -> io::Result<()> {
+        let prog = SockFprog {
+            len: self.program.len() as libc::c_ushort,
+            filter: self.program.as_ptr(),
+        };
+        unsafe {
+            if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            if libc::prctl(libc::PR_SET_SECCOMP, libc::SECCOMP_MODE_FILTER, &prog as *const SockFprog) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+}
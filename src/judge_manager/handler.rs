@@ -1,15 +1,24 @@
 use actix::prelude::*;
 use diesel::prelude::*;
-use crate::statics::JUDGE_SERVER_INFOS;
-use crate::statics::WAITING_QUEUE;
-use crate::status::model::*;
+use std::panic::{ self, AssertUnwindSafe };
+use crate::statics::{ JUDGE_BACKEND, WAITING_QUEUE, JUDGE_MAX_RETRY_TIMES, JUDGE_PROGRESS, STATUS_FEED, PENDING_RUNS };
 use crate::JudgeManager;
-use crate::judge_manager::utils::{ 
-    chooser::choose_judge_server,
-    process::run_judge_client,
-    result::get_judge_result,
+use crate::judge_manager::model::JudgeResponse;
+use crate::judge_manager::backend::JudgeSlot;
+use crate::judge_manager::utils::{
+    client::{ dispatch_judge_request, dispatch_run_request },
+    queue::{ QueuedTask, JudgePriority },
+    result::{ get_judge_result, get_subtask_results, get_verdict_summary, is_compile_error, normalize_compile_error },
+    retry::{ get_retry_delay, requeue_later },
+    recover::requeue_orphaned_tasks,
+    compare::recheck_outputs,
+    progress::ProgressEvent,
+    feed::StatusChange,
 };
+use crate::judge_server::model::JudgeSetting;
 use crate::utils::time::get_cur_naive_date_time;
+use crate::test_case::utils::subtask::load_subtasks;
+use crate::status::model::Verdict;
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct StartJudge();
@@ -20,129 +29,350 @@ impl Message for StartJudge {
 
 impl Handler<StartJudge> for JudgeManager {
     type Result = ();
-    
-    fn handle(&mut self, _msg: StartJudge, _: &mut Self::Context) -> Self::Result {
-        use crate::schema::status;
-        use crate::schema::problems;
 
-        let mut queue_size = {
+    fn handle(&mut self, _msg: StartJudge, ctx: &mut Self::Context) -> Self::Result {
+        // a panic would take the whole judge thread down with it
+        if panic::catch_unwind(AssertUnwindSafe(|| self.start_judge(ctx))).is_err() {
+            error!("judge loop panicked while starting judge tasks");
+        }
+    }
+}
+
+impl JudgeManager {
+    fn start_judge(&mut self, ctx: &mut SyncContext<Self>) {
+        let queue_size = {
             let lock = WAITING_QUEUE.read().unwrap();
-            lock.len().clone()
+            lock.len()
         };
         info!("queue_size: {}", queue_size);
-        while queue_size != 0 {
-            let server = choose_judge_server();
-            if server.is_none() { return (); }
-            let (server_url, server_token) = server.unwrap();
-            
-            let task_uuid = {
+
+        loop {
+            let slot = match JUDGE_BACKEND.acquire() {
+                Some(slot) => slot,
+                None => { return; },
+            };
+
+            let task = {
                 let mut lock = WAITING_QUEUE.write().unwrap();
-                lock.pop_front().clone().unwrap()
+                lock.pop_front()
+            };
+            let task = match task {
+                Some(task) => task,
+                None => {
+                    JUDGE_BACKEND.release(&slot.server_url);
+                    return;
+                },
             };
 
-            let cur_state = status::table
-                .filter(status::id.eq(task_uuid))
-                .select(status::state)
-                .first::<String>(&self.0)
-                .expect("Error loading setting_data from status.");
-
-            if cur_state == "Waiting".to_owned() {
-
-                let (judge_type_string, setting_string) = status::table
-                    .filter(status::id.eq(task_uuid))
-                    .select((status::judge_type, status::setting_data))
-                    .first::<(String, String)>(&self.0)
-                    .expect("Error loading setting_data from status.");
-
-                let target = status::table.filter(status::id.eq(task_uuid));
-                diesel::update(target)
-                    .set((
-                        status::state.eq("Pending".to_owned()),
-                        status::start_pend_time.eq(Some(get_cur_naive_date_time())),
-                        status::host_name.eq({
-                            let lock = JUDGE_SERVER_INFOS.read().unwrap();
-                            let server_info = lock.get(&server_url).unwrap().clone();
-                            Some(server_info.hostname.clone())
-                        }),
-                    ))
-                    .execute(&self.0).expect("Error changing status's state to Pending.");
-                
-                info!("sending request to {}", server_url);
-                {
-                    let mut lock = JUDGE_SERVER_INFOS.write().unwrap();
-                    let mut server_info = lock.get(&server_url).unwrap().clone();
-                    server_info.task_number += 1;
-                    lock.insert(server_url.clone(), server_info);
+            // runs have no status, nor any retries
+            if task.priority == JudgePriority::Run {
+                match PENDING_RUNS.take(task.id) {
+                    Some(setting) => dispatch_run_request(ctx.address(), task, slot, setting),
+                    None => JUDGE_BACKEND.release(&slot.server_url),
                 }
-                let result_string = run_judge_client(server_token, server_url.clone(), setting_string);
-                info!("{}", result_string);
-
-                {
-                    let mut lock = JUDGE_SERVER_INFOS.write().unwrap();
-                    let mut server_info = lock.get(&server_url).unwrap().clone();
-                    server_info.task_number -= 1;
-                    lock.insert(server_url, server_info);
+                continue;
+            }
+
+            let server_url = slot.server_url.clone();
+            match self.start_task(ctx.address(), task, slot) {
+                Ok(true) => (),
+                Ok(false) => JUDGE_BACKEND.release(&server_url),
+                Err(e) => {
+                    JUDGE_BACKEND.release(&server_url);
+                    error!("Error starting judge task {}: {}", task.id, e);
+                    requeue_later(ctx.address(), task, get_retry_delay(1));
+                },
+            }
+        }
+    }
+
+    /// Returns whether the task has been sent to the slot.
+    fn start_task(&mut self, jm: Addr<JudgeManager>, task: QueuedTask, slot: JudgeSlot) -> QueryResult<bool> {
+        use crate::schema::status;
+
+        let (cur_state, judge_type_string, setting_string) = status::table
+            .filter(status::id.eq(task.id))
+            .select((status::state, status::judge_type, status::setting_data))
+            .first::<(String, String, String)>(&self.0)?;
+
+        if cur_state != "Waiting".to_owned() { return Ok(false); }
+
+        diesel::update(status::table.filter(status::id.eq(task.id)))
+            .set((
+                status::state.eq("Pending".to_owned()),
+                status::start_pend_time.eq(Some(get_cur_naive_date_time())),
+                status::host_name.eq(Some(slot.host_name.clone())),
+                status::judge_server_url.eq(Some(slot.server_url.clone())),
+            ))
+            .execute(&self.0)?;
+        JUDGE_PROGRESS.publish(task.id, ProgressEvent::Pending { host_name: slot.host_name.clone() });
+        self.publish_status_change(task.id);
+
+        info!("sending request to {}", slot.server_url);
+        dispatch_judge_request(
+            jm,
+            task,
+            judge_type_string,
+            slot,
+            setting_string,
+        );
+
+        Ok(true)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FinishJudge {
+    pub task: QueuedTask,
+    pub judge_type: String,
+    pub server_url: String,
+    pub result: Result<JudgeResponse, String>,
+}
+
+impl Message for FinishJudge {
+    type Result = ();
+}
+
+impl Handler<FinishJudge> for JudgeManager {
+    type Result = ();
+
+    fn handle(&mut self, msg: FinishJudge, ctx: &mut Self::Context) -> Self::Result {
+        let task_uuid = msg.task.id;
+        // the supervisor may have given the task to another server meanwhile,
+        // then it isn't counted by this one anymore
+        let is_assigned = self.is_still_assigned(task_uuid, &msg.server_url);
+        if !matches!(is_assigned, Ok(false)) {
+            JUDGE_BACKEND.release(&msg.server_url);
+        }
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            if !is_assigned? {
+                info!("dropping the late result of {} from {}", task_uuid, msg.server_url);
+                return Ok(());
+            }
+            match msg.result {
+                Err(reason) => self.retry_task(ctx.address(), msg.task, reason),
+                Ok(response) => self.save_judge_result(task_uuid, msg.judge_type, response),
+            }
+        }));
+        match result {
+            Err(_) => error!("judge loop panicked while finishing judge task {}", task_uuid),
+            Ok(Err(e)) => error!("Error saving judge result of {}: {}", task_uuid, e),
+            Ok(Ok(())) => (),
+        }
+
+        // the judge server has a free slot now
+        ctx.address().do_send(StartJudge());
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FinishRun {
+    pub server_url: String,
+}
+
+impl Message for FinishRun {
+    type Result = ();
+}
+
+impl Handler<FinishRun> for JudgeManager {
+    type Result = ();
+
+    fn handle(&mut self, msg: FinishRun, ctx: &mut Self::Context) -> Self::Result {
+        JUDGE_BACKEND.release(&msg.server_url);
+        ctx.address().do_send(StartJudge());
+    }
+}
+
+/// Takes back the tasks of a judge server which stopped answering or was disabled.
+#[derive(Debug, Clone)]
+pub struct RequeueJudgeServerTasks {
+    pub service_url: String,
+}
+
+impl Message for RequeueJudgeServerTasks {
+    type Result = ();
+}
+
+impl Handler<RequeueJudgeServerTasks> for JudgeManager {
+    type Result = ();
+
+    fn handle(&mut self, msg: RequeueJudgeServerTasks, ctx: &mut Self::Context) -> Self::Result {
+        match requeue_orphaned_tasks(&self.0, &msg.service_url) {
+            Err(e) => error!("Error requeueing tasks of {}: {}", msg.service_url, e),
+            Ok(ids) => {
+                info!("requeued {} tasks left on {}", ids.len(), msg.service_url);
+                for id in ids {
+                    JUDGE_PROGRESS.publish(id, ProgressEvent::Waiting);
+                    self.publish_status_change(id);
                 }
+            },
+        }
+        ctx.address().do_send(StartJudge());
+    }
+}
+
+impl JudgeManager {
+    fn is_still_assigned(&self, task_uuid: uuid::Uuid, server_url: &str) -> QueryResult<bool> {
+        use crate::schema::status;
+
+        let (state, cur_server_url) = status::table
+            .filter(status::id.eq(task_uuid))
+            .select((status::state, status::judge_server_url))
+            .first::<(String, Option<String>)>(&self.0)?;
+        Ok(state == "Pending" && cur_server_url.as_deref() == Some(server_url))
+    }
+
+    fn retry_task(&mut self, jm: Addr<JudgeManager>, task: QueuedTask, reason: String) -> QueryResult<()> {
+        use crate::schema::status;
+
+        info!("{}", reason);
+        let retry_times = diesel::update(status::table.filter(status::id.eq(task.id)))
+            .set(status::retry_times.eq(status::retry_times + 1))
+            .returning(status::retry_times)
+            .get_result::<i32>(&self.0)?;
+
+        if retry_times > *JUDGE_MAX_RETRY_TIMES {
+            diesel::update(status::table.filter(status::id.eq(task.id)))
+                .set((
+                    status::state.eq("Finished".to_owned()),
+                    status::result.eq(Some("System Error".to_owned())),
+                    status::score.eq(None::<f64>),
+                    status::verdict.eq(Some(Verdict::SE.as_str().to_owned())),
+                    status::err_reason.eq(Some(reason)),
+                    status::finish_time.eq(Some(get_cur_naive_date_time())),
+                ))
+                .execute(&self.0)?;
+
+            JUDGE_PROGRESS.publish(task.id, ProgressEvent::Finished { verdict: Verdict::SE });
+            self.publish_status_change(task.id);
+            info!("gave up judging {} after {} attempts", task.id, retry_times);
+        } else {
+            diesel::update(status::table.filter(status::id.eq(task.id)))
+                .set((
+                    status::state.eq("Waiting".to_owned()),
+                    status::start_pend_time.eq(None::<chrono::NaiveDateTime>),
+                    status::host_name.eq(None::<String>),
+                    status::judge_server_url.eq(None::<String>),
+                ))
+                .execute(&self.0)?;
+
+            JUDGE_PROGRESS.publish(task.id, ProgressEvent::Waiting);
+            self.publish_status_change(task.id);
+            let delay = get_retry_delay(retry_times);
+            info!("pushing {} back to queue in {}s", task.id, delay.as_secs());
+            requeue_later(jm, task, delay);
+        }
 
-                if result_string == String::from("") {
-                    let target = status::table.filter(status::id.eq(task_uuid));
-                    diesel::update(target)
-                        .set((
-                            status::state.eq("Waiting".to_owned()),
-                            status::start_pend_time.eq(Some(get_cur_naive_date_time())),
-                            status::host_name.eq({ let tmp: Option<String> = None; tmp }),
-                        ))
-                    .execute(&self.0).expect("Error changing status's state to Pending.");
-
-                    {
-                        let mut lock = WAITING_QUEUE.write().unwrap();
-                        lock.push_front(task_uuid);
-                    }
-                    
-                    info!("pushed {} back to queue", task_uuid);
-                    continue;
+        Ok(())
+    }
+
+    fn save_judge_result(&mut self, task_uuid: uuid::Uuid, judge_type: String, response: JudgeResponse) -> QueryResult<()> {
+        use crate::schema::status;
+        use crate::schema::problems;
+
+        let (max_score, test_case, setting_data) = status::table
+            .inner_join(problems::table.on(status::problem_id.eq(problems::id)
+                .and(status::problem_region.eq(problems::region))))
+            .filter(status::id.eq(task_uuid))
+            .select((problems::max_score, problems::test_case, status::setting_data))
+            .first::<(i32, Option<String>, String)>(&self.0)?;
+
+        let mut response = response;
+        if let JudgeResponse::Finished(judge_result) = &mut response {
+            let judge_setting = serde_json::from_str::<JudgeSetting>(&setting_data).ok();
+            let comparison = judge_setting.as_ref().and_then(|judge_setting| judge_setting.comparison.clone());
+            if let (Some(comparison), Some(test_case)) = (comparison, test_case.as_ref()) {
+                if let Err(e) = recheck_outputs(&comparison, test_case, &mut judge_result.data) {
+                    error!("keeping the judge's verdicts of {}: {}", task_uuid, e);
                 }
+            }
+            // outputs asked for the recheck only would give the hidden inputs away
+            if !judge_setting.map(|judge_setting| judge_setting.shows_output()).unwrap_or(false) {
+                for data in judge_result.data.iter_mut() {
+                    data.output = None;
+                }
+            }
+
+            let subtasks = match test_case.as_ref().map(|test_case| load_subtasks(test_case)) {
+                Some(Ok(subtasks)) => subtasks,
+                Some(Err(e)) => {
+                    error!("scoring {} without subtasks: {}", task_uuid, e);
+                    Vec::new()
+                },
+                None => Vec::new(),
+            };
+            if !subtasks.is_empty() {
+                judge_result.subtasks = Some(get_subtask_results(&subtasks, &judge_result.data, max_score));
+            }
+        }
 
-                let (op_result, op_score, op_err_reason) = get_judge_result(judge_type_string, result_string.clone());
-
-                // update status
-                let target = status::table.filter(status::id.eq(task_uuid));
-                diesel::update(target)
-                    .set((
-                        status::state.eq("Finished".to_owned()),
-                        status::result.eq(op_result.clone()),
-                        status::score.eq(op_score),
-                        status::result_data.eq(Some(result_string)),
-                        status::err_reason.eq(op_err_reason),
-                        status::finish_time.eq(Some(get_cur_naive_date_time())),
-                    ))
-                    .execute(&self.0).expect("Error changing status's data.");
-
-                let result = status::table.filter(status::id.eq(task_uuid))    
-                    .first::<Status>(&self.0).expect("Error changing status's data.");
-                
-                let target = problems::table
-                    .filter(problems::region.eq(result.problem_region))
-                    .filter(problems::id.eq(result.problem_id));
-                
-                diesel::update(target)
-                    .set((
-                        problems::submit_times.eq(problems::submit_times + if op_result.clone().is_some() {
-                            if op_result.clone().unwrap() == "Unaccepted".to_owned() || op_result.clone().unwrap() == "Accepted".to_owned() { 1 } else { 0 }
-                        } else { 0 }),
-                        problems::accept_times.eq(problems::accept_times + if op_result.clone().is_some() { 
-                            if op_result.unwrap() == "Accepted".to_owned() { 1 } else { 0 }
-                        } else { 0 } )
-                    ))
-                    .execute(&self.0).expect("Error changing problem's data.");
+        if let JudgeResponse::Failed(err_result) = &mut response {
+            if is_compile_error(err_result) {
+                err_result.data = normalize_compile_error(&err_result.data);
             }
+        }
+
+        let result_string = response.to_result_data();
+        info!("{}", result_string);
+
+        let summary = get_verdict_summary(&response);
+
+        let (op_result, op_score, op_err_reason) = get_judge_result(judge_type, response, max_score);
+
+        // update status
+        let (problem_region, problem_id) = diesel::update(status::table.filter(status::id.eq(task_uuid)))
+            .set((
+                status::state.eq("Finished".to_owned()),
+                status::result.eq(op_result.clone()),
+                status::score.eq(op_score),
+                status::result_data.eq(Some(result_string)),
+                status::err_reason.eq(op_err_reason),
+                status::finish_time.eq(Some(get_cur_naive_date_time())),
+                status::verdict.eq(Some(summary.verdict.as_str().to_owned())),
+                status::verdict_test_case.eq(summary.test_case),
+                status::max_time.eq(summary.max_time),
+                status::max_memory.eq(summary.max_memory),
+            ))
+            .returning((status::problem_region, status::problem_id))
+            .get_result::<(String, i32)>(&self.0)?;
+        JUDGE_PROGRESS.publish(task_uuid, ProgressEvent::Finished { verdict: summary.verdict });
+        self.publish_status_change(task_uuid);
 
-            queue_size = {
-                let lock = WAITING_QUEUE.read().unwrap();
-                lock.len().clone()
-            }; 
+        let is_accepted = op_result == Some("Accepted".to_owned());
+        let is_effective = is_accepted || op_result == Some("Unaccepted".to_owned());
+        if is_effective {
+            diesel::update(problems::table
+                .filter(problems::region.eq(problem_region))
+                .filter(problems::id.eq(problem_id)))
+                .set((
+                    problems::submit_times.eq(problems::submit_times + 1),
+                    problems::accept_times.eq(problems::accept_times + if is_accepted { 1 } else { 0 }),
+                ))
+                .execute(&self.0)?;
         }
 
-        ()
+        Ok(())
     }
-}
\ No newline at end of file
+
+    fn publish_status_change(&self, task_uuid: uuid::Uuid) {
+        use crate::schema::status;
+
+        if !STATUS_FEED.is_watched() { return; }
+        let change = status::table
+            .filter(status::id.eq(task_uuid))
+            .select((status::owner_id, status::problem_region, status::problem_id, status::state, status::verdict))
+            .first::<(i32, String, i32, String, Option<String>)>(&self.0);
+        match change {
+            Ok((owner_id, problem_region, problem_id, state, verdict)) => STATUS_FEED.publish(StatusChange {
+                id: task_uuid,
+                owner_id: owner_id,
+                problem_region: problem_region,
+                problem_id: problem_id,
+                state: state,
+                verdict: verdict.and_then(|verdict| verdict.parse().ok()),
+            }),
+            Err(e) => error!("Error loading status change of {}: {}", task_uuid, e),
+        }
+    }
+}
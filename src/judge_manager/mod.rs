@@ -1,10 +1,15 @@
 pub mod handler;
 pub mod utils;
 pub mod model;
+pub mod backend;
 
 use diesel::prelude::*;
 use actix::prelude::*;
-use crate::statics::DATABASE_URL;
+use crate::statics::{ DATABASE_URL, JUDGE_BACKEND_NAME, JUDGE_SCHEDULING_POLICY, JUDGE_BACKEND, SCHEDULING_POLICY };
+use utils::recover::recover_waiting_queue;
+use utils::scheduler::get_scheduling_policy;
+use backend::get_judge_backend;
+use backend::jail::check_jail;
 
 pub struct JudgeManager(pub PgConnection);
 
@@ -17,9 +22,27 @@ pub struct JMState {
     pub jm: Addr<JudgeManager>,
 }
 
+/// A mistyped backend or policy would otherwise only panic once the judge loop runs,
+/// and an unsandboxed local backend would judge untrusted code as the server.
+pub fn check_judge_config() {
+    let checked = get_judge_backend(&JUDGE_BACKEND_NAME).map(|_| ())
+        .and_then(|_| if *JUDGE_BACKEND_NAME == "local" { check_jail() } else { Ok(()) })
+        .and_then(|_| get_scheduling_policy(&JUDGE_SCHEDULING_POLICY).map(|_| ()));
+    if let Err(msg) = checked {
+        error!("{}", msg);
+        std::process::exit(1);
+    }
+    lazy_static::initialize(&JUDGE_BACKEND);
+    lazy_static::initialize(&SCHEDULING_POLICY);
+}
+
 pub fn create_judge_manager() -> Addr<JudgeManager> {
     let database_url = (*DATABASE_URL).clone();
 
+    // rebuild waiting queue from unfinished status before any judge starts
+    let conn = PgConnection::establish(&database_url).unwrap();
+    recover_waiting_queue(&conn);
+
     SyncArbiter::start(8, move || {
         JudgeManager(PgConnection::establish(&database_url).unwrap())
     })
@@ -0,0 +1,14 @@
+use crate::status::model::{ JudgeResult, ErrResult };
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum JudgeResponse {
+    Finished(JudgeResult),
+    Failed(ErrResult),
+}
+
+impl JudgeResponse {
+    pub fn to_result_data(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
@@ -1,12 +1,15 @@
-use crate::statics::JUDGE_SERVER_INFOS;
+use crate::statics::{ JUDGE_SERVER_INFOS, SCHEDULING_POLICY };
 
 pub fn choose_judge_server() -> Option<(String, String)> {
     let lock = JUDGE_SERVER_INFOS.read().unwrap();
-    for (url, info) in lock.iter() {
+    let mut candidates = Vec::new();
+    for (_url, info) in lock.iter() {
         let last_heartbeat = info.heartbeat_time.elapsed().unwrap().as_secs() as i32;
-        if !info.is_deprecated && info.task_number + 1 <= info.cpu_core * 2 && last_heartbeat <= 5 {
-            return Some((url.to_owned(), info.token.clone()));
+        if !info.is_deprecated && info.control().accepts_tasks()
+            && info.task_number + 1 <= info.capacity() && last_heartbeat <= 5 {
+            candidates.push(info);
         }
     }
-    None
+    SCHEDULING_POLICY.choose(&candidates)
+        .map(|info| (info.service_url.clone(), info.token.clone()))
 }
\ No newline at end of file
@@ -0,0 +1,97 @@
+use actix::prelude::*;
+use actix_web::client::{ Client, Connector };
+use std::time::Duration;
+use crate::statics::{ JUDGE_REQUEST_TIMEOUT, JUDGE_CONNECT_TIMEOUT, JUDGE_BACKEND, PENDING_RUNS };
+use crate::status::model::ErrChecker;
+use super::queue::QueuedTask;
+use crate::judge_manager::{
+    JudgeManager,
+    model::JudgeResponse,
+    handler::{ FinishJudge, FinishRun },
+    backend::JudgeSlot,
+};
+
+// judge results may carry users' output, so allow a much bigger body than the default
+const MAX_RESPONSE_SIZE: usize = 64 * 1024 * 1024;
+
+pub async fn send_judge_request(
+    token: String,
+    url: String,
+    judge_setting: String,
+) -> Result<JudgeResponse, String> {
+    let client = Client::builder()
+        .connector(Connector::new()
+            .timeout(Duration::from_secs(*JUDGE_CONNECT_TIMEOUT))
+            .finish())
+        .timeout(Duration::from_secs(*JUDGE_REQUEST_TIMEOUT))
+        .finish();
+
+    let mut response = client
+        .post(format!("{}/judge", url))
+        .set_header("X-Judge-Server-Token", token)
+        .set_header("Content-Type", "application/json")
+        .send_body(judge_setting)
+        .await
+        .map_err(|e| format!("Error sending judge request to {}: {}", url, e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("Judge server {} responded with {}", url, response.status()));
+    }
+
+    let body = response.body()
+        .limit(MAX_RESPONSE_SIZE)
+        .await
+        .map_err(|e| format!("Error reading judge response from {}: {}", url, e))?;
+
+    let err_checker: ErrChecker = serde_json::from_slice(&body)
+        .map_err(|e| format!("Malformed judge response from {}: {}", url, e))?;
+    if err_checker.err.is_none() {
+        serde_json::from_slice(&body)
+            .map(JudgeResponse::Finished)
+            .map_err(|e| format!("Malformed judge result from {}: {}", url, e))
+    } else {
+        serde_json::from_slice(&body)
+            .map(JudgeResponse::Failed)
+            .map_err(|e| format!("Malformed judge error from {}: {}", url, e))
+    }
+}
+
+/// Runs the judge request on the system arbiter, so the calling
+/// *JudgeManager* thread is free as soon as the task is sent.
+pub fn dispatch_judge_request(
+    jm: Addr<JudgeManager>,
+    task: QueuedTask,
+    judge_type: String,
+    slot: JudgeSlot,
+    judge_setting: String,
+) {
+    System::current().arbiter().exec_fn(move || {
+        actix_rt::spawn(async move {
+            let server_url = slot.server_url.clone();
+            let result = JUDGE_BACKEND.judge(task.id, slot, judge_setting).await;
+            jm.do_send(FinishJudge {
+                task: task,
+                judge_type: judge_type,
+                server_url: server_url,
+                result: result,
+            });
+        });
+    });
+}
+
+/// Like `dispatch_judge_request`, but the result goes straight back to whoever waits for the run.
+pub fn dispatch_run_request(
+    jm: Addr<JudgeManager>,
+    task: QueuedTask,
+    slot: JudgeSlot,
+    setting: String,
+) {
+    System::current().arbiter().exec_fn(move || {
+        actix_rt::spawn(async move {
+            let server_url = slot.server_url.clone();
+            let result = JUDGE_BACKEND.judge(task.id, slot, setting).await;
+            PENDING_RUNS.finish(task.id, result);
+            jm.do_send(FinishRun { server_url: server_url });
+        });
+    });
+}
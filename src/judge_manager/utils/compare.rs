@@ -0,0 +1,105 @@
+use crate::judge_server::model::Comparison;
+use crate::status::model::JudgeResultData;
+use std::fs;
+
+fn compare_float(expected: &str, actual: &str, epsilon: f64) -> bool {
+    match (expected.parse::<f64>(), actual.parse::<f64>()) {
+        (Ok(expected), Ok(actual)) => {
+            let diff = (expected - actual).abs();
+            diff <= epsilon || diff <= epsilon * expected.abs()
+        },
+        _ => expected == actual,
+    }
+}
+
+fn sorted_lines(text: &str) -> Vec<&str> {
+    let mut lines: Vec<&str> = text.lines().map(|line| line.trim_end()).collect();
+    while lines.last() == Some(&"") { lines.pop(); }
+    lines.sort_unstable();
+    lines
+}
+
+pub fn compare_output(comparison: &Comparison, expected: &str, actual: &str) -> bool {
+    match comparison {
+        Comparison::Token => expected.split_whitespace().eq(actual.split_whitespace()),
+        Comparison::CaseInsensitive => expected.split_whitespace().map(|token| token.to_lowercase())
+            .eq(actual.split_whitespace().map(|token| token.to_lowercase())),
+        Comparison::Float { epsilon } => {
+            let expected: Vec<&str> = expected.split_whitespace().collect();
+            let actual: Vec<&str> = actual.split_whitespace().collect();
+            expected.len() == actual.len()
+                && expected.iter().zip(actual.iter()).all(|(e, a)| compare_float(e, a, *epsilon))
+        },
+        Comparison::UnorderedLines => sorted_lines(expected) == sorted_lines(actual),
+    }
+}
+
+/// Judges the outputs again with `comparison`, in place of the judge's own md5 comparison.
+/// Only test cases the program ran through are touched.
+pub fn recheck_outputs(comparison: &Comparison, test_case: &str, data: &mut Vec<JudgeResultData>) -> Result<(), String> {
+    let dir = "data/test_case/".to_owned() + test_case;
+    let content = fs::read_to_string(dir.clone() + "/info").map_err(|e| format!("Error reading {}/info: {}", dir, e))?;
+    let info: serde_json::Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Malformed test case info {}/info: {}", dir, e))?;
+
+    for case in data.iter_mut() {
+        if case.error != 0 || (case.result != 0 && case.result != -1) { continue; }
+        let actual = match &case.output {
+            Some(actual) => actual,
+            None => { continue; },
+        };
+        let output_name = info["test_cases"][&case.test_case]["output_name"].as_str()
+            .ok_or(format!("Test case {} has no output in {}/info", case.test_case, dir))?;
+        let expected = fs::read_to_string(dir.clone() + "/" + output_name)
+            .map_err(|e| format!("Error reading {}/{}: {}", dir, output_name, e))?;
+
+        case.result = if compare_output(comparison, &expected, actual) { 0 } else { -1 };
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_ignores_whitespace_layout() {
+        assert!(compare_output(&Comparison::Token, "1 2\n3\n", "1\t2 3"));
+        assert!(!compare_output(&Comparison::Token, "1 2 3", "1 2"));
+        assert!(!compare_output(&Comparison::Token, "Yes", "yes"));
+    }
+
+    #[test]
+    fn case_insensitive_ignores_case_of_tokens() {
+        assert!(compare_output(&Comparison::CaseInsensitive, "YES\nNo", "yes no"));
+        assert!(!compare_output(&Comparison::CaseInsensitive, "yes", "yes no"));
+    }
+
+    #[test]
+    fn float_allows_absolute_or_relative_epsilon() {
+        let comparison = Comparison::Float { epsilon: 1e-6 };
+        assert!(compare_output(&comparison, "0.3333333", "0.33333335"));
+        assert!(compare_output(&comparison, "1000000000", "1000000000.5"));
+        assert!(!compare_output(&comparison, "0.5", "0.51"));
+        // tokens which aren't numbers must match exactly
+        assert!(compare_output(&comparison, "case 1.0", "case 1"));
+        assert!(!compare_output(&comparison, "case", "Case"));
+        assert!(!compare_output(&comparison, "1 2", "1"));
+    }
+
+    #[test]
+    fn unordered_lines_ignores_line_order_and_trailing_space() {
+        assert!(compare_output(&Comparison::UnorderedLines, "a 1\nb 2\n", "b 2  \na 1\n\n"));
+        assert!(!compare_output(&Comparison::UnorderedLines, "a 1\nb 2", "a 1\na 1"));
+        assert!(!compare_output(&Comparison::UnorderedLines, "a  1", "a 1"));
+    }
+
+    #[test]
+    fn modes_are_parsed_with_their_epsilon() {
+        assert_eq!(Comparison::from_mode("exact", None), Ok(None));
+        assert_eq!(Comparison::from_mode("float", Some(0.01)), Ok(Some(Comparison::Float { epsilon: 0.01 })));
+        assert!(Comparison::from_mode("float", None).is_err());
+        assert!(Comparison::from_mode("float", Some(-1.0)).is_err());
+        assert!(Comparison::from_mode("regex", None).is_err());
+    }
+}
@@ -0,0 +1,65 @@
+use futures::channel::mpsc::{ unbounded, UnboundedReceiver, UnboundedSender };
+use std::sync::Mutex;
+use uuid::Uuid;
+use crate::status::model::Verdict;
+
+/// A status moving to another state, made by a submit, a rejudge or the *JudgeManager*.
+#[derive(Debug, Clone, Serialize, juniper::GraphQLObject)]
+pub struct StatusChange {
+    pub id: Uuid,
+    pub owner_id: i32,
+    pub problem_region: String,
+    pub problem_id: i32,
+    pub state: String,
+    pub verdict: Option<Verdict>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StatusFilter {
+    /// every change of one user's statuses
+    Owner(i32),
+    /// statuses of a region as they finish
+    FinishedInRegion(String),
+}
+
+impl StatusFilter {
+    fn matches(&self, change: &StatusChange) -> bool {
+        match self {
+            StatusFilter::Owner(owner_id) => change.owner_id == *owner_id,
+            StatusFilter::FinishedInRegion(region) => {
+                change.state == "Finished" && &change.problem_region == region
+            },
+        }
+    }
+}
+
+/// Hands status changes to everyone watching a matching stream.
+pub struct StatusFeed {
+    subscribers: Mutex<Vec<(StatusFilter, UnboundedSender<StatusChange>)>>,
+}
+
+impl StatusFeed {
+    pub fn new() -> Self {
+        StatusFeed { subscribers: Mutex::new(Vec::new()) }
+    }
+
+    pub fn subscribe(&self, filter: StatusFilter) -> UnboundedReceiver<StatusChange> {
+        let (sender, receiver) = unbounded();
+        self.subscribers.lock().unwrap().push((filter, sender));
+        receiver
+    }
+
+    /// Lets publishers skip loading a change nobody would get.
+    pub fn is_watched(&self) -> bool {
+        !self.subscribers.lock().unwrap().is_empty()
+    }
+
+    pub fn publish(&self, change: StatusChange) {
+        let mut lock = self.subscribers.lock().unwrap();
+        // forget the ones that went away
+        lock.retain(|(filter, sender)| {
+            if sender.is_closed() { return false; }
+            !filter.matches(&change) || sender.unbounded_send(change.clone()).is_ok()
+        });
+    }
+}
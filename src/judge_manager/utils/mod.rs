@@ -1,4 +1,14 @@
-pub mod process;
+pub mod client;
 pub mod chooser;
+pub mod scheduler;
 pub mod result;
-pub mod mapper;
\ No newline at end of file
+pub mod mapper;
+pub mod recover;
+pub mod queue;
+pub mod priority;
+pub mod retry;
+pub mod compare;
+pub mod progress;
+pub mod feed;
+pub mod supervisor;
+pub mod run;
\ No newline at end of file
@@ -0,0 +1,51 @@
+use diesel::prelude::*;
+use chrono::NaiveDateTime;
+use std::collections::HashSet;
+use crate::utils::time::get_cur_naive_date_time;
+use super::queue::JudgePriority;
+
+/// Submissions to a running contest go first, everything else is practice.
+pub fn get_judge_priority(conn: &PgConnection, problem_region: &str) -> JudgePriority {
+    use crate::schema::{ regions, contests };
+
+    let self_type = regions::table
+        .filter(regions::name.eq(problem_region))
+        .select(regions::self_type)
+        .first::<String>(conn);
+
+    match self_type {
+        Ok(ref self_type) if self_type == "contest" => {
+            let cur_time = get_cur_naive_date_time();
+            match contests::table
+                .filter(contests::region.eq(problem_region))
+                .select((contests::start_time, contests::end_time))
+                .first::<(NaiveDateTime, NaiveDateTime)>(conn)
+            {
+                Ok((start_time, end_time)) if start_time <= cur_time && cur_time <= end_time => JudgePriority::Contest,
+                _ => JudgePriority::Practice,
+            }
+        },
+        _ => JudgePriority::Practice,
+    }
+}
+
+/// Regions of the running contests, for giving many statuses their priority at once.
+pub fn get_running_contest_regions(conn: &PgConnection) -> QueryResult<HashSet<String>> {
+    use crate::schema::{ regions, contests };
+
+    let cur_time = get_cur_naive_date_time();
+    let running_regions = regions::table
+        .filter(regions::self_type.eq("contest"))
+        .filter(regions::name.eq_any(contests::table
+            .filter(contests::start_time.le(cur_time))
+            .filter(contests::end_time.ge(cur_time))
+            .select(contests::region)))
+        .select(regions::name)
+        .load::<String>(conn)?;
+
+    Ok(running_regions.into_iter().collect())
+}
+
+pub fn get_priority_in(running_contest_regions: &HashSet<String>, problem_region: &str) -> JudgePriority {
+    if running_contest_regions.contains(problem_region) { JudgePriority::Contest } else { JudgePriority::Practice }
+}
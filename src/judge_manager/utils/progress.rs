@@ -0,0 +1,107 @@
+use futures::channel::mpsc::{ unbounded, UnboundedReceiver, UnboundedSender };
+use std::collections::HashMap;
+use std::sync::Mutex;
+use uuid::Uuid;
+use crate::status::model::{ JudgeResultData, Verdict };
+
+/// What happened to a status while it was judged, in the order it happened.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ProgressEvent {
+    /// back in the queue, after a judge server failed
+    Waiting,
+    Pending { host_name: String },
+    Compiling,
+    /// `index` counts from 1
+    Running { test_case: String, index: i32, total: i32 },
+    TestCaseFinished {
+        test_case: String,
+        index: i32,
+        total: i32,
+        verdict: Verdict,
+        cpu_time: i32,
+        memory: i32,
+    },
+    Finished { verdict: Verdict },
+}
+
+impl ProgressEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProgressEvent::Waiting => "waiting",
+            ProgressEvent::Pending { .. } => "pending",
+            ProgressEvent::Compiling => "compiling",
+            ProgressEvent::Running { .. } => "running",
+            ProgressEvent::TestCaseFinished { .. } => "test_case_finished",
+            ProgressEvent::Finished { .. } => "finished",
+        }
+    }
+
+    pub fn test_case_finished(data: &JudgeResultData, index: usize, total: usize) -> ProgressEvent {
+        ProgressEvent::TestCaseFinished {
+            test_case: data.test_case.clone(),
+            index: index as i32 + 1,
+            total: total as i32,
+            verdict: Verdict::from_result(data.result),
+            cpu_time: data.cpu_time,
+            memory: data.memory,
+        }
+    }
+}
+
+/// Hands progress events of each status to whoever is watching it.
+/// Nothing is kept for statuses nobody watches.
+pub struct ProgressHub {
+    subscribers: Mutex<HashMap<Uuid, Vec<UnboundedSender<ProgressEvent>>>>,
+}
+
+impl ProgressHub {
+    pub fn new() -> Self {
+        ProgressHub { subscribers: Mutex::new(HashMap::new()) }
+    }
+
+    /// The receiver ends after the finished event.
+    pub fn subscribe(&self, status_id: Uuid) -> UnboundedReceiver<ProgressEvent> {
+        let (sender, receiver) = unbounded();
+        let mut lock = self.subscribers.lock().unwrap();
+        // streams of statuses which never publish again are only cleaned up here
+        lock.retain(|_, senders| {
+            senders.retain(|sender| !sender.is_closed());
+            !senders.is_empty()
+        });
+        lock.entry(status_id).or_insert_with(Vec::new).push(sender);
+        receiver
+    }
+
+    /// Forgets the dropped receivers of one status.
+    pub fn unsubscribe_closed(&self, status_id: Uuid) {
+        let mut lock = self.subscribers.lock().unwrap();
+        if let Some(senders) = lock.get_mut(&status_id) {
+            senders.retain(|sender| !sender.is_closed());
+            if senders.is_empty() {
+                lock.remove(&status_id);
+            }
+        }
+    }
+
+    pub fn publish(&self, status_id: Uuid, event: ProgressEvent) {
+        let mut lock = self.subscribers.lock().unwrap();
+        if let ProgressEvent::Finished { .. } = event {
+            // dropping the senders closes every stream
+            if let Some(senders) = lock.remove(&status_id) {
+                for sender in senders.iter() {
+                    let _ = sender.unbounded_send(event.clone());
+                }
+            }
+            return;
+        }
+
+        if let Some(senders) = lock.get_mut(&status_id) {
+            // forget the ones that went away
+            senders.retain(|sender| sender.unbounded_send(event.clone()).is_ok());
+            if senders.is_empty() {
+                lock.remove(&status_id);
+            }
+        }
+    }
+}
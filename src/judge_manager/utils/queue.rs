@@ -0,0 +1,255 @@
+use std::collections::{ HashMap, VecDeque };
+use uuid::Uuid;
+
+// a non-empty lane is served at least once after being passed over this many times
+const MAX_SKIPPED_TIMES: u32 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JudgePriority {
+    Contest,
+    Practice,
+    Bulk,
+    /// custom input runs, which aren't graded
+    Run,
+}
+
+impl JudgePriority {
+    fn lane(&self) -> usize {
+        match self {
+            JudgePriority::Contest => 0,
+            JudgePriority::Practice => 1,
+            JudgePriority::Bulk => 2,
+            JudgePriority::Run => 3,
+        }
+    }
+
+    fn from_lane(lane: usize) -> Self {
+        match lane {
+            0 => JudgePriority::Contest,
+            1 => JudgePriority::Practice,
+            2 => JudgePriority::Bulk,
+            _ => JudgePriority::Run,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueuedTask {
+    pub id: Uuid,
+    pub owner_id: i32,
+    pub priority: JudgePriority,
+}
+
+/// Tasks of one priority, taken from the owners in turn
+/// so that a long backlog of one user doesn't hold back the others.
+#[derive(Debug, Default)]
+struct Lane {
+    owners: VecDeque<i32>,
+    tasks: HashMap<i32, VecDeque<Uuid>>,
+}
+
+impl Lane {
+    fn len(&self) -> usize {
+        self.tasks.values().map(|tasks| tasks.len()).sum()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.owners.is_empty()
+    }
+
+    fn clear(&mut self) {
+        self.owners.clear();
+        self.tasks.clear();
+    }
+
+    fn push_back(&mut self, id: Uuid, owner_id: i32) {
+        let tasks = self.tasks.entry(owner_id).or_insert_with(VecDeque::new);
+        if tasks.is_empty() {
+            self.owners.push_back(owner_id);
+        }
+        tasks.push_back(id);
+    }
+
+    /// The owner goes first too, so the task is served next.
+    fn push_front(&mut self, id: Uuid, owner_id: i32) {
+        let tasks = self.tasks.entry(owner_id).or_insert_with(VecDeque::new);
+        if !tasks.is_empty() {
+            self.owners.retain(|owner| *owner != owner_id);
+        }
+        self.owners.push_front(owner_id);
+        tasks.push_front(id);
+    }
+
+    fn pop_front(&mut self) -> Option<(Uuid, i32)> {
+        let owner_id = self.owners.pop_front()?;
+        let tasks = self.tasks.get_mut(&owner_id)?;
+        let id = tasks.pop_front()?;
+        if tasks.is_empty() {
+            self.tasks.remove(&owner_id);
+        } else {
+            self.owners.push_back(owner_id);
+        }
+        Some((id, owner_id))
+    }
+}
+
+/// Waiting status split into priority lanes.
+/// Higher lanes are always drained first, unless a lower lane has been starving.
+#[derive(Debug)]
+pub struct JudgeQueue {
+    lanes: [Lane; 4],
+    skipped_times: [u32; 4],
+}
+
+impl JudgeQueue {
+    pub fn new() -> Self {
+        JudgeQueue {
+            lanes: [Lane::default(), Lane::default(), Lane::default(), Lane::default()],
+            skipped_times: [0; 4],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.lanes.iter().map(|lane| lane.len()).sum()
+    }
+
+    pub fn clear(&mut self) {
+        for lane in self.lanes.iter_mut() {
+            lane.clear();
+        }
+        self.skipped_times = [0; 4];
+    }
+
+    pub fn push_back(&mut self, task: QueuedTask) {
+        self.lanes[task.priority.lane()].push_back(task.id, task.owner_id);
+    }
+
+    /// Used for tasks which have been taken out before and need to go again.
+    pub fn push_front(&mut self, task: QueuedTask) {
+        self.lanes[task.priority.lane()].push_front(task.id, task.owner_id);
+    }
+
+    pub fn pop_front(&mut self) -> Option<QueuedTask> {
+        let highest = self.lanes.iter().position(|lane| !lane.is_empty())?;
+        let starving = (highest + 1..self.lanes.len())
+            .filter(|&lane| !self.lanes[lane].is_empty() && self.skipped_times[lane] >= MAX_SKIPPED_TIMES)
+            .max_by_key(|&lane| self.skipped_times[lane]);
+        let chosen = starving.unwrap_or(highest);
+
+        for lane in 0..self.lanes.len() {
+            if lane == chosen {
+                self.skipped_times[lane] = 0;
+            } else if !self.lanes[lane].is_empty() {
+                self.skipped_times[lane] += 1;
+            }
+        }
+
+        self.lanes[chosen].pop_front().map(|(id, owner_id)| QueuedTask {
+            id: id,
+            owner_id: owner_id,
+            priority: JudgePriority::from_lane(chosen),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(owner_id: i32, priority: JudgePriority) -> QueuedTask {
+        QueuedTask { id: Uuid::new_v4(), owner_id: owner_id, priority: priority }
+    }
+
+    fn pop_owners(queue: &mut JudgeQueue, count: usize) -> Vec<i32> {
+        (0..count).filter_map(|_| queue.pop_front()).map(|task| task.owner_id).collect()
+    }
+
+    #[test]
+    fn contest_lane_goes_first() {
+        let mut queue = JudgeQueue::new();
+        let practice = task(1, JudgePriority::Practice);
+        let contest = task(2, JudgePriority::Contest);
+        queue.push_back(practice);
+        queue.push_back(contest);
+
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.pop_front(), Some(contest));
+        assert_eq!(queue.pop_front(), Some(practice));
+        assert_eq!(queue.pop_front(), None);
+    }
+
+    #[test]
+    fn owners_take_turns_within_a_lane() {
+        let mut queue = JudgeQueue::new();
+        for _ in 0..3 {
+            queue.push_back(task(1, JudgePriority::Practice));
+        }
+        queue.push_back(task(2, JudgePriority::Practice));
+        queue.push_back(task(3, JudgePriority::Practice));
+
+        assert_eq!(pop_owners(&mut queue, 5), vec![1, 2, 3, 1, 1]);
+    }
+
+    #[test]
+    fn tasks_of_one_owner_keep_their_order() {
+        let mut queue = JudgeQueue::new();
+        let first = task(1, JudgePriority::Practice);
+        let second = task(1, JudgePriority::Practice);
+        queue.push_back(first);
+        queue.push_back(second);
+
+        assert_eq!(queue.pop_front(), Some(first));
+        assert_eq!(queue.pop_front(), Some(second));
+    }
+
+    #[test]
+    fn requeued_task_is_served_next() {
+        let mut queue = JudgeQueue::new();
+        queue.push_back(task(1, JudgePriority::Practice));
+        queue.push_back(task(2, JudgePriority::Practice));
+        let retried = task(2, JudgePriority::Practice);
+        queue.push_front(retried);
+
+        assert_eq!(queue.pop_front(), Some(retried));
+        assert_eq!(pop_owners(&mut queue, 2), vec![1, 2]);
+    }
+
+    #[test]
+    fn starving_lane_is_served_after_max_skipped_times() {
+        let mut queue = JudgeQueue::new();
+        for _ in 0..MAX_SKIPPED_TIMES * 2 {
+            queue.push_back(task(1, JudgePriority::Contest));
+        }
+        let bulk = task(2, JudgePriority::Bulk);
+        queue.push_back(bulk);
+
+        let popped: Vec<QueuedTask> = (0..MAX_SKIPPED_TIMES + 1).filter_map(|_| queue.pop_front()).collect();
+        assert!(popped[..MAX_SKIPPED_TIMES as usize].iter().all(|task| task.priority == JudgePriority::Contest));
+        assert_eq!(popped[MAX_SKIPPED_TIMES as usize], bulk);
+        assert_eq!(queue.pop_front().map(|task| task.priority), Some(JudgePriority::Contest));
+    }
+
+    #[test]
+    fn empty_lanes_are_not_counted_as_skipped() {
+        let mut queue = JudgeQueue::new();
+        for _ in 0..MAX_SKIPPED_TIMES * 2 {
+            queue.push_back(task(1, JudgePriority::Contest));
+        }
+        pop_owners(&mut queue, MAX_SKIPPED_TIMES as usize);
+        let run = task(2, JudgePriority::Run);
+        queue.push_back(run);
+
+        assert_eq!(queue.pop_front().map(|task| task.priority), Some(JudgePriority::Contest));
+    }
+
+    #[test]
+    fn clear_empties_every_lane() {
+        let mut queue = JudgeQueue::new();
+        queue.push_back(task(1, JudgePriority::Contest));
+        queue.push_back(task(2, JudgePriority::Run));
+        queue.clear();
+
+        assert_eq!(queue.len(), 0);
+        assert_eq!(queue.pop_front(), None);
+    }
+}
@@ -0,0 +1,100 @@
+use diesel::prelude::*;
+use crate::statics::WAITING_QUEUE;
+use std::collections::HashSet;
+use uuid::Uuid;
+use super::queue::{ JudgePriority, QueuedTask };
+use super::priority::{ get_running_contest_regions, get_priority_in };
+
+pub fn recover_waiting_queue(conn: &PgConnection) {
+    use crate::schema::{ status, rejudge_items };
+
+    // every judge task from last run is lost, so "Pending" can't be trusted anymore
+    let reset_count = diesel::update(status::table.filter(status::state.eq("Pending".to_owned())))
+        .set((
+            status::state.eq("Waiting".to_owned()),
+            status::start_pend_time.eq({ let tmp: Option<chrono::NaiveDateTime> = None; tmp }),
+            status::host_name.eq({ let tmp: Option<String> = None; tmp }),
+            status::judge_server_url.eq({ let tmp: Option<String> = None; tmp }),
+        ))
+        .execute(conn)
+        .expect("Error resetting pending status.");
+    info!("reset {} pending status to waiting", reset_count);
+
+    let waiting_status = status::table
+        .filter(status::state.eq("Waiting".to_owned()))
+        .order_by(status::submit_time.asc())
+        .select((status::id, status::owner_id, status::problem_region))
+        .load::<(Uuid, i32, String)>(conn)
+        .expect("Error loading waiting status.");
+
+    // a waiting status with rejudge items can only be in the middle of a rejudge
+    let rejudging_ids = rejudge_items::table
+        .inner_join(status::table.on(rejudge_items::status_id.eq(status::id)))
+        .filter(status::state.eq("Waiting".to_owned()))
+        .select(status::id)
+        .load::<Uuid>(conn)
+        .expect("Error loading rejudging status.")
+        .into_iter()
+        .collect::<HashSet<Uuid>>();
+    let running_contest_regions = get_running_contest_regions(conn)
+        .expect("Error loading running contests.");
+
+    let mut lock = WAITING_QUEUE.write().unwrap();
+    lock.clear();
+    for (id, owner_id, problem_region) in waiting_status {
+        let priority = if rejudging_ids.contains(&id) {
+            JudgePriority::Bulk
+        } else {
+            get_priority_in(&running_contest_regions, &problem_region)
+        };
+        lock.push_back(QueuedTask {
+            id: id,
+            owner_id: owner_id,
+            priority: priority,
+        });
+    }
+    info!("recovered {} status into waiting queue", lock.len());
+}
+
+/// Sends the statuses a lost judge server was running back to the queue, ahead of the others.
+/// Returns their ids.
+pub fn requeue_orphaned_tasks(conn: &PgConnection, service_url: &str) -> QueryResult<Vec<Uuid>> {
+    use crate::schema::{ status, rejudge_items };
+
+    let orphaned = diesel::update(status::table
+        .filter(status::state.eq("Pending".to_owned()))
+        .filter(status::judge_server_url.eq(service_url)))
+        .set((
+            status::state.eq("Waiting".to_owned()),
+            status::start_pend_time.eq(None::<chrono::NaiveDateTime>),
+            status::host_name.eq(None::<String>),
+            status::judge_server_url.eq(None::<String>),
+        ))
+        .returning((status::id, status::owner_id, status::problem_region))
+        .get_results::<(Uuid, i32, String)>(conn)?;
+
+    let ids: Vec<Uuid> = orphaned.iter().map(|(id, _, _)| *id).collect();
+    let rejudging_ids = rejudge_items::table
+        .filter(rejudge_items::status_id.eq_any(ids.clone()))
+        .select(rejudge_items::status_id)
+        .load::<Uuid>(conn)?
+        .into_iter()
+        .collect::<HashSet<Uuid>>();
+    let running_contest_regions = get_running_contest_regions(conn)?;
+
+    let mut lock = WAITING_QUEUE.write().unwrap();
+    for (id, owner_id, problem_region) in orphaned {
+        let priority = if rejudging_ids.contains(&id) {
+            JudgePriority::Bulk
+        } else {
+            get_priority_in(&running_contest_regions, &problem_region)
+        };
+        lock.push_front(QueuedTask {
+            id: id,
+            owner_id: owner_id,
+            priority: priority,
+        });
+    }
+
+    Ok(ids)
+}
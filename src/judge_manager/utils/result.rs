@@ -1,58 +1,272 @@
 use super::mapper::*;
-use crate::status::model::*;
-
-#[derive(Debug, Clone, Serialize)]
-pub struct TestCaseResult {
-    test_case: String,
-    result: String,
-    error: String,
-    cpu_time: i32,
-    real_time: i32,
-    memory: i32,
-    signal: i32,
-    exit_code: i32,
-    output_md5: Option<String>,
-    output: Option<String>,
-}
-
-pub fn get_judge_result(judge_type: String, result_str: String) -> (Option<String>, Option<f64>, Option<String>) {
-    let err_checker: ErrChecker = serde_json::from_str(&result_str).unwrap();
-    if err_checker.err.is_none() {
-        let judge_result: JudgeResult = serde_json::from_str(&result_str).unwrap();
-        let mut final_result = "Accepted".to_owned();
-        let mut total_test_cases = 0;
-        let mut passed_test_cases = 0;
-        let mut test_case_results: Vec<TestCaseResult> = Vec::new();
-        for judge_result_data in judge_result.data {
-            total_test_cases += 1;
-            test_case_results.push(TestCaseResult {
-                test_case: judge_result_data.test_case,
-                result: result_mapper(judge_result_data.result),
-                error: err_mapper(judge_result_data.error),
-                cpu_time: judge_result_data.cpu_time,
-                real_time: judge_result_data.real_time,
-                memory: judge_result_data.memory,
-                signal: judge_result_data.signal,
-                exit_code: judge_result_data.exit_code,
-                output_md5: judge_result_data.output_md5,
-                output: judge_result_data.output,
-            });
-            if result_mapper(judge_result_data.result) != "SUCCESS".to_owned() {
-                final_result = "Unaccepted".to_owned()
-            } else {
-                passed_test_cases += 1;
+use crate::judge_manager::model::JudgeResponse;
+use crate::status::model::{ JudgeResultData, SubtaskResult, Verdict, ErrResult };
+use crate::test_case::utils::subtask::{ Aggregation, Subtask };
+use std::collections::HashMap;
+
+/// Compiler output beyond this many bytes is cut off before it is stored.
+pub const MAX_COMPILE_ERROR_SIZE: usize = 16 * 1024;
+
+/// Strips terminal colors and carriage returns from compiler output and cuts it to `MAX_COMPILE_ERROR_SIZE`.
+pub fn normalize_compile_error(log: &str) -> String {
+    let mut normalized = String::with_capacity(log.len().min(MAX_COMPILE_ERROR_SIZE));
+    let mut chars = log.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            // CSI sequences such as the colors of -fdiagnostics-color end with a letter
+            '\u{1b}' if chars.peek() == Some(&'[') => {
+                for c in chars.by_ref() {
+                    if c.is_ascii_alphabetic() { break; }
+                }
+            },
+            '\r' => (),
+            _ => normalized.push(c),
+        }
+    }
+
+    if normalized.len() > MAX_COMPILE_ERROR_SIZE {
+        let mut end = MAX_COMPILE_ERROR_SIZE;
+        while !normalized.is_char_boundary(end) { end -= 1; }
+        normalized.truncate(end);
+        normalized.push_str("\n... (truncated)");
+    }
+    normalized
+}
+
+/// Compile errors are the contestant's own, so they are recognized whatever backend reported them.
+pub fn is_compile_error(err_result: &ErrResult) -> bool {
+    err_result.err.as_deref() == Some("CompileError")
+}
+
+/// Share of one test case earned, checkers may give a part of it.
+fn get_test_case_share(judge_result_data: &JudgeResultData) -> f64 {
+    match judge_result_data.score {
+        Some(score) => score.max(0.0).min(1.0),
+        None => if result_mapper(judge_result_data.result) == "SUCCESS".to_owned() { 1.0 } else { 0.0 },
+    }
+}
+
+/// Scores every subtask on the scale of `max_score`, in the order they are listed.
+pub fn get_subtask_results(subtasks: &[Subtask], data: &[JudgeResultData], max_score: i32) -> Vec<SubtaskResult> {
+    let total_weight: f64 = subtasks.iter().map(|subtask| subtask.score).sum();
+    let mut shares: HashMap<i32, f64> = HashMap::new();
+    let mut results = Vec::new();
+    for subtask in subtasks.iter() {
+        // a test case missing from the result earns nothing
+        let test_case_shares: Vec<f64> = subtask.test_cases.iter()
+            .map(|name| data.iter()
+                .find(|judge_result_data| &judge_result_data.test_case == name)
+                .map(get_test_case_share)
+                .unwrap_or(0.0))
+            .collect();
+        // uploads reject empty subtasks, but an edited info file may still have one
+        let mut share = match subtask.aggregation {
+            _ if test_case_shares.is_empty() => 0.0,
+            Aggregation::Min => test_case_shares.iter().cloned().fold(1.0, f64::min),
+            Aggregation::Sum => test_case_shares.iter().sum::<f64>() / test_case_shares.len() as f64,
+        };
+        for dependency in subtask.dependencies.iter() {
+            share = share.min(shares.get(dependency).cloned().unwrap_or(0.0));
+        }
+        shares.insert(subtask.id, share);
+
+        let full_score = if total_weight > 0.0 { max_score as f64 * subtask.score / total_weight } else { 0.0 };
+        results.push(SubtaskResult {
+            id: subtask.id,
+            score: full_score * share,
+            full_score: full_score,
+        });
+    }
+    results
+}
+
+/// What a status shows at a glance, the verdict comes from the first failing test case.
+#[derive(Debug, Clone)]
+pub struct VerdictSummary {
+    pub verdict: Verdict,
+    pub test_case: Option<String>,
+    pub max_time: Option<i32>,
+    pub max_memory: Option<i32>,
+}
+
+pub fn get_verdict_summary(response: &JudgeResponse) -> VerdictSummary {
+    match response {
+        JudgeResponse::Finished(judge_result) => {
+            let first_failure = judge_result.data.iter()
+                .find(|judge_result_data| judge_result_data.result != 0);
+            VerdictSummary {
+                verdict: first_failure.map(|data| Verdict::from_result(data.result)).unwrap_or(Verdict::AC),
+                test_case: first_failure.map(|data| data.test_case.clone()),
+                max_time: judge_result.data.iter().map(|data| data.cpu_time).max(),
+                max_memory: judge_result.data.iter().map(|data| data.memory).max(),
+            }
+        },
+        JudgeResponse::Failed(err_result) => VerdictSummary {
+            verdict: if is_compile_error(err_result) { Verdict::CE } else { Verdict::SE },
+            test_case: None,
+            max_time: None,
+            max_memory: None,
+        },
+    }
+}
+
+pub fn get_judge_result(judge_type: String, response: JudgeResponse, max_score: i32) -> (Option<String>, Option<f64>, Option<String>) {
+    match response {
+        JudgeResponse::Finished(judge_result) => {
+            let mut final_result = "Accepted".to_owned();
+            let mut total_test_cases = 0;
+            let mut passed_share = 0.0;
+            for judge_result_data in judge_result.data.iter() {
+                total_test_cases += 1;
+                passed_share += get_test_case_share(judge_result_data);
+                if result_mapper(judge_result_data.result) != "SUCCESS".to_owned() {
+                    final_result = "Unaccepted".to_owned()
+                }
+            }
+            match judge_type.as_str() {
+                "OI" => {
+                    let score = match judge_result.subtasks {
+                        Some(subtasks) => subtasks.iter().map(|subtask| subtask.score).sum::<f64>(),
+                        None if total_test_cases == 0 => 0.0,
+                        None => max_score as f64 * (passed_share / total_test_cases as f64),
+                    };
+                    (None, Some(score), None)
+                },
+                _ => { (Some(final_result), None, None) },
             }
+        },
+        JudgeResponse::Failed(err_result) => {
+            (
+                Some(err_result.err.unwrap_or("System Error".to_owned())),
+                None,
+                Some(err_result.data),
+            )
+        },
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::status::model::JudgeResult;
+
+    fn data(test_case: &str, result: i32, score: Option<f64>) -> JudgeResultData {
+        JudgeResultData {
+            cpu_time: 10,
+            real_time: 20,
+            memory: 1024,
+            signal: 0,
+            exit_code: 0,
+            error: 0,
+            result: result,
+            test_case: test_case.to_owned(),
+            output_md5: None,
+            output: None,
+            score: score,
+            stderr: None,
         }
-        match judge_type.as_str() {
-            "OI" => { (None, Some(100.0 * (passed_test_cases as f64 / total_test_cases as f64)), None) },
-            _ => { (Some(final_result), None, None) },
+    }
+
+    fn subtask(id: i32, score: f64, aggregation: Aggregation, test_cases: &[&str], dependencies: &[i32]) -> Subtask {
+        Subtask {
+            id: id,
+            score: score,
+            aggregation: aggregation,
+            test_cases: test_cases.iter().map(|name| name.to_string()).collect(),
+            dependencies: dependencies.to_vec(),
         }
-    } else {
-        let err_result: ErrResult = serde_json::from_str(&result_str).unwrap();
-        (
-            Some(err_result.err.unwrap()),
-            None,
-            Some(err_result.data),
-        )
-    }
-}
\ No newline at end of file
+    }
+
+    fn scores(results: &[SubtaskResult]) -> Vec<(f64, f64)> {
+        results.iter().map(|result| (result.score, result.full_score)).collect()
+    }
+
+    #[test]
+    fn min_subtask_is_decided_by_its_worst_test_case() {
+        let subtasks = [subtask(1, 1.0, Aggregation::Min, &["1", "2"], &[])];
+        let data = [data("1", 0, None), data("2", -1, None)];
+        assert_eq!(scores(&get_subtask_results(&subtasks, &data, 100)), vec![(0.0, 100.0)]);
+    }
+
+    #[test]
+    fn sum_subtask_counts_each_test_case_and_partial_scores() {
+        let subtasks = [subtask(1, 1.0, Aggregation::Sum, &["1", "2", "3", "4"], &[])];
+        let data = [data("1", 0, None), data("2", -1, None), data("3", -1, Some(0.5)), data("4", 0, Some(1.0))];
+        assert_eq!(scores(&get_subtask_results(&subtasks, &data, 100)), vec![(62.5, 100.0)]);
+    }
+
+    #[test]
+    fn weights_are_scaled_to_max_score() {
+        let subtasks = [
+            subtask(1, 1.0, Aggregation::Min, &["1"], &[]),
+            subtask(2, 3.0, Aggregation::Min, &["2"], &[]),
+        ];
+        let data = [data("1", 0, None), data("2", 0, None)];
+        assert_eq!(scores(&get_subtask_results(&subtasks, &data, 200)), vec![(50.0, 50.0), (150.0, 150.0)]);
+    }
+
+    #[test]
+    fn dependencies_cap_the_share() {
+        let subtasks = [
+            subtask(1, 1.0, Aggregation::Sum, &["1", "2"], &[]),
+            subtask(2, 1.0, Aggregation::Min, &["3"], &[1]),
+        ];
+        let data = [data("1", 0, None), data("2", -1, None), data("3", 0, None)];
+        assert_eq!(scores(&get_subtask_results(&subtasks, &data, 100)), vec![(25.0, 50.0), (25.0, 50.0)]);
+    }
+
+    #[test]
+    fn missing_test_cases_earn_nothing() {
+        let subtasks = [subtask(1, 1.0, Aggregation::Sum, &["1", "2"], &[])];
+        let data = [data("1", 0, None)];
+        assert_eq!(scores(&get_subtask_results(&subtasks, &data, 100)), vec![(50.0, 100.0)]);
+    }
+
+    #[test]
+    fn empty_subtasks_and_zero_weights_score_nothing() {
+        let subtasks = [subtask(1, 1.0, Aggregation::Sum, &[], &[])];
+        assert_eq!(scores(&get_subtask_results(&subtasks, &[], 100)), vec![(0.0, 100.0)]);
+
+        let subtasks = [subtask(1, 0.0, Aggregation::Min, &["1"], &[])];
+        assert_eq!(scores(&get_subtask_results(&subtasks, &[data("1", 0, None)], 100)), vec![(0.0, 0.0)]);
+    }
+
+    #[test]
+    fn oi_score_without_subtasks_follows_passed_share() {
+        let response = JudgeResponse::Finished(JudgeResult {
+            err: None,
+            data: vec![data("1", 0, None), data("2", -1, Some(0.5)), data("3", -1, None), data("4", 0, None)],
+            subtasks: None,
+        });
+        assert_eq!(get_judge_result("OI".to_owned(), response, 100), (None, Some(62.5), None));
+
+        let empty = JudgeResponse::Finished(JudgeResult { err: None, data: Vec::new(), subtasks: None });
+        assert_eq!(get_judge_result("OI".to_owned(), empty, 100), (None, Some(0.0), None));
+    }
+
+    #[test]
+    fn verdict_comes_from_the_first_failure() {
+        let response = JudgeResponse::Finished(JudgeResult {
+            err: None,
+            data: vec![data("1", 0, None), data("2", 1, None), data("3", -1, None)],
+            subtasks: None,
+        });
+        let summary = get_verdict_summary(&response);
+        assert_eq!(summary.verdict, Verdict::TLE);
+        assert_eq!(summary.test_case.as_deref(), Some("2"));
+        assert_eq!(summary.max_time, Some(10));
+    }
+
+    #[test]
+    fn compile_error_is_stripped_of_colors_and_carriage_returns() {
+        let log = "\u{1b}[01m\u{1b}[Kmain.c:1:1:\u{1b}[m\u{1b}[K \u{1b}[01;31merror\u{1b}[0m: oops\r\n";
+        assert_eq!(normalize_compile_error(log), "main.c:1:1: error: oops\n");
+    }
+
+    #[test]
+    fn long_compile_error_is_truncated_on_a_char_boundary() {
+        let log = "é".repeat(MAX_COMPILE_ERROR_SIZE);
+        let normalized = normalize_compile_error(&log);
+        assert!(normalized.ends_with("\n... (truncated)"));
+        assert!(normalized.len() <= MAX_COMPILE_ERROR_SIZE + "\n... (truncated)".len());
+    }
+}
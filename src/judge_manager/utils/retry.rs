@@ -0,0 +1,28 @@
+use actix::prelude::*;
+use std::time::Duration;
+use crate::statics::{ WAITING_QUEUE, JUDGE_RETRY_BASE_DELAY };
+use crate::judge_manager::{
+    JudgeManager,
+    handler::StartJudge,
+};
+use super::queue::QueuedTask;
+
+pub fn get_retry_delay(retry_times: i32) -> Duration {
+    let exponent = (retry_times.max(1) - 1).min(16) as u32;
+    Duration::from_secs(*JUDGE_RETRY_BASE_DELAY * 2u64.pow(exponent))
+}
+
+/// Puts the task back to the queue once the delay is over,
+/// without holding the *JudgeManager* thread meanwhile.
+pub fn requeue_later(jm: Addr<JudgeManager>, task: QueuedTask, delay: Duration) {
+    System::current().arbiter().exec_fn(move || {
+        actix_rt::spawn(async move {
+            actix_rt::time::delay_for(delay).await;
+            {
+                let mut lock = WAITING_QUEUE.write().unwrap();
+                lock.push_front(task);
+            }
+            jm.do_send(StartJudge());
+        });
+    });
+}
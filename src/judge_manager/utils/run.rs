@@ -0,0 +1,158 @@
+use futures::channel::oneshot::{ channel, Receiver, Sender };
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{ Duration, Instant };
+use uuid::Uuid;
+use crate::judge_manager::model::JudgeResponse;
+use crate::statics::JUDGE_RUN_TIMEOUT;
+
+/// Polled results are dropped this long after the run finished.
+const RUN_RESULT_TTL: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Clone)]
+pub enum RunState {
+    Waiting,
+    Running,
+    Finished(Result<JudgeResponse, String>),
+}
+
+/// A custom input run, which has no status to hold its setting nor its result.
+struct PendingRun {
+    owner_id: i32,
+    setting: String,
+    /// `None` if the owner polls for the result instead of waiting for it
+    sender: Option<Sender<Result<JudgeResponse, String>>>,
+    state: RunState,
+    since: Instant,
+}
+
+impl PendingRun {
+    fn is_in_flight(&self) -> bool {
+        match self.state {
+            RunState::Finished(_) => false,
+            _ => true,
+        }
+    }
+}
+
+/// Runs from being queued until they are finished, or their polled result expires.
+pub struct RunRegistry {
+    runs: Mutex<HashMap<Uuid, PendingRun>>,
+}
+
+impl RunRegistry {
+    pub fn new() -> Self {
+        RunRegistry { runs: Mutex::new(HashMap::new()) }
+    }
+
+    /// Gives up polled runs no judge took in time, queued runs nobody waits for
+    /// and expired results.
+    fn prune(runs: &mut HashMap<Uuid, PendingRun>) {
+        let wait_timeout = Duration::from_secs(*JUDGE_RUN_TIMEOUT);
+        for run in runs.values_mut() {
+            if let RunState::Waiting = run.state {
+                if run.sender.is_none() && run.since.elapsed() > wait_timeout {
+                    run.state = RunState::Finished(Err("No judge was free to run the code in time.".to_owned()));
+                    run.since = Instant::now();
+                }
+            }
+        }
+        runs.retain(|_, run| match run.state {
+            RunState::Waiting => !run.sender.as_ref().map_or(false, |sender| sender.is_canceled()),
+            RunState::Running => true,
+            RunState::Finished(_) => run.since.elapsed() < RUN_RESULT_TTL,
+        });
+    }
+
+    /// `false` if the owner already has `limit` runs in flight, there is no limit unless it's positive.
+    fn insert(
+        &self,
+        id: Uuid,
+        owner_id: i32,
+        setting: String,
+        sender: Option<Sender<Result<JudgeResponse, String>>>,
+        limit: i64,
+    ) -> bool {
+        let mut runs = self.runs.lock().unwrap();
+        Self::prune(&mut runs);
+        let in_flight = runs.values().filter(|run| run.owner_id == owner_id && run.is_in_flight()).count();
+        if limit > 0 && in_flight as i64 >= limit {
+            return false;
+        }
+        runs.insert(id, PendingRun {
+            owner_id: owner_id,
+            setting: setting,
+            sender: sender,
+            state: RunState::Waiting,
+            since: Instant::now(),
+        });
+        true
+    }
+
+    /// The receiver gets the judge's answer once the run is done.
+    pub fn add(&self, id: Uuid, owner_id: i32, setting: String, limit: i64) -> Option<Receiver<Result<JudgeResponse, String>>> {
+        let (sender, receiver) = channel();
+        if self.insert(id, owner_id, setting, Some(sender), limit) { Some(receiver) } else { None }
+    }
+
+    /// The answer is kept for `poll`.
+    pub fn add_polled(&self, id: Uuid, owner_id: i32, setting: String, limit: i64) -> bool {
+        self.insert(id, owner_id, setting, None, limit)
+    }
+
+    /// The setting of a run about to be sent to a judge, `None` if nobody waits for it anymore.
+    pub fn take(&self, id: Uuid) -> Option<String> {
+        let mut runs = self.runs.lock().unwrap();
+        let canceled = match runs.get(&id) {
+            Some(run) => match run.state {
+                RunState::Waiting => run.sender.as_ref().map_or(false, |sender| sender.is_canceled()),
+                _ => return None,
+            },
+            None => return None,
+        };
+        if canceled {
+            runs.remove(&id);
+            return None;
+        }
+        runs.get_mut(&id).map(|run| {
+            run.state = RunState::Running;
+            run.since = Instant::now();
+            std::mem::replace(&mut run.setting, String::new())
+        })
+    }
+
+    /// Drops a run still in the queue, one already sent to a judge counts until it finishes.
+    pub fn cancel(&self, id: Uuid) {
+        let mut runs = self.runs.lock().unwrap();
+        if matches!(runs.get(&id).map(|run| &run.state), Some(RunState::Waiting)) {
+            runs.remove(&id);
+        }
+    }
+
+    pub fn finish(&self, id: Uuid, result: Result<JudgeResponse, String>) {
+        let mut runs = self.runs.lock().unwrap();
+        let sender = match runs.get_mut(&id) {
+            Some(run) => run.sender.take(),
+            None => return,
+        };
+        match sender {
+            Some(sender) => {
+                runs.remove(&id);
+                let _ = sender.send(result);
+            },
+            None => {
+                if let Some(run) = runs.get_mut(&id) {
+                    run.state = RunState::Finished(result);
+                    run.since = Instant::now();
+                }
+            },
+        }
+    }
+
+    /// `None` if there is no such run of the owner, or its result expired.
+    pub fn poll(&self, id: Uuid, owner_id: i32) -> Option<RunState> {
+        let mut runs = self.runs.lock().unwrap();
+        Self::prune(&mut runs);
+        runs.get(&id).filter(|run| run.owner_id == owner_id).map(|run| run.state.clone())
+    }
+}
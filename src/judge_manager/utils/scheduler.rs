@@ -0,0 +1,162 @@
+use crate::judge_server::model::JudgeServerInfo;
+use std::sync::atomic::{ AtomicUsize, Ordering };
+
+/// Decides which of the available judge servers gets the next task.
+/// `candidates` only contains servers which are alive and have a free slot.
+pub trait SchedulingPolicy: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn choose<'a>(&self, candidates: &[&'a JudgeServerInfo]) -> Option<&'a JudgeServerInfo>;
+}
+
+fn slot_usage(info: &JudgeServerInfo) -> f32 {
+    info.task_number as f32 / info.capacity().max(1) as f32
+}
+
+/// Prefers the server with the lowest combined slot usage, reported cpu and memory load.
+pub struct LeastLoaded;
+
+impl LeastLoaded {
+    fn load(info: &JudgeServerInfo) -> f32 {
+        // cpu and memory are reported as percentages
+        0.5 * slot_usage(info) + 0.3 * (info.cpu / 100.0) + 0.2 * (info.memory / 100.0)
+    }
+}
+
+impl SchedulingPolicy for LeastLoaded {
+    fn name(&self) -> &'static str { "least_loaded" }
+
+    fn choose<'a>(&self, candidates: &[&'a JudgeServerInfo]) -> Option<&'a JudgeServerInfo> {
+        candidates.iter()
+            .min_by(|a, b| {
+                LeastLoaded::load(a).partial_cmp(&LeastLoaded::load(b))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+                    .then_with(|| a.service_url.cmp(&b.service_url))
+            })
+            .map(|info| *info)
+    }
+}
+
+/// Hands out tasks in proportion to the number of cpu cores of each server.
+pub struct WeightedByCores;
+
+impl SchedulingPolicy for WeightedByCores {
+    fn name(&self) -> &'static str { "weighted_by_cores" }
+
+    fn choose<'a>(&self, candidates: &[&'a JudgeServerInfo]) -> Option<&'a JudgeServerInfo> {
+        candidates.iter()
+            .min_by(|a, b| {
+                slot_usage(a).partial_cmp(&slot_usage(b))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+                    .then_with(|| b.cpu_core.cmp(&a.cpu_core))
+                    .then_with(|| a.service_url.cmp(&b.service_url))
+            })
+            .map(|info| *info)
+    }
+}
+
+/// Cycles through the servers ordered by their service url.
+pub struct RoundRobin {
+    cursor: AtomicUsize,
+}
+
+impl RoundRobin {
+    pub fn new() -> Self {
+        RoundRobin { cursor: AtomicUsize::new(0) }
+    }
+}
+
+impl SchedulingPolicy for RoundRobin {
+    fn name(&self) -> &'static str { "round_robin" }
+
+    fn choose<'a>(&self, candidates: &[&'a JudgeServerInfo]) -> Option<&'a JudgeServerInfo> {
+        if candidates.is_empty() { return None; }
+        let mut sorted = candidates.to_vec();
+        sorted.sort_by(|a, b| a.service_url.cmp(&b.service_url));
+        let index = self.cursor.fetch_add(1, Ordering::Relaxed) % sorted.len();
+        Some(sorted[index])
+    }
+}
+
+pub fn get_scheduling_policy(name: &str) -> Result<Box<dyn SchedulingPolicy>, String> {
+    match name {
+        "least_loaded" => Ok(Box::new(LeastLoaded)),
+        "weighted_by_cores" => Ok(Box::new(WeightedByCores)),
+        "round_robin" => Ok(Box::new(RoundRobin::new())),
+        _ => Err(format!(
+            "Unknown judge scheduling policy \"{}\", expected least_loaded, weighted_by_cores or round_robin.", name
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::SystemTime;
+
+    fn server(service_url: &str, cpu_core: i32, task_number: i32, cpu: f32, memory: f32) -> JudgeServerInfo {
+        JudgeServerInfo {
+            judger_version: "2.1.1".to_owned(),
+            hostname: service_url.to_owned(),
+            cpu_core: cpu_core,
+            memory: memory,
+            cpu: cpu,
+            task_number: task_number,
+            service_url: service_url.to_owned(),
+            token: String::new(),
+            heartbeat_time: SystemTime::now(),
+            is_deprecated: false,
+        }
+    }
+
+    fn chosen_url(policy: &dyn SchedulingPolicy, candidates: &[&JudgeServerInfo]) -> Option<String> {
+        policy.choose(candidates).map(|info| info.service_url.clone())
+    }
+
+    #[test]
+    fn least_loaded_prefers_free_slots_and_low_load() {
+        let busy = server("http://scheduler-test-busy", 4, 6, 10.0, 10.0);
+        let idle = server("http://scheduler-test-idle", 4, 1, 10.0, 10.0);
+        assert_eq!(chosen_url(&LeastLoaded, &[&busy, &idle]), Some(idle.service_url.clone()));
+
+        let hot = server("http://scheduler-test-hot", 4, 1, 95.0, 90.0);
+        assert_eq!(chosen_url(&LeastLoaded, &[&hot, &idle]), Some(idle.service_url.clone()));
+    }
+
+    #[test]
+    fn weighted_by_cores_breaks_ties_for_more_cores() {
+        let small = server("http://scheduler-test-small", 2, 1, 0.0, 0.0);
+        let large = server("http://scheduler-test-large", 8, 4, 0.0, 0.0);
+        assert_eq!(chosen_url(&WeightedByCores, &[&small, &large]), Some(large.service_url.clone()));
+
+        let loaded = server("http://scheduler-test-loaded", 8, 12, 0.0, 0.0);
+        assert_eq!(chosen_url(&WeightedByCores, &[&small, &loaded]), Some(small.service_url.clone()));
+    }
+
+    #[test]
+    fn round_robin_cycles_in_url_order() {
+        let a = server("http://scheduler-test-a", 1, 0, 0.0, 0.0);
+        let b = server("http://scheduler-test-b", 1, 0, 0.0, 0.0);
+        let c = server("http://scheduler-test-c", 1, 0, 0.0, 0.0);
+        let policy = RoundRobin::new();
+        let chosen: Vec<String> = (0..4).filter_map(|_| chosen_url(&policy, &[&c, &a, &b])).collect();
+
+        assert_eq!(chosen, vec![
+            a.service_url.clone(),
+            b.service_url.clone(),
+            c.service_url.clone(),
+            a.service_url.clone(),
+        ]);
+    }
+
+    #[test]
+    fn no_candidate_is_no_choice() {
+        for name in &["least_loaded", "weighted_by_cores", "round_robin"] {
+            assert!(get_scheduling_policy(name).unwrap().choose(&[]).is_none());
+        }
+    }
+
+    #[test]
+    fn unknown_policy_is_rejected() {
+        assert!(get_scheduling_policy("random").is_err());
+    }
+}
@@ -0,0 +1,57 @@
+use actix::prelude::*;
+use std::time::{ Duration, SystemTime };
+use crate::statics::{ JUDGE_SERVER_INFOS, JUDGE_HEARTBEAT_TIMEOUT, JUDGE_SUPERVISOR_INTERVAL };
+use crate::judge_server::service::ping::ping_judge_server;
+use crate::judge_manager::{
+    JudgeManager,
+    handler::{ StartJudge, RequeueJudgeServerTasks },
+};
+
+fn is_silent(heartbeat_time: SystemTime) -> bool {
+    heartbeat_time.elapsed().map(|elapsed| elapsed.as_secs() > *JUDGE_HEARTBEAT_TIMEOUT).unwrap_or(false)
+}
+
+/// Pings the judge servers which went silent or failed a ping,
+/// evicting the silent ones which don't answer and taking back the ones which recovered.
+async fn check_judge_servers(jm: &Addr<JudgeManager>) {
+    let targets: Vec<(String, String, bool)> = {
+        let lock = JUDGE_SERVER_INFOS.read().unwrap();
+        lock.values()
+            .filter(|info| info.is_deprecated || is_silent(info.heartbeat_time))
+            .map(|info| (info.service_url.clone(), info.token.clone(), is_silent(info.heartbeat_time)))
+            .collect()
+    };
+
+    for (service_url, token, is_silent) in targets {
+        match ping_judge_server(&service_url, &token).await {
+            Ok(()) => {
+                let mut lock = JUDGE_SERVER_INFOS.write().unwrap();
+                if let Some(info) = lock.get_mut(&service_url) {
+                    if info.is_deprecated {
+                        info!("judge server {} answers ping again", service_url);
+                        info.is_deprecated = false;
+                        jm.do_send(StartJudge());
+                    }
+                }
+            },
+            Err(e) if is_silent => {
+                warn!("evicting judge server {}: {}", service_url, e);
+                JUDGE_SERVER_INFOS.write().unwrap().remove(&service_url);
+                jm.do_send(RequeueJudgeServerTasks { service_url: service_url });
+            },
+            Err(e) => {
+                info!("judge server {} is still deprecated: {}", service_url, e);
+            },
+        }
+    }
+}
+
+/// Keeps checking the judge servers in the background, for as long as the system runs.
+pub fn start_judge_supervisor(jm: Addr<JudgeManager>) {
+    actix_rt::spawn(async move {
+        loop {
+            actix_rt::time::delay_for(Duration::from_secs(*JUDGE_SUPERVISOR_INTERVAL)).await;
+            check_judge_servers(&jm).await;
+        }
+    });
+}
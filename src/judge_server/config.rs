@@ -1,3 +1,7 @@
+use std::fs;
+use std::path::Path;
+use crate::statics::LANGUAGES;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompileConfig {
     pub src_name: String, 
@@ -39,135 +43,229 @@ pub struct SpjCompileConfig {
     pub compile_command: String,
 }
 
-fn default_env() -> Vec<String> {
-    vec!["LANG=en_US.UTF-8".to_owned(), "LANGUAGE=en_US:en".to_owned(), "LC_ALL=en_US.UTF-8".to_owned()]
+/// How a special judge or an interactor written in a language is compiled and run.
+/// `{spj_version}` in the file names is replaced by the judge server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpjDefinition {
+    pub src_name: String,
+    pub exe_name: String,
+    pub max_cpu_time: i32,
+    pub max_real_time: i32,
+    pub max_memory: i32,
+    pub compile_command: String,
+    pub command: String,
+    pub seccomp_rule: String,
 }
 
-fn c_lang_config() -> LanguageConfig {
-    LanguageConfig {
-        compile: CompileConfig {
-            src_name: "main.c".to_owned(),
-            exe_name: "main".to_owned(),
-            max_cpu_time: 3000,
-            max_real_time: 5000,
-            max_memory: 128 * 1024 * 1024,
-            compile_command: "/usr/bin/gcc -DONLINE_JUDGE -O2 -w -fmax-errors=3 -std=c99 {src_path} -lm -o {exe_path}".to_owned(),
-        },
-        run: RunConfig {
-            command: "{exe_path}".to_owned(),
-            seccomp_rule: Some("c_cpp".to_owned()),
-            env: default_env(),
-            memory_limit_check_only: 0,
+impl SpjDefinition {
+    pub fn compile_config(&self) -> SpjCompileConfig {
+        SpjCompileConfig {
+            src_name: self.src_name.clone(),
+            exe_name: self.exe_name.clone(),
+            max_cpu_time: self.max_cpu_time,
+            max_real_time: self.max_real_time,
+            max_memory: self.max_memory,
+            compile_command: self.compile_command.clone(),
         }
     }
-}
 
-pub fn c_lang_spj_compile() -> SpjCompileConfig {
-    SpjCompileConfig {
-        src_name: "spj-{spj_version}.c".to_owned(),
-        exe_name: "spj-{spj_version}".to_owned(),
-        max_cpu_time: 3000,
-        max_real_time: 5000,
-        max_memory: 1024 * 1024 * 1024,
-        compile_command: "/usr/bin/gcc -DONLINE_JUDGE -O2 -w -fmax-errors=3 -std=c99 {src_path} -lm -o {exe_path}".to_owned(),
+    pub fn config(&self) -> SpjConfig {
+        SpjConfig {
+            exe_name: self.exe_name.clone(),
+            command: self.command.clone(),
+            seccomp_rule: self.seccomp_rule.clone(),
+        }
     }
-}
 
-pub fn c_lang_spj_config() -> SpjConfig {
-    SpjConfig {
-        exe_name: "spj-{spj_version}".to_owned(),
-        command: "{exe_path} {in_file_path} {user_out_file_path}".to_owned(),
-        seccomp_rule: "c_cpp".to_owned()
+    /// Name of a checker or interactor source in an uploaded test case,
+    /// e.g. `spj_src.cpp` for the stem `spj_src`.
+    pub fn upload_name(&self, stem: &str) -> String {
+        match Path::new(&self.src_name).extension() {
+            Some(extension) => format!("{}.{}", stem, extension.to_string_lossy()),
+            None => stem.to_owned(),
+        }
     }
 }
 
-fn cpp_lang_config() -> LanguageConfig {
-    LanguageConfig {
-        compile: CompileConfig {
-            src_name: "main.cpp".to_owned(),
-            exe_name: "main".to_owned(),
-            max_cpu_time: 3000,
-            max_real_time: 5000,
-            max_memory: 128 * 1024 * 1024,
-            compile_command: "/usr/bin/g++ -DONLINE_JUDGE -O2 -w -fmax-errors=3 -std=c++11 {src_path} -lm -o {exe_path}".to_owned(),
-        },
-        run: RunConfig {
-            command: "{exe_path}".to_owned(),
-            seccomp_rule: Some("c_cpp".to_owned()),
-            env: default_env(),
-            memory_limit_check_only: 0,
-        }
-    }
+fn default_multiplier() -> f64 { 1.0 }
+
+/// One entry of the language config file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LanguageDefinition {
+    pub name: String,
+    pub display_name: String,
+    pub compile: CompileConfig,
+    pub run: RunConfig,
+    #[serde(default = "default_multiplier")]
+    pub time_multiplier: f64,
+    #[serde(default = "default_multiplier")]
+    pub memory_multiplier: f64,
+    /// set if special judges may be written in this language
+    #[serde(default)]
+    pub spj: Option<SpjDefinition>,
 }
 
-fn java_lang_config() -> LanguageConfig {
-    LanguageConfig {
-        compile: CompileConfig {
-            src_name: "Main.java".to_owned(),
-            exe_name: "Main".to_owned(),
-            max_cpu_time: 5000,
-            max_real_time: 10000,
-            max_memory: -1,
-            compile_command: "/usr/bin/javac {src_path} -d {exe_dir} -encoding UTF8".to_owned(),
-        },
-        run: RunConfig {
-            command: "/usr/bin/java -cp {exe_dir} -XX:MaxRAM={max_memory}k -Djava.security.manager -Dfile.encoding=UTF-8 -Djava.security.policy==/etc/java_policy -Djava.awt.headless=true Main".to_owned(),
-            seccomp_rule: None,
-            env: default_env(),
-            memory_limit_check_only: 1,
+impl LanguageDefinition {
+    pub fn config(&self) -> LanguageConfig {
+        LanguageConfig {
+            compile: self.compile.clone(),
+            run: self.run.clone(),
         }
     }
-}
 
-fn py2_lang_config() -> LanguageConfig {
-    LanguageConfig {
-        compile: CompileConfig {
-            src_name: "solution.py".to_owned(),
-            exe_name: "solution.pyc".to_owned(),
-            max_cpu_time: 3000,
-            max_real_time: 5000,
-            max_memory: 128 * 1024 * 1024,
-            compile_command: "/usr/bin/python -m py_compile {src_path}".to_owned(),
-        },
-        run: RunConfig {
-            command: "/usr/bin/python {exe_path}".to_owned(),
-            seccomp_rule: Some("general".to_owned()),
-            env: default_env(),
-            memory_limit_check_only: 0,
+    fn validate(&self) -> Result<(), String> {
+        let fail = |reason: &str| Err(format!("Language \"{}\": {}", self.name, reason));
+
+        if self.name.trim().is_empty() { return fail("name is empty"); }
+        if self.compile.src_name.trim().is_empty() { return fail("compile.src_name is empty"); }
+        if self.compile.exe_name.trim().is_empty() { return fail("compile.exe_name is empty"); }
+        if !self.compile.compile_command.contains("{src_path}") {
+            return fail("compile.compile_command doesn't use {src_path}");
+        }
+        if self.run.command.trim().is_empty() { return fail("run.command is empty"); }
+        if self.compile.max_cpu_time <= 0 || self.compile.max_real_time <= 0 {
+            return fail("compile time limits must be positive");
+        }
+        if self.compile.max_memory <= 0 && self.compile.max_memory != -1 {
+            return fail("compile.max_memory must be positive or -1");
         }
+        if self.run.memory_limit_check_only != 0 && self.run.memory_limit_check_only != 1 {
+            return fail("run.memory_limit_check_only must be 0 or 1");
+        }
+        if let Some(pair) = self.run.env.iter().find(|pair| !pair.contains('=')) {
+            return Err(format!("Language \"{}\": env \"{}\" is not KEY=VALUE", self.name, pair));
+        }
+        if !(self.time_multiplier > 0.0) || !(self.memory_multiplier > 0.0) {
+            return fail("multipliers must be positive");
+        }
+        if let Some(spj) = &self.spj {
+            if !spj.src_name.contains("{spj_version}") || !spj.exe_name.contains("{spj_version}") {
+                return fail("spj.src_name and spj.exe_name must use {spj_version}");
+            }
+            if !spj.compile_command.contains("{src_path}") { return fail("spj.compile_command doesn't use {src_path}"); }
+            if !spj.command.contains("{exe_path}") { return fail("spj.command doesn't use {exe_path}"); }
+            if spj.max_cpu_time <= 0 || spj.max_real_time <= 0 || spj.max_memory <= 0 {
+                return fail("spj limits must be positive");
+            }
+        }
+        Ok(())
     }
 }
 
-fn py3_lang_config() -> LanguageConfig {
-    LanguageConfig {
-        compile: CompileConfig {
-            src_name: "solution.py".to_owned(),
-            exe_name: "__pycache__/solution.cpython-36.pyc".to_owned(),
-            max_cpu_time: 3000,
-            max_real_time: 5000,
-            max_memory: 128 * 1024 * 1024,
-            compile_command: "/usr/bin/python3 -m py_compile {src_path}".to_owned(),
-        },
-        run: RunConfig {
-            command: "/usr/bin/python3 {exe_path}".to_owned(),
-            seccomp_rule: Some("general".to_owned()),
-            env: {
-                let mut default_env = default_env();
-                default_env.push("PYTHONIOENCODING=UTF-8".to_owned());
-                default_env
-            },
-            memory_limit_check_only: 0,
+/// Every language users may submit in, loaded from the language config file.
+#[derive(Debug, Clone)]
+pub struct LanguageRegistry {
+    languages: Vec<LanguageDefinition>,
+}
+
+impl LanguageRegistry {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Error reading language config {}: {}", path, e))?;
+        let languages: Vec<LanguageDefinition> = serde_json::from_str(&content)
+            .map_err(|e| format!("Malformed language config {}: {}", path, e))?;
+
+        if languages.is_empty() {
+            return Err(format!("Language config {} has no language", path));
         }
+        for (index, language) in languages.iter().enumerate() {
+            language.validate()?;
+            if languages[..index].iter().any(|other| other.name == language.name) {
+                return Err(format!("Language \"{}\" is defined twice", language.name));
+            }
+        }
+
+        Ok(LanguageRegistry { languages: languages })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&LanguageDefinition> {
+        self.languages.iter().find(|language| language.name == name)
     }
+
+    pub fn all(&self) -> &[LanguageDefinition] {
+        &self.languages
+    }
+}
+
+/// Loads the language registry, so a broken config file fails at startup.
+pub fn check_language_registry() {
+    info!("{} languages loaded", LANGUAGES.all().len());
 }
 
-pub fn get_lang_config(language: &str) -> LanguageConfig {
-    match language {
-        "c" => c_lang_config(),
-        "cpp" => cpp_lang_config(),
-        "java" => java_lang_config(),
-        "py2" => py2_lang_config(),
-        "py3" => py3_lang_config(),
-        _ => c_lang_config(),
+pub fn get_lang_config(language: &str) -> Option<LanguageConfig> {
+    LANGUAGES.get(language).map(|language| language.config())
+}
+
+pub fn get_spj_definition(language: &str) -> Option<&'static SpjDefinition> {
+    LANGUAGES.get(language).and_then(|language| language.spj.as_ref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn c_definition() -> LanguageDefinition {
+        let content = fs::read_to_string("config/languages.json").unwrap();
+        let languages: Vec<LanguageDefinition> = serde_json::from_str(&content).unwrap();
+        languages.into_iter().find(|language| language.name == "c").unwrap()
+    }
+
+    fn write_config(languages: &[LanguageDefinition]) -> String {
+        let path = std::env::temp_dir().join(format!("languages-{}.json", uuid::Uuid::new_v4()));
+        fs::write(&path, serde_json::to_string(languages).unwrap()).unwrap();
+        path.to_string_lossy().into_owned()
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn shipped_config_loads() {
+        let registry = LanguageRegistry::load("config/languages.json").unwrap();
+        assert!(registry.get("c").is_some());
+        assert!(registry.get("brainfuck").is_none());
+        assert!(registry.all().iter().all(|language| language.validate().is_ok()));
+    }
+
+    #[test]
+    fn multipliers_default_to_one() {
+        let mut value = serde_json::to_value(c_definition()).unwrap();
+        value.as_object_mut().unwrap().remove("time_multiplier");
+        let language: LanguageDefinition = serde_json::from_value(value).unwrap();
+        assert_eq!(language.time_multiplier, 1.0);
+    }
+
+    #[test]
+    fn broken_definitions_are_rejected() {
+        let mut language = c_definition();
+        language.compile.compile_command = "/usr/bin/gcc -o {exe_path}".to_owned();
+        assert!(language.validate().is_err());
+
+        let mut language = c_definition();
+        language.run.env.push("NOT_A_PAIR".to_owned());
+        assert!(language.validate().is_err());
+
+        let mut language = c_definition();
+        language.time_multiplier = 0.0;
+        assert!(language.validate().is_err());
+
+        let mut language = c_definition();
+        language.spj.as_mut().unwrap().exe_name = "spj".to_owned();
+        assert!(language.validate().is_err());
+    }
+
+    #[test]
+    fn empty_and_duplicate_configs_are_rejected() {
+        let path = write_config(&[]);
+        assert!(LanguageRegistry::load(&path).is_err());
+        let _ = fs::remove_file(&path);
+
+        let path = write_config(&[c_definition(), c_definition()]);
+        assert!(LanguageRegistry::load(&path).is_err());
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn checker_sources_are_uploaded_with_the_language_extension() {
+        let spj = c_definition().spj.unwrap();
+        assert_eq!(spj.upload_name("spj_src"), "spj_src.c");
+        assert_eq!(spj.compile_config().src_name, spj.src_name);
+    }
+}
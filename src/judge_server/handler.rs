@@ -12,13 +12,73 @@ use futures::{StreamExt, TryStreamExt};
 
 use super::service::{
     info::server_info,
+    rejection::{ get_rejections_service, GetRejectionsMessage },
+    language::get_languages_service,
     submit::submit_service,
+    run::run_service,
+    fleet::{ control_judge_server_service, FleetAction, JudgeServerForm, SetMaxTasksForm },
 };
 
 pub async fn get_server_info(id: Identity) -> Result<HttpResponse, ServiceError> {
     server_info(id).await.map(|res| HttpResponse::Ok().json(&res))
 }
 
+pub async fn get_languages() -> Result<HttpResponse, ServiceError> {
+    get_languages_service().await.map(|res| HttpResponse::Ok().json(&res))
+}
+
+pub async fn get_rejections(
+    data: web::Data<DBState>,
+    form: web::Form<GetRejectionsMessage>,
+    id: Identity,
+) -> Result<HttpResponse, ServiceError> {
+    get_rejections_service(data, form.into_inner(), id).await.map(|res| HttpResponse::Ok().json(&res))
+}
+
+pub async fn drain_judge_server(
+    data: web::Data<DBState>,
+    judge_manager: web::Data<JMState>,
+    form: web::Form<JudgeServerForm>,
+    id: Identity,
+) -> Result<HttpResponse, ServiceError> {
+    control_judge_server_service(data, judge_manager, form.service_url.clone(), FleetAction::Drain, id).await
+        .map(|res| HttpResponse::Ok().json(&res))
+}
+
+pub async fn disable_judge_server(
+    data: web::Data<DBState>,
+    judge_manager: web::Data<JMState>,
+    form: web::Form<JudgeServerForm>,
+    id: Identity,
+) -> Result<HttpResponse, ServiceError> {
+    control_judge_server_service(data, judge_manager, form.service_url.clone(), FleetAction::Disable, id).await
+        .map(|res| HttpResponse::Ok().json(&res))
+}
+
+pub async fn enable_judge_server(
+    data: web::Data<DBState>,
+    judge_manager: web::Data<JMState>,
+    form: web::Form<JudgeServerForm>,
+    id: Identity,
+) -> Result<HttpResponse, ServiceError> {
+    control_judge_server_service(data, judge_manager, form.service_url.clone(), FleetAction::Enable, id).await
+        .map(|res| HttpResponse::Ok().json(&res))
+}
+
+pub async fn set_judge_server_max_tasks(
+    data: web::Data<DBState>,
+    judge_manager: web::Data<JMState>,
+    form: web::Form<SetMaxTasksForm>,
+    id: Identity,
+) -> Result<HttpResponse, ServiceError> {
+    control_judge_server_service(
+        data, judge_manager,
+        form.service_url.clone(), FleetAction::SetMaxTasks(form.max_tasks),
+        id
+    ).await
+    .map(|res| HttpResponse::Ok().json(&res))
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct SubmitRequestForm {
     pub problem_id: i32,
@@ -48,6 +108,27 @@ pub async fn submit(
     ).await.map(|res| HttpResponse::Ok().json(&res))
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct RunRequestForm {
+    pub src: String,
+    pub language: String,
+    pub stdin: String,
+}
+
+pub async fn run(
+    judge_manager: web::Data<JMState>,
+    form: web::Form<RunRequestForm>,
+    id: Identity,
+) -> Result<HttpResponse, ServiceError> {
+    run_service(
+        judge_manager,
+        form.language.clone(),
+        form.src.clone(),
+        form.stdin.clone(),
+        id
+    ).await.map(|res| HttpResponse::Ok().json(&res))
+}
+
 pub async fn get_file(mut payload: Multipart) -> Result<HttpResponse, Error> {
     // iterate over multipart stream
     while let Ok(Some(mut field)) = payload.try_next().await {
@@ -5,19 +5,22 @@ pub mod model;
 pub mod utils;
 
 use handler::*;
-use service::{
-    heartbeat::handle_heartbeat,
-    // ping::ping_judge_server,
-};
+use service::heartbeat::handle_heartbeat;
 use actix_web::web;
 
 pub fn route(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/judge_server")
             .service(web::resource("/heartbeat").route(web::post().to(handle_heartbeat)))
-            //.service(web::resource("/ping").route(web::post().to(ping_judge_server)))
             .service(web::resource("/submit").route(web::post().to(submit)))
+            .service(web::resource("/run").route(web::post().to(run)))
             .service(web::resource("/info").route(web::post().to(get_server_info)))
+            .service(web::resource("/drain").route(web::post().to(drain_judge_server)))
+            .service(web::resource("/disable").route(web::post().to(disable_judge_server)))
+            .service(web::resource("/enable").route(web::post().to(enable_judge_server)))
+            .service(web::resource("/set_max_tasks").route(web::post().to(set_judge_server_max_tasks)))
+            .service(web::resource("/languages").route(web::post().to(get_languages)))
+            .service(web::resource("/rejections").route(web::post().to(get_rejections)))
             .service(web::resource("/get_file").route(web::post().to(get_file)))
     );
 }
\ No newline at end of file
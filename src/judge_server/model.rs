@@ -1,4 +1,6 @@
 use super::config::*;
+use crate::statics::JUDGE_SERVER_CONTROLS;
+use std::fmt;
 use std::time::SystemTime;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -7,6 +9,39 @@ pub struct TestCase {
     pub output: String,
 }
 
+/// How outputs are compared instead of by the md5 of the trimmed output.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum Comparison {
+    /// tokens separated by any whitespace
+    Token,
+    /// tokens separated by any whitespace, ignoring case
+    CaseInsensitive,
+    /// tokens, where numbers may differ by an absolute or relative epsilon
+    Float { epsilon: f64 },
+    /// lines in any order
+    UnorderedLines,
+}
+
+impl Comparison {
+    pub const MODES: [&'static str; 5] = ["exact", "token", "case_insensitive", "float", "unordered_lines"];
+
+    /// `None` is the exact comparison, done by the judge itself.
+    pub fn from_mode(mode: &str, epsilon: Option<f64>) -> Result<Option<Comparison>, String> {
+        match mode {
+            "exact" => Ok(None),
+            "token" => Ok(Some(Comparison::Token)),
+            "case_insensitive" => Ok(Some(Comparison::CaseInsensitive)),
+            "float" => match epsilon {
+                Some(epsilon) if epsilon >= 0.0 && epsilon.is_finite() => Ok(Some(Comparison::Float { epsilon: epsilon })),
+                _ => Err("Float comparison needs a non-negative epsilon.".to_owned()),
+            },
+            "unordered_lines" => Ok(Some(Comparison::UnorderedLines)),
+            _ => Err(format!("Comparison mode must be one of {}.", Comparison::MODES.join(", "))),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JudgeSetting {
     pub language_config: LanguageConfig,
@@ -14,15 +49,37 @@ pub struct JudgeSetting {
     pub max_cpu_time: i32,
     pub max_memory: i32,
     pub test_case_id: Option<String>,
+    /// hash of the test case data, judges without this version download it from `/test_case/archive/{version}`
+    pub test_case_version: Option<String>,
     pub test_case: Option<Vec<TestCase>>,
     pub spj_version: Option<String>,
     pub spj_config: Option<SpjConfig>,
     pub spj_compile_config: Option<SpjCompileConfig>,
     pub spj_src: Option<String>,
+    /// set for interactive test cases, where the program talks to the interactor instead of reading a file
+    pub interactor_version: Option<String>,
+    pub interactor_config: Option<SpjConfig>,
+    pub interactor_compile_config: Option<SpjCompileConfig>,
+    pub interactor_src: Option<String>,
+    /// set when outputs are compared by the online judge, which then needs them back
+    pub comparison: Option<Comparison>,
+    /// asks the judge for the outputs, also when only the online judge needs them
     pub output: bool,
+    /// whether the outputs are kept for the submitter, unknown for settings stored before
+    #[serde(default)]
+    pub show_output: Option<bool>,
+    /// set for custom input runs, which show what the program wrote to stderr
+    #[serde(default)]
+    pub stderr: bool,
+}
+
+impl JudgeSetting {
+    pub fn shows_output(&self) -> bool {
+        self.show_output.unwrap_or(self.output && self.comparison.is_none())
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct JudgeServerInfo {
     pub judger_version: String,
     pub hostname: String,
@@ -34,4 +91,94 @@ pub struct JudgeServerInfo {
     pub token: String,
     pub heartbeat_time: SystemTime,
     pub is_deprecated: bool,
-}
\ No newline at end of file
+}
+
+// keep the token out of logs
+impl fmt::Debug for JudgeServerInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JudgeServerInfo")
+            .field("judger_version", &self.judger_version)
+            .field("hostname", &self.hostname)
+            .field("cpu_core", &self.cpu_core)
+            .field("memory", &self.memory)
+            .field("cpu", &self.cpu)
+            .field("task_number", &self.task_number)
+            .field("service_url", &self.service_url)
+            .field("heartbeat_time", &self.heartbeat_time)
+            .field("is_deprecated", &self.is_deprecated)
+            .finish()
+    }
+}
+
+impl JudgeServerInfo {
+    pub fn control(&self) -> JudgeServerControl {
+        JUDGE_SERVER_CONTROLS.read().unwrap().get(&self.service_url).cloned().unwrap_or_default()
+    }
+
+    /// How many tasks the server may run at the same time.
+    pub fn capacity(&self) -> i32 {
+        self.control().max_tasks.unwrap_or(self.cpu_core * 2)
+    }
+}
+
+/// What admins decided for a judge server, kept while the server goes away and comes back.
+#[derive(Debug, Clone, Default)]
+pub struct JudgeServerControl {
+    /// finishes the running tasks but takes no new ones
+    pub is_draining: bool,
+    /// takes no tasks, the running ones have been sent back to the queue
+    pub is_disabled: bool,
+    /// overrides the capacity told by the server's cpu cores
+    pub max_tasks: Option<i32>,
+}
+
+impl JudgeServerControl {
+    pub fn accepts_tasks(&self) -> bool {
+        !self.is_draining && !self.is_disabled
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(service_url: &str, cpu_core: i32) -> JudgeServerInfo {
+        JudgeServerInfo {
+            judger_version: "2.1.0".to_owned(),
+            hostname: "judger".to_owned(),
+            cpu_core: cpu_core,
+            memory: 0.0,
+            cpu: 0.0,
+            task_number: 0,
+            service_url: service_url.to_owned(),
+            token: "token".to_owned(),
+            heartbeat_time: SystemTime::now(),
+            is_deprecated: false,
+        }
+    }
+
+    #[test]
+    fn draining_and_disabled_servers_take_no_tasks() {
+        assert!(JudgeServerControl::default().accepts_tasks());
+        assert!(!JudgeServerControl { is_draining: true, ..Default::default() }.accepts_tasks());
+        assert!(!JudgeServerControl { is_disabled: true, ..Default::default() }.accepts_tasks());
+    }
+
+    #[test]
+    fn capacity_follows_the_cores_unless_overridden() {
+        let info = server("http://model-test-capacity", 4);
+        assert_eq!(info.capacity(), 8);
+
+        JUDGE_SERVER_CONTROLS.write().unwrap().insert(info.service_url.clone(), JudgeServerControl {
+            max_tasks: Some(3),
+            ..Default::default()
+        });
+        assert_eq!(info.capacity(), 3);
+        JUDGE_SERVER_CONTROLS.write().unwrap().remove(&info.service_url);
+        assert_eq!(info.capacity(), 8);
+    }
+
+    #[test]
+    fn tokens_stay_out_of_logs() {
+        assert!(!format!("{:?}", server("http://model-test-debug", 1)).contains("token"));
+    }
+}
@@ -0,0 +1,84 @@
+use crate::{
+    database::*,
+    judge_manager::*,
+    judge_manager::handler::{ StartJudge, RequeueJudgeServerTasks },
+    errors::{ ServiceError, ServiceResult },
+    statics::{ JUDGE_SERVER_INFOS, JUDGE_SERVER_CONTROLS },
+    user::service::me::auth_check,
+};
+use super::info::OutJudgeServerInfo;
+use actix_web::web;
+use actix_identity::Identity;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct JudgeServerForm {
+    pub service_url: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SetMaxTasksForm {
+    pub service_url: String,
+    /// back to twice the cpu cores if not given
+    pub max_tasks: Option<i32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FleetAction {
+    /// finish the running tasks, take no new ones
+    Drain,
+    /// take no tasks and send the running ones back to the queue
+    Disable,
+    Enable,
+    SetMaxTasks(Option<i32>),
+}
+
+pub async fn control_judge_server_service(
+    data: web::Data<DBState>,
+    judge_manager: web::Data<JMState>,
+    service_url: String,
+    action: FleetAction,
+    id: Identity,
+) -> ServiceResult<OutJudgeServerInfo> {
+    auth_check(data.clone(), id, "admin".to_owned()).await?;
+
+    if let FleetAction::SetMaxTasks(Some(max_tasks)) = action {
+        if max_tasks <= 0 {
+            return Err(ServiceError::BadRequest("Max tasks should be larger than 0.".to_owned()));
+        }
+    }
+
+    if !JUDGE_SERVER_INFOS.read().unwrap().contains_key(&service_url) {
+        return Err(ServiceError::BadRequest("Judge server not found.".to_owned()));
+    }
+
+    {
+        let mut lock = JUDGE_SERVER_CONTROLS.write().unwrap();
+        let control = lock.entry(service_url.clone()).or_default();
+        match action {
+            FleetAction::Drain => { control.is_draining = true; },
+            FleetAction::Disable => { control.is_disabled = true; },
+            FleetAction::Enable => {
+                control.is_draining = false;
+                control.is_disabled = false;
+            },
+            FleetAction::SetMaxTasks(max_tasks) => { control.max_tasks = max_tasks; },
+        }
+    }
+    info!("{:?} judge server {}", action, service_url);
+
+    if action == FleetAction::Disable {
+        // late results of the taken back tasks are dropped, so they don't count anymore
+        if let Some(info) = JUDGE_SERVER_INFOS.write().unwrap().get_mut(&service_url) {
+            info.task_number = 0;
+        }
+        judge_manager.jm.do_send(RequeueJudgeServerTasks { service_url: service_url.clone() });
+    } else {
+        // enabling or raising the cap may have freed slots
+        judge_manager.jm.do_send(StartJudge());
+    }
+
+    let lock = JUDGE_SERVER_INFOS.read().unwrap();
+    lock.get(&service_url)
+        .map(|info| OutJudgeServerInfo::from(info.clone()))
+        .ok_or(ServiceError::BadRequest("Judge server not found.".to_owned()))
+}
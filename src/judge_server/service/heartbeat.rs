@@ -1,15 +1,18 @@
 use actix_web::{
     web,
-    Responder,
     HttpRequest,
     HttpResponse, 
 };
+use crate::database::*;
+use crate::errors::ServiceError;
+use crate::judge_server::utils::auth::authenticate_judge_server;
+use super::rejection::RecordRejectionMessage;
+use super::ping::ping_judge_server;
 use crate::statics::JUDGE_SERVER_INFOS;
 use crate::judge_server::model::JudgeServerInfo;
 use std::time::SystemTime;
 use crate::judge_manager::*;
 use crate::judge_manager::handler::StartJudge;
-use actix_web::client::Client;
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct HeartbeatResquest {
@@ -28,65 +31,90 @@ struct HeartbeatResponse {
 }
 
 pub async fn handle_heartbeat(
+    data: web::Data<DBState>,
     judge_manager: web::Data<JMState>,
-    req: HttpRequest, 
+    req: HttpRequest,
     info: web::Json<HeartbeatResquest>
-) -> impl Responder {
-    let token = req.headers().get("x-judge-server-token").unwrap()
-        .to_str().unwrap().to_string();
+) -> Result<HttpResponse, ServiceError> {
+    let token = req.headers().get("x-judge-server-token")
+        .and_then(|token| token.to_str().ok())
+        .map(|token| token.to_owned());
+    let rejected = match &token {
+        None => Some("Missing judge server token.".to_owned()),
+        Some(token) => authenticate_judge_server(&info.service_url.clone().unwrap_or_default(), token).err(),
+    };
+
+    if let Some(reason) = rejected {
+        warn!("rejected heartbeat from {:?}: {}", info.service_url, reason);
+        // on record before the judge server hears about it
+        let recorded = data.db.send(RecordRejectionMessage {
+            service_url: info.service_url.clone(),
+            hostname: Some(info.hostname.clone()),
+            remote_addr: req.connection_info().realip_remote_addr().map(|addr| addr.to_owned()),
+            reason: reason,
+        }).await;
+        match recorded {
+            Ok(Ok(())) => (),
+            Ok(Err(e)) => error!("{}", e),
+            Err(e) => error!("Error recording judge server rejection: {}", e),
+        }
+        return Err(ServiceError::Unauthorized);
+    }
+    let token = token.unwrap_or_default();
 
     if !info.service_url.is_none()
     {        
         let service_url = info.service_url.clone().unwrap();
-        let task_number = {
-            let lock = JUDGE_SERVER_INFOS.read().unwrap();
-            if lock.get(&service_url).is_none() { 0 } 
-            else { 
-                let target = lock.get(&service_url).unwrap();
-                target.task_number
-            }
-        };
 
-        let response = Client::new()
-            .post(format!("{}/ping", service_url))
-            .set_header("X-Judge-Server-Token", token.clone())
-            .set_header("Content-Type", "application/json")
-            .send()
-            .await;
-
-        let is_deprecated = {
-            if !response.is_ok() {
-                info!("setting is_deprecated to true");
+        let is_deprecated = match ping_judge_server(&service_url, &token).await {
+            Err(e) => {
+                info!("setting is_deprecated to true: {}", e);
                 true
-            } else { false }
+            },
+            Ok(()) => false,
         };
 
-        let now = SystemTime::now();
-        let judge_server_info = JudgeServerInfo {
-            judger_version: info.judger_version.clone(),
-            hostname: info.hostname.clone(),
-            cpu_core: info.cpu_core,
-            memory: info.memory,
-            cpu: info.cpu,
-            task_number: task_number,
-            service_url: service_url,
-            token: token.clone(),
-            heartbeat_time: now,
-            is_deprecated: is_deprecated,
-        };
-        let mut lock = JUDGE_SERVER_INFOS.write().unwrap();
-        lock.insert(info.service_url.clone().unwrap(), judge_server_info);
+        // locked only after the ping, and the task number is left to slot acquires and releases
+        {
+            let now = SystemTime::now();
+            let mut lock = JUDGE_SERVER_INFOS.write().unwrap();
+            match lock.get_mut(&service_url) {
+                Some(server_info) => {
+                    server_info.judger_version = info.judger_version.clone();
+                    server_info.hostname = info.hostname.clone();
+                    server_info.cpu_core = info.cpu_core;
+                    server_info.memory = info.memory;
+                    server_info.cpu = info.cpu;
+                    server_info.token = token;
+                    server_info.heartbeat_time = now;
+                    server_info.is_deprecated = is_deprecated;
+                },
+                None => {
+                    lock.insert(service_url.clone(), JudgeServerInfo {
+                        judger_version: info.judger_version.clone(),
+                        hostname: info.hostname.clone(),
+                        cpu_core: info.cpu_core,
+                        memory: info.memory,
+                        cpu: info.cpu,
+                        task_number: 0,
+                        service_url: service_url,
+                        token: token,
+                        heartbeat_time: now,
+                        is_deprecated: is_deprecated,
+                    });
+                },
+            }
+        }
 
         if !is_deprecated {
             judge_manager.jm.do_send(StartJudge());
         }
     }
    
-    HttpResponse::Ok()
-        .set_header("X-Judge-Server-Token", token)
+    Ok(HttpResponse::Ok()
         .set_header("Content-Type", "application/json")
         .json(HeartbeatResponse {
             data: "success".to_owned(),
             error: None,
-        })
-}   
\ No newline at end of file
+        }))
+}
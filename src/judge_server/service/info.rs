@@ -16,10 +16,16 @@ pub struct OutJudgeServerInfo {
     pub service_url: String,
     pub last_heartbeat: i32,
     pub is_deprecated: bool,
+    pub is_draining: bool,
+    pub is_disabled: bool,
+    pub max_tasks: Option<i32>,
+    pub capacity: i32,
 }
 
 impl From<JudgeServerInfo> for OutJudgeServerInfo {
     fn from(info: JudgeServerInfo) -> Self {
+        let control = info.control();
+        let capacity = info.capacity();
         let JudgeServerInfo {
             judger_version,
             hostname,
@@ -47,6 +53,10 @@ impl From<JudgeServerInfo> for OutJudgeServerInfo {
             service_url,
             last_heartbeat,
             is_deprecated,
+            is_draining: control.is_draining,
+            is_disabled: control.is_disabled,
+            max_tasks: control.max_tasks,
+            capacity: capacity,
         }
     }
 }
@@ -0,0 +1,36 @@
+use crate::{
+    errors::ServiceResult,
+    judge_server::config::LanguageDefinition,
+    statics::LANGUAGES,
+};
+
+#[derive(Debug, Clone, Serialize, juniper::GraphQLObject)]
+pub struct OutLanguage {
+    pub name: String,
+    pub display_name: String,
+    pub src_name: String,
+    pub compile_command: String,
+    pub run_command: String,
+    pub time_multiplier: f64,
+    pub memory_multiplier: f64,
+    pub supports_spj: bool,
+}
+
+impl From<&LanguageDefinition> for OutLanguage {
+    fn from(language: &LanguageDefinition) -> Self {
+        Self {
+            name: language.name.clone(),
+            display_name: language.display_name.clone(),
+            src_name: language.compile.src_name.clone(),
+            compile_command: language.compile.compile_command.clone(),
+            run_command: language.run.command.clone(),
+            time_multiplier: language.time_multiplier,
+            memory_multiplier: language.memory_multiplier,
+            supports_spj: language.spj.is_some(),
+        }
+    }
+}
+
+pub async fn get_languages_service() -> ServiceResult<Vec<OutLanguage>> {
+    Ok(LANGUAGES.all().iter().map(OutLanguage::from).collect())
+}
@@ -1,4 +1,8 @@
 pub mod heartbeat;
-// pub mod ping;
+pub mod ping;
 pub mod info;
-pub mod submit;
\ No newline at end of file
+pub mod submit;
+pub mod rejection;
+pub mod language;
+pub mod fleet;
+pub mod run;
\ No newline at end of file
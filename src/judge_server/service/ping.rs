@@ -1,20 +1,33 @@
-use actix_web::{
-    Responder,
-    HttpResponse, 
-};
-use crate::JUDGE_SERVER_TOKEN;
-use actix_web::client::Client;
+use actix_web::client::{ Client, Connector };
+use std::time::Duration;
+use crate::statics::JUDGE_CONNECT_TIMEOUT;
+use crate::status::model::ErrChecker;
 
-pub async fn ping_judge_server() -> impl Responder {
-    let token = (*JUDGE_SERVER_TOKEN).clone();
-   
-    let response = Client::new()
-        .post("http://127.0.0.1:12358/ping")
+/// Asks a judge server whether it is up, the same way QDUOJ's backend does.
+pub async fn ping_judge_server(service_url: &str, token: &str) -> Result<(), String> {
+    let client = Client::builder()
+        .connector(Connector::new()
+            .timeout(Duration::from_secs(*JUDGE_CONNECT_TIMEOUT))
+            .finish())
+        .timeout(Duration::from_secs(*JUDGE_CONNECT_TIMEOUT))
+        .finish();
+
+    let mut response = client
+        .post(format!("{}/ping", service_url))
         .set_header("X-Judge-Server-Token", token)
         .set_header("Content-Type", "application/json")
         .send()
-        .await;
-        
-    info!("Response: {:?}", response);
-    HttpResponse::Ok().body("ping sended")
-}
\ No newline at end of file
+        .await
+        .map_err(|e| format!("Error pinging {}: {}", service_url, e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("Judge server {} responded to ping with {}", service_url, response.status()));
+    }
+    let err_checker: ErrChecker = response.json()
+        .await
+        .map_err(|e| format!("Malformed ping response from {}: {}", service_url, e))?;
+    match err_checker.err {
+        None => Ok(()),
+        Some(err) => Err(format!("Judge server {} refused ping: {}", service_url, err)),
+    }
+}
@@ -0,0 +1,103 @@
+use crate::{
+    database::*,
+    errors::{ServiceError, ServiceResult},
+    schema::judge_server_rejections,
+    user::service::me::auth_check,
+    utils::time::get_cur_naive_date_time,
+};
+use diesel::prelude::*;
+use actix::prelude::*;
+use actix_web::web;
+use actix_identity::Identity;
+use chrono::*;
+
+#[derive(Debug, Clone, Deserialize, Insertable)]
+#[table_name = "judge_server_rejections"]
+struct InsertableRejection {
+    service_url: Option<String>,
+    hostname: Option<String>,
+    remote_addr: Option<String>,
+    reason: String,
+    create_time: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RecordRejectionMessage {
+    pub service_url: Option<String>,
+    pub hostname: Option<String>,
+    pub remote_addr: Option<String>,
+    pub reason: String,
+}
+
+impl Message for RecordRejectionMessage {
+    type Result = Result<(), String>;
+}
+
+impl Handler<RecordRejectionMessage> for DbExecutor {
+    type Result = Result<(), String>;
+
+    fn handle(&mut self, msg: RecordRejectionMessage, _: &mut Self::Context) -> Self::Result {
+        diesel::insert_into(judge_server_rejections::table)
+            .values(&InsertableRejection {
+                service_url: msg.service_url,
+                hostname: msg.hostname,
+                remote_addr: msg.remote_addr,
+                reason: msg.reason,
+                create_time: get_cur_naive_date_time(),
+            })
+            .execute(&self.0)
+            .map(|_| ())
+            .map_err(|_| "Error recording judge server rejection.".to_owned())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Queryable, juniper::GraphQLObject)]
+pub struct JudgeServerRejection {
+    pub id: i32,
+    pub service_url: Option<String>,
+    pub hostname: Option<String>,
+    pub remote_addr: Option<String>,
+    pub reason: String,
+    pub create_time: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GetRejectionsMessage {
+    pub count: i32,
+}
+
+impl Message for GetRejectionsMessage {
+    type Result = Result<Vec<JudgeServerRejection>, String>;
+}
+
+impl Handler<GetRejectionsMessage> for DbExecutor {
+    type Result = Result<Vec<JudgeServerRejection>, String>;
+
+    fn handle(&mut self, msg: GetRejectionsMessage, _: &mut Self::Context) -> Self::Result {
+        judge_server_rejections::table
+            .order_by(judge_server_rejections::id.desc())
+            .limit(msg.count as i64)
+            .load::<JudgeServerRejection>(&self.0)
+            .map_err(|_| "Error loading judge server rejections.".to_owned())
+    }
+}
+
+pub async fn get_rejections_service(
+    data: web::Data<DBState>,
+    msg: GetRejectionsMessage,
+    id: Identity,
+) -> ServiceResult<Vec<JudgeServerRejection>> {
+    auth_check(data.clone(), id, "admin".to_owned()).await?;
+
+    let db_result = data.db.send(msg).await;
+
+    match db_result {
+        Err(_) => Err(ServiceError::InternalServerError),
+        Ok(inner_result) => {
+            match inner_result {
+                Err(msg) => Err(ServiceError::BadRequest(msg)),
+                Ok(rejections) => Ok(rejections),
+            }
+        }
+    }
+}
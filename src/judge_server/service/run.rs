@@ -0,0 +1,227 @@
+use crate::{
+    judge_manager::*,
+    judge_manager::handler::StartJudge,
+    judge_manager::model::JudgeResponse,
+    judge_manager::utils::{
+        queue::{ JudgePriority, QueuedTask },
+        run::RunState,
+        result::{ is_compile_error, normalize_compile_error },
+    },
+    errors::{ ServiceError, ServiceResult },
+    statics::{ WAITING_QUEUE, PENDING_RUNS, MAX_IN_FLIGHT_PER_USER, JUDGE_RUN_TIMEOUT },
+    judge_server::model::{ JudgeSetting, TestCase },
+    judge_server::config::get_lang_config,
+    judge_server::utils::filter::{ language_filter, setting_filter },
+    status::model::Verdict,
+};
+use actix_web::web;
+use actix_identity::Identity;
+use std::time::Duration;
+use uuid::Uuid;
+use atoi::atoi;
+
+const RUN_MAX_CPU_TIME: i32 = 1000;
+const RUN_MAX_MEMORY: i32 = 256 * 1024 * 1024;
+const MAX_RUN_INPUT_SIZE: usize = 1024 * 1024;
+/// stdout and stderr beyond this many bytes are cut off
+const MAX_RUN_OUTPUT_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Serialize, juniper::GraphQLObject)]
+pub struct RunResult {
+    /// AC when the program ran to the end, as there is no answer to compare with
+    pub verdict: Verdict,
+    pub stdout: String,
+    /// only the local backend captures stderr, `None` from a QDUOJ judge server
+    pub stderr: Option<String>,
+    pub compile_error: Option<String>,
+    /// cpu time in ms
+    pub time: Option<i32>,
+    /// in bytes
+    pub memory: Option<i32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, juniper::GraphQLEnum)]
+pub enum RunProgress {
+    Waiting,
+    Running,
+    Finished,
+}
+
+#[derive(Debug, Clone, Serialize, juniper::GraphQLObject)]
+pub struct RunPoll {
+    pub progress: RunProgress,
+    /// set once the run is finished
+    pub result: Option<RunResult>,
+}
+
+fn truncate_output(mut output: String) -> String {
+    if output.len() > MAX_RUN_OUTPUT_SIZE {
+        let mut end = MAX_RUN_OUTPUT_SIZE;
+        while !output.is_char_boundary(end) { end -= 1; }
+        output.truncate(end);
+        output.push_str("\n... (truncated)");
+    }
+    output
+}
+
+fn get_run_result(response: Result<JudgeResponse, String>) -> RunResult {
+    let mut run_result = RunResult {
+        verdict: Verdict::SE,
+        stdout: String::new(),
+        stderr: None,
+        compile_error: None,
+        time: None,
+        memory: None,
+    };
+
+    match response {
+        Err(reason) => { info!("custom input run failed: {}", reason); },
+        Ok(JudgeResponse::Failed(err_result)) => {
+            if is_compile_error(&err_result) {
+                run_result.verdict = Verdict::CE;
+                run_result.compile_error = Some(normalize_compile_error(&err_result.data));
+            }
+        },
+        Ok(JudgeResponse::Finished(judge_result)) => {
+            if let Some(data) = judge_result.data.into_iter().next() {
+                run_result.verdict = match Verdict::from_result(data.result) {
+                    // the empty answer never matches
+                    Verdict::WA | Verdict::PE => Verdict::AC,
+                    verdict => verdict,
+                };
+                run_result.stdout = truncate_output(data.output.unwrap_or_default());
+                run_result.stderr = data.stderr.map(truncate_output);
+                run_result.time = Some(data.cpu_time);
+                run_result.memory = Some(data.memory);
+            }
+        },
+    }
+
+    run_result
+}
+
+fn get_owner_id(id: &Identity) -> ServiceResult<i32> {
+    let cur_id = match id.identity() {
+        Some(cur_id) => cur_id,
+        None => { return Err(ServiceError::Unauthorized); },
+    };
+    atoi::<i32>(cur_id.as_bytes()).ok_or(ServiceError::Unauthorized)
+}
+
+fn build_run_setting(language: String, src: String, stdin: String) -> ServiceResult<String> {
+    if !language_filter(&language) {
+        return Err(ServiceError::BadRequest("Language doesn't support.".to_owned()));
+    }
+    let language_config = get_lang_config(&language)
+        .ok_or(ServiceError::BadRequest("Language doesn't support.".to_owned()))?;
+
+    if stdin.len() > MAX_RUN_INPUT_SIZE {
+        return Err(ServiceError::BadRequest("Input is too large.".to_owned()));
+    }
+
+    let (max_cpu_time, max_memory) = setting_filter(&language, RUN_MAX_CPU_TIME, RUN_MAX_MEMORY);
+    let judge_setting = JudgeSetting {
+        language_config: language_config,
+        src: src,
+        max_cpu_time: max_cpu_time,
+        max_memory: max_memory,
+        test_case_id: None,
+        test_case_version: None,
+        test_case: Some(vec![TestCase {
+            input: stdin,
+            output: String::new(),
+        }]),
+        spj_version: None,
+        spj_config: None,
+        spj_compile_config: None,
+        spj_src: None,
+        interactor_version: None,
+        interactor_config: None,
+        interactor_compile_config: None,
+        interactor_src: None,
+        comparison: None,
+        output: true,
+        show_output: Some(true),
+        stderr: true,
+    };
+
+    Ok(serde_json::to_string(&judge_setting).unwrap())
+}
+
+fn too_many_runs() -> ServiceError {
+    ServiceError::BadRequest("You already have too many runs waiting.".to_owned())
+}
+
+fn queue_run(judge_manager: &web::Data<JMState>, run_id: Uuid, owner_id: i32) {
+    {
+        let mut lock = WAITING_QUEUE.write().unwrap();
+        lock.push_back(QueuedTask {
+            id: run_id,
+            owner_id: owner_id,
+            priority: JudgePriority::Run,
+        });
+    }
+    judge_manager.jm.do_send(StartJudge());
+}
+
+/// Runs the code on the given input through the judge queue, behind every graded submission.
+/// Nothing is recorded, so no status nor problem counter changes.
+pub async fn run_service(
+    judge_manager: web::Data<JMState>,
+    language: String,
+    src: String,
+    stdin: String,
+    id: Identity,
+) -> ServiceResult<RunResult> {
+    let owner_id = get_owner_id(&id)?;
+    let setting = build_run_setting(language, src, stdin)?;
+
+    let run_id = Uuid::new_v4();
+    let receiver = PENDING_RUNS.add(run_id, owner_id, setting, *MAX_IN_FLIGHT_PER_USER)
+        .ok_or_else(too_many_runs)?;
+    queue_run(&judge_manager, run_id, owner_id);
+
+    match actix_rt::time::timeout(Duration::from_secs(*JUDGE_RUN_TIMEOUT), receiver).await {
+        Ok(Ok(response)) => Ok(get_run_result(response)),
+        Ok(Err(_)) => Err(ServiceError::InternalServerError),
+        Err(_) => {
+            // a run already sent to a judge still counts until it finishes
+            PENDING_RUNS.cancel(run_id);
+            Err(ServiceError::BadRequest("No judge was free to run the code in time.".to_owned()))
+        },
+    }
+}
+
+/// Like `run_service`, but returns at once with the id to poll the result by.
+pub fn start_run_service(
+    judge_manager: web::Data<JMState>,
+    language: String,
+    src: String,
+    stdin: String,
+    id: Identity,
+) -> ServiceResult<Uuid> {
+    let owner_id = get_owner_id(&id)?;
+    let setting = build_run_setting(language, src, stdin)?;
+
+    let run_id = Uuid::new_v4();
+    if !PENDING_RUNS.add_polled(run_id, owner_id, setting, *MAX_IN_FLIGHT_PER_USER) {
+        return Err(too_many_runs());
+    }
+    queue_run(&judge_manager, run_id, owner_id);
+
+    Ok(run_id)
+}
+
+pub fn poll_run_service(run_id: Uuid, id: Identity) -> ServiceResult<RunPoll> {
+    let owner_id = get_owner_id(&id)?;
+
+    match PENDING_RUNS.poll(run_id, owner_id) {
+        Some(RunState::Waiting) => Ok(RunPoll { progress: RunProgress::Waiting, result: None }),
+        Some(RunState::Running) => Ok(RunPoll { progress: RunProgress::Running, result: None }),
+        Some(RunState::Finished(response)) => Ok(RunPoll {
+            progress: RunProgress::Finished,
+            result: Some(get_run_result(response)),
+        }),
+        None => Err(ServiceError::BadRequest("Run not found, its result may have expired.".to_owned())),
+    }
+}
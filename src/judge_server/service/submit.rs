@@ -2,10 +2,17 @@ use crate::{
     database::*,
     judge_manager::*,
     judge_manager::handler::StartJudge,
+    judge_manager::utils::{
+        queue::{ JudgePriority, QueuedTask },
+        priority::get_judge_priority,
+    },
     errors::{ServiceError, ServiceResult},
-    statics::WAITING_QUEUE,
+    statics::{ WAITING_QUEUE, MAX_IN_FLIGHT_PER_USER, STATUS_FEED },
+    judge_manager::utils::feed::StatusChange,
     utils::time::get_cur_naive_date_time,
     region::service::info::GetRegionMessage,
+    problem::model::LanguageLimit,
+    judge_server::model::Comparison,
 };
 use diesel::prelude::*;
 use actix::prelude::*;
@@ -18,6 +25,9 @@ use crate::judge_server::utils::{
 };
 use atoi::atoi;
 
+/// first key of the advisory lock taken while counting one owner's status in flight
+const IN_FLIGHT_LOCK_SPACE: i32 = 1;
+
 #[derive(Debug, Clone, Deserialize)]
 struct SubmitStatusMessage {
     id: Uuid,
@@ -31,35 +41,61 @@ struct SubmitStatusMessage {
 }
 
 impl Message for SubmitStatusMessage {
-    type Result = Result<(), String>;
+    type Result = Result<JudgePriority, String>;
 }
 
 impl Handler<SubmitStatusMessage> for DbExecutor {
-    type Result = Result<(), String>;
+    type Result = Result<JudgePriority, String>;
     
     fn handle(&mut self, msg: SubmitStatusMessage, _: &mut Self::Context) -> Self::Result {
         use crate::schema::status::dsl::*;
+        use crate::schema::rejudge_items;
         use crate::status::model::InsertableStatus;
 
-        let _rows_inserted = diesel::insert_into(status)
-            .values(&InsertableStatus{
-                id: msg.id,
-                owner_id: msg.owner_id,
-                problem_id: msg.problem_id,
-                problem_region: msg.problem_region,
-                state: "Waiting".to_owned(),
-                judge_type: msg.judge_type,
-                setting_data: msg.setting_data,
-                submit_time: get_cur_naive_date_time(),
-                start_pend_time: None,
-                finish_time: None,
-                language: msg.language,
-                host_name: None,
-            })
-            .execute(&self.0)
-            .expect("Insert status failed");
-
-        Ok(())
+        // a lock per owner, so concurrent submits can't both pass the in-flight check
+        let inserted = self.0.transaction::<_, diesel::result::Error, _>(|| {
+            diesel::sql_query("SELECT pg_advisory_xact_lock($1, $2)")
+                .bind::<diesel::sql_types::Integer, _>(IN_FLIGHT_LOCK_SPACE)
+                .bind::<diesel::sql_types::Integer, _>(msg.owner_id)
+                .execute(&self.0)?;
+
+            if *MAX_IN_FLIGHT_PER_USER > 0 {
+                // statuses an admin sent back for a rejudge aren't the user's doing
+                let in_flight = status
+                    .filter(owner_id.eq(msg.owner_id))
+                    .filter(state.eq_any(vec!["Waiting".to_owned(), "Pending".to_owned()]))
+                    .filter(diesel::dsl::not(diesel::dsl::exists(
+                        rejudge_items::table.filter(rejudge_items::status_id.eq(id))
+                    )))
+                    .count()
+                    .get_result::<i64>(&self.0)?;
+                if in_flight >= *MAX_IN_FLIGHT_PER_USER {
+                    return Ok(Err(format!("You already have {} submissions waiting to be judged.", in_flight)));
+                }
+            }
+
+            let priority = get_judge_priority(&self.0, &msg.problem_region);
+            diesel::insert_into(status)
+                .values(&InsertableStatus{
+                    id: msg.id,
+                    owner_id: msg.owner_id,
+                    problem_id: msg.problem_id,
+                    problem_region: msg.problem_region,
+                    state: "Waiting".to_owned(),
+                    judge_type: msg.judge_type,
+                    setting_data: msg.setting_data,
+                    submit_time: get_cur_naive_date_time(),
+                    start_pend_time: None,
+                    finish_time: None,
+                    language: msg.language,
+                    host_name: None,
+                })
+                .execute(&self.0)?;
+
+            Ok(Ok(priority))
+        });
+
+        inserted.map_err(|_| "Error inserting status.".to_owned())?
     }
 }
 
@@ -68,7 +104,13 @@ pub struct ProblemSetting {
     pub default_max_cpu_time: i32,
     pub default_max_memory: i32,
     pub is_spj: bool,
+    pub spj_language: Option<String>,
+    pub is_interactive: bool,
     pub opaque_output: bool,
+    pub allowed_languages: Option<Vec<String>>,
+    pub language_limits: Vec<LanguageLimit>,
+    pub comparison_mode: Option<String>,
+    pub comparison_epsilon: Option<f64>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -88,25 +130,42 @@ impl Handler<GetSettingMessage> for DbExecutor {
         use crate::schema::problems::dsl::*;
         use crate::schema::test_cases::dsl::*;
 
-        let (default_max_cpu_time_val, default_max_memory_val, test_case_name, opaque_output_val) = problems
+        let (
+            default_max_cpu_time_val,
+            default_max_memory_val,
+            test_case_name,
+            opaque_output_val,
+            allowed_languages_val,
+            language_limits_val,
+            comparison_mode_val,
+            comparison_epsilon_val,
+        ) = problems
             .filter(region.eq(msg.region))
             .filter(id.eq(msg.problem_id))
-            .select( (default_max_cpu_time, default_max_memory, test_case, opaque_output) )
-            .first::<(i32, i32, Option<String>, bool)>(&self.0)
+            .select( (default_max_cpu_time, default_max_memory, test_case, opaque_output, allowed_languages, language_limits, comparison_mode, comparison_epsilon) )
+            .first::<(i32, i32, Option<String>, bool, Option<Vec<String>>, Option<String>, Option<String>, Option<f64>)>(&self.0)
             .expect("Error loading problem setting.");
 
         info!("{:?}", test_case_name);
         if test_case_name.is_none() { return Err("Problem doesn't have test cases.".to_owned()) }
-        let is_spj_val = test_cases.filter(name.eq(test_case_name.unwrap()))
-            .select(is_spj)
-            .first::<bool>(&self.0)
+        let (is_spj_val, spj_language_val, is_interactive_val) = test_cases.filter(name.eq(test_case_name.unwrap()))
+            .select((is_spj, spj_language, is_interactive))
+            .first::<(bool, Option<String>, bool)>(&self.0)
             .expect("Error loading test case info.");
 
         Ok(ProblemSetting{
             default_max_cpu_time: default_max_cpu_time_val,
             default_max_memory: default_max_memory_val,
             is_spj: is_spj_val,
+            spj_language: if is_spj_val || is_interactive_val { spj_language_val } else { None },
+            is_interactive: is_interactive_val,
             opaque_output: opaque_output_val,
+            allowed_languages: allowed_languages_val,
+            language_limits: language_limits_val
+                .and_then(|limits| serde_json::from_str(&limits).ok())
+                .unwrap_or_default(),
+            comparison_mode: comparison_mode_val,
+            comparison_epsilon: comparison_epsilon_val,
         })
     }
 }
@@ -178,28 +237,42 @@ pub async fn submit_service(
             match inner_result {
                 Err(msg) => Err(ServiceError::BadRequest(msg)),
                 Ok(problem_setting) => {
-                    let default_max_cpu_time = problem_setting.default_max_cpu_time;
-                    let default_max_memory = problem_setting.default_max_memory;
-                    let (max_cpu_time, max_memory) = setting_filter(&language, default_max_cpu_time, default_max_memory);
+                    if let Some(allowed_languages) = &problem_setting.allowed_languages {
+                        if !allowed_languages.contains(&language) {
+                            let msg = format!("Problem only allow languages \"{}\"", allowed_languages.join(", "));
+                            return Err(ServiceError::BadRequest(msg));
+                        }
+                    }
+                    let comparison = match &problem_setting.comparison_mode {
+                        Some(mode) => match Comparison::from_mode(mode, problem_setting.comparison_epsilon) {
+                            Ok(comparison) => comparison,
+                            Err(msg) => { return Err(ServiceError::BadRequest(msg)); },
+                        },
+                        None => None,
+                    };
                     let submittion_id = Uuid::new_v4();
+                    let owner_id = atoi::<i32>(cur_id.as_bytes()).unwrap();
                     match get_judge_setting(
                         data.clone(),
                         problem_region.clone(),
                         problem_id,
                         language.clone(),
                         src,
-                        problem_setting.is_spj,
-                        max_cpu_time,
-                        max_memory,
+                        problem_setting.spj_language.clone(),
+                        problem_setting.is_interactive,
+                        problem_setting.default_max_cpu_time,
+                        problem_setting.default_max_memory,
+                        &problem_setting.language_limits,
+                        comparison,
                         problem_setting.opaque_output || output,
                     ).await {
                         Err(msg) => Err(ServiceError::BadRequest(msg)),
                         Ok(judge_setting) => {
                             let db_result = data.db.send(SubmitStatusMessage {
                                 id: submittion_id,
-                                owner_id: atoi::<i32>(cur_id.as_bytes()).unwrap(),
+                                owner_id: owner_id,
                                 problem_id: problem_id,
-                                problem_region: problem_region,
+                                problem_region: problem_region.clone(),
                                 state: "Waiting".to_owned(),
                                 judge_type: judge_type,
                                 setting_data: serde_json::to_string(&judge_setting).unwrap(),
@@ -209,11 +282,23 @@ pub async fn submit_service(
                                 Err(_) => Err(ServiceError::InternalServerError),Ok(inner_result) => {
                                     match inner_result {
                                         Err(msg) => Err(ServiceError::BadRequest(msg)),
-                                        Ok(_) => {
+                                        Ok(priority) => {
                                             {
                                                 let mut lock = WAITING_QUEUE.write().unwrap();
-                                                lock.push_back(submittion_id);
+                                                lock.push_back(QueuedTask {
+                                                    id: submittion_id,
+                                                    owner_id: owner_id,
+                                                    priority: priority,
+                                                });
                                             }
+                                            STATUS_FEED.publish(StatusChange {
+                                                id: submittion_id,
+                                                owner_id: owner_id,
+                                                problem_region: problem_region,
+                                                problem_id: problem_id,
+                                                state: "Waiting".to_owned(),
+                                                verdict: None,
+                                            });
                                             judge_manager.jm.do_send(StartJudge());
                                             Ok(SubmitResult { status_id: submittion_id.to_string() })
                                         }
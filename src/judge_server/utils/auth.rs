@@ -0,0 +1,34 @@
+use crate::statics::{ JUDGE_SERVER_SECRET, JUDGE_SERVER_CREDENTIALS };
+use crate::utils::encryption::encode::get_sha256;
+
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Judge servers send the sha256 of their token, the same way QDUOJ's JudgeServer does.
+/// A server listed in the credentials file must use its own token,
+/// any other server must know the shared secret.
+pub fn authenticate_judge_server(service_url: &str, token: &str) -> Result<(), String> {
+    if let Some(expected) = JUDGE_SERVER_CREDENTIALS.get(service_url) {
+        return if constant_time_eq(&get_sha256(expected), token) {
+            Ok(())
+        } else {
+            Err("Invalid token for this judge server.".to_owned())
+        };
+    }
+
+    match &*JUDGE_SERVER_SECRET {
+        Some(secret) if constant_time_eq(&get_sha256(secret), token) => Ok(()),
+        Some(_) => Err("Invalid judge server token.".to_owned()),
+        None => Err("Unknown judge server.".to_owned()),
+    }
+}
+
+/// Loads the judge server credentials, so a broken configuration fails at startup.
+pub fn check_judge_server_auth() {
+    if JUDGE_SERVER_SECRET.is_none() && JUDGE_SERVER_CREDENTIALS.is_empty() {
+        warn!("neither JUDGE_SERVER_SECRET nor JUDGE_SERVER_CREDENTIALS is set, every judge server will be rejected");
+    } else {
+        info!("{} judge servers with own credentials", JUDGE_SERVER_CREDENTIALS.len());
+    }
+}
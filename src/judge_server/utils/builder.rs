@@ -1,14 +1,22 @@
 use crate::judge_server::model::{
     JudgeSetting,
+    Comparison,
 };
 use crate::judge_server::config::*;
-use std::fs::File;
-use std::io::prelude::*;
+use crate::judge_server::utils::filter::setting_filter;
+use crate::problem::model::LanguageLimit;
+use crate::utils::encryption::encode::get_sha256;
+use crate::test_case::utils::make::{ SPJ_SRC_STEM, INTERACTOR_SRC_STEM };
+use crate::test_case::utils::archive::get_test_case_version;
+use std::fs;
+use crate::statics::JUDGE_BACKEND;
 use actix::prelude::*;
 use diesel::prelude::*;
 use crate::database::*;
 use actix_web::web;
 
+pub const INTERACTIVE_UNSUPPORTED: &str = "Interactive problems need the local judge backend.";
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct GetTestCaseName {
     pub id: i32,
@@ -41,15 +49,37 @@ pub async fn get_judge_setting(
     problem_id: i32,
     language: String,
     src: String,
-    is_spj: bool,
-    max_cpu_time: i32,
-    max_memory: i32,
+    spj_language: Option<String>,
+    is_interactive: bool,
+    default_max_cpu_time: i32,
+    default_max_memory: i32,
+    language_limits: &[LanguageLimit],
+    comparison: Option<Comparison>,
     output: bool,
 ) -> Result<JudgeSetting, String> {
+    let language_config = match get_lang_config(&language) {
+        Some(language_config) => language_config,
+        None => { return Err("Language doesn't support.".to_owned()); },
+    };
+
+    if is_interactive && !JUDGE_BACKEND.supports_interactive() {
+        return Err(INTERACTIVE_UNSUPPORTED.to_owned());
+    }
+
+    let (mut max_cpu_time, mut max_memory) = setting_filter(&language, default_max_cpu_time, default_max_memory);
+    if let Some(limit) = language_limits.iter().find(|limit| limit.language == language) {
+        max_cpu_time = limit.max_cpu_time.unwrap_or(max_cpu_time);
+        max_memory = limit.max_memory.unwrap_or(max_memory);
+    }
+
     let mut spj_version: Option<String> = None;
     let mut spj_config: Option<SpjConfig> = None;
     let mut spj_compile_config: Option<SpjCompileConfig> = None;
     let mut spj_src: Option<String> = None;
+    let mut interactor_version: Option<String> = None;
+    let mut interactor_config: Option<SpjConfig> = None;
+    let mut interactor_compile_config: Option<SpjCompileConfig> = None;
+    let mut interactor_src: Option<String> = None;
 
     let wraped_test_case = data.db.send(GetTestCaseName {
         id: problem_id,
@@ -62,29 +92,66 @@ pub async fn get_judge_setting(
         return Err("Problem doesn't have test cases.".to_owned());
     };
 
-    if is_spj {
-        spj_version = Some("1".to_owned());
-        spj_config = Some(c_lang_spj_config());
-        spj_compile_config = Some(c_lang_spj_compile());
+    // judges synced some other way can still go without a version
+    // a test case from before versions is packed here, off the worker
+    let test_case_name = test_case.clone();
+    let test_case_version = match web::block(move || get_test_case_version(&test_case_name)).await {
+        Ok(version) => Some(version),
+        Err(e) => {
+            warn!("{}", e);
+            None
+        },
+    };
+
+    // checkers and interactors decide on their own
+    let comparison = if spj_language.is_some() { None } else { comparison };
+
+    if let Some(spj_language) = spj_language {
+        let spj = match get_spj_definition(&spj_language) {
+            Some(spj) => spj,
+            None => { return Err(format!("Special judge in \"{}\" doesn't support.", spj_language)); },
+        };
+
+        let stem = if is_interactive { INTERACTOR_SRC_STEM } else { SPJ_SRC_STEM };
+        let spj_src_path = "data/test_case/".to_owned() + &test_case + "/" + &spj.upload_name(stem);
+        let contents = fs::read_to_string(&spj_src_path)
+            .map_err(|e| format!("Error reading special judge of test case {}: {}", test_case, e))?;
 
-        let mut file = File::open("data/test_case/".to_owned() + &test_case +"/spj_src.c").expect("Error opening spj src");
-        let mut contents = String::new();
-        file.read_to_string(&mut contents).expect("Error reading spj src");
-        spj_src = Some(contents);
+        // a changed checker gets a new version, so judge servers compile it again
+        if is_interactive {
+            interactor_version = Some(get_sha256(&contents));
+            interactor_config = Some(spj.config());
+            interactor_compile_config = Some(spj.compile_config());
+            interactor_src = Some(contents);
+        } else {
+            spj_version = Some(get_sha256(&contents));
+            spj_config = Some(spj.config());
+            spj_compile_config = Some(spj.compile_config());
+            spj_src = Some(contents);
+        }
     }
     
     let judge_setting = JudgeSetting {
-        language_config: get_lang_config(&language),
+        language_config: language_config,
         src: src,
         max_cpu_time: max_cpu_time,
         max_memory: max_memory,
         test_case_id: Some(test_case),
+        test_case_version: test_case_version,
         test_case: None,
         spj_version: spj_version,
         spj_config: spj_config,
         spj_compile_config: spj_compile_config,
         spj_src: spj_src,
-        output: output,
+        interactor_version: interactor_version,
+        interactor_config: interactor_config,
+        interactor_compile_config: interactor_compile_config,
+        interactor_src: interactor_src,
+        // the online judge compares outputs it gets back
+        output: output || comparison.is_some(),
+        show_output: Some(output),
+        comparison: comparison,
+        stderr: false,
     };
 
     Ok(judge_setting)
@@ -1,12 +1,7 @@
+use crate::statics::LANGUAGES;
+
 pub fn language_filter(language: &str) -> bool {
-    match language {
-        "c" => true,
-        "cpp" => true,
-        "java" => true,
-        "py2" => true,
-        "py3" => true,
-        _ => false,
-    }
+    LANGUAGES.get(language).is_some()
 }
 
 pub fn judge_type_filter(judge_type: &str) -> bool {
@@ -18,12 +13,30 @@ pub fn judge_type_filter(judge_type: &str) -> bool {
 }
 
 pub fn setting_filter(language: &str, max_cpu_time:i32, max_memory: i32) -> (i32, i32) {
-    match language {
-        "c" => (max_cpu_time, max_memory),
-        "cpp" => (max_cpu_time, max_memory),
-        "java" => (max_cpu_time * 2, max_memory * 2),
-        "py2" => (max_cpu_time * 2, max_memory * 2),
-        "py3" => (max_cpu_time * 2, max_memory * 2),
-        _ => (max_cpu_time * 2, max_memory * 2),
+    match LANGUAGES.get(language) {
+        Some(language) => (
+            (max_cpu_time as f64 * language.time_multiplier) as i32,
+            (max_memory as f64 * language.memory_multiplier) as i32,
+        ),
+        None => (max_cpu_time, max_memory),
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_are_scaled_by_the_language_multipliers() {
+        let language = LANGUAGES.get("java").unwrap();
+        let (max_cpu_time, max_memory) = setting_filter("java", 1000, 1 << 20);
+        assert_eq!(max_cpu_time, (1000.0 * language.time_multiplier) as i32);
+        assert_eq!(max_memory, ((1 << 20) as f64 * language.memory_multiplier) as i32);
+        assert_eq!(setting_filter("cobol", 1000, 1 << 20), (1000, 1 << 20));
+    }
+
+    #[test]
+    fn only_configured_languages_pass() {
+        assert!(language_filter("cpp"));
+        assert!(!language_filter("cobol"));
+    }
+}
@@ -1,2 +1,3 @@
 pub mod filter;
-pub mod builder;
\ No newline at end of file
+pub mod builder;
+pub mod auth;
\ No newline at end of file
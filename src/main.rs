@@ -48,9 +48,14 @@ async fn main() -> io::Result<()> {
     std::env::set_var("RUST_LOG", "info, actix_web=info");
     env_logger::init();
 
+    judge_server::config::check_language_registry();
+    judge_server::utils::auth::check_judge_server_auth();
+    judge_manager::check_judge_config();
+
     // Create schema
     let db_addr = create_db_executor();
     let jm_addr = create_judge_manager();
+    judge_manager::utils::supervisor::start_judge_supervisor(jm_addr.clone());
 
     // Create Juniper schema
     let graphql_schema = std::sync::Arc::new(graphql_schema::create_schema());
@@ -86,7 +91,7 @@ async fn main() -> io::Result<()> {
             .configure(contest::route)
             .configure(test_case::route)
     })
-    .bind("0.0.0.0:8080")?
+    .bind(&*statics::BIND_ADDRESS)?
     .run()
     .await
 }
\ No newline at end of file
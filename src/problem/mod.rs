@@ -1,6 +1,7 @@
 pub mod model;
 pub mod service;
 pub mod handler;
+pub mod utils;
 
 use actix_web::web;
 use handler::*;
@@ -19,6 +19,19 @@ pub struct Problem {
     pub test_case: Option<String>,
     pub max_score: i32,
     pub opaque_output: bool,
+    pub allowed_languages: Option<Vec<String>>,
+    pub language_limits: Option<String>,
+    pub comparison_mode: Option<String>,
+    pub comparison_epsilon: Option<f64>,
+}
+
+/// Overrides the default limits of a problem for one language.
+/// A missing limit falls back to the default one scaled by the language's multiplier.
+#[derive(Debug, Clone, Serialize, Deserialize, juniper::GraphQLObject)]
+pub struct LanguageLimit {
+    pub language: String,
+    pub max_cpu_time: Option<i32>,
+    pub max_memory: Option<i32>,
 }
 
 #[derive(Debug, Clone, Serialize, juniper::GraphQLObject)]
@@ -55,6 +68,10 @@ pub struct OutProblem {
     pub accept_rate: f64,
     pub test_case: Option<String>,
     pub opaque_output: bool,
+    pub allowed_languages: Option<Vec<String>>,
+    pub language_limits: Vec<LanguageLimit>,
+    pub comparison_mode: String,
+    pub comparison_epsilon: Option<f64>,
 }
 
 impl From<Problem> for OutProblem {
@@ -79,6 +96,10 @@ impl From<Problem> for OutProblem {
             test_case,
             max_score,
             opaque_output,
+            allowed_languages,
+            language_limits,
+            comparison_mode,
+            comparison_epsilon,
         } = problem;
 
         let examples = {
@@ -115,6 +136,12 @@ impl From<Problem> for OutProblem {
             else { accept_times as f64 / submit_times as f64 },
             test_case: test_case,
             opaque_output: opaque_output,
+            allowed_languages: allowed_languages,
+            language_limits: language_limits
+                .and_then(|limits| serde_json::from_str(&limits).ok())
+                .unwrap_or_default(),
+            comparison_mode: comparison_mode.unwrap_or("exact".to_owned()),
+            comparison_epsilon: comparison_epsilon,
         }
     }
 }
\ No newline at end of file
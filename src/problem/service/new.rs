@@ -2,6 +2,8 @@ use crate::{
     schema::problems,
     database::*,
     problem::model::{ Problem, OutProblem },
+    problem::utils::language::{ check_allowed_languages, check_language_limits },
+    problem::utils::comparison::check_comparison,
     errors::{ ServiceError, ServiceResult },
 };
 use diesel::prelude::*;
@@ -31,6 +33,10 @@ struct InsertableProblem {
     test_case: Option<String>,
     max_score: i32,
     opaque_output: bool,
+    allowed_languages: Option<Vec<String>>,
+    language_limits: Option<String>,
+    comparison_mode: Option<String>,
+    comparison_epsilon: Option<f64>,
 }
 
 impl Message for NewProblemMessage {
@@ -56,6 +62,11 @@ pub struct NewProblemMessage {
     pub test_case: Option<String>,
     pub max_score: i32,
     pub opaque_output: bool,
+    pub allowed_languages: Option<Vec<String>>,
+    pub language_limits: Option<String>,
+    /// exact if not given
+    pub comparison_mode: Option<String>,
+    pub comparison_epsilon: Option<f64>,
 }
 
 impl Handler<NewProblemMessage> for DbExecutor {
@@ -64,6 +75,19 @@ impl Handler<NewProblemMessage> for DbExecutor {
     fn handle(&mut self, msg: NewProblemMessage, _: &mut Self::Context) -> Self::Result {
         use crate::schema::problems::dsl::problems;
 
+        let allowed_languages = match msg.allowed_languages {
+            Some(languages) => check_allowed_languages(languages)?,
+            None => None,
+        };
+        let language_limits = match msg.language_limits {
+            Some(limits) => check_language_limits(&limits)?,
+            None => None,
+        };
+        let (comparison_mode, comparison_epsilon) = check_comparison(
+            msg.comparison_mode.as_deref().unwrap_or("exact"),
+            msg.comparison_epsilon,
+        )?;
+
         let result = diesel::insert_into(problems)
             .values(&InsertableProblem{
                 id: msg.id,
@@ -93,6 +117,10 @@ impl Handler<NewProblemMessage> for DbExecutor {
                 test_case: msg.test_case,
                 max_score: msg.max_score,
                 opaque_output: msg.opaque_output,
+                allowed_languages: allowed_languages,
+                language_limits: language_limits,
+                comparison_mode: comparison_mode,
+                comparison_epsilon: comparison_epsilon,
             })
             .get_result::<Problem>(&self.0);
 
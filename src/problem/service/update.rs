@@ -2,6 +2,8 @@ use crate::{
     schema::problems,
     database::*,
     problem::model::{ Problem, OutProblem },
+    problem::utils::language::{ check_allowed_languages, check_language_limits },
+    problem::utils::comparison::check_comparison,
     errors::{ ServiceError, ServiceResult },
 };
 use diesel::prelude::*;
@@ -30,6 +32,10 @@ struct ProblemChange {
     test_case: Option<String>,
     max_score: Option<i32>,
     opaque_output: Option<bool>,
+    allowed_languages: Option<Option<Vec<String>>>,
+    language_limits: Option<Option<String>>,
+    comparison_mode: Option<Option<String>>,
+    comparison_epsilon: Option<Option<f64>>,
 }
 
 impl Message for UpdateProblemMessage {
@@ -56,12 +62,37 @@ pub struct UpdateProblemMessage {
     pub new_test_case: Option<String>,
     pub new_max_score: Option<i32>,
     pub new_opaque_output: Option<bool>,
+    /// an empty list allows every language again
+    pub new_allowed_languages: Option<Vec<String>>,
+    /// an empty string removes every per-language limit
+    pub new_language_limits: Option<String>,
+    pub new_comparison_mode: Option<String>,
+    /// only taken along with new_comparison_mode
+    pub new_comparison_epsilon: Option<f64>,
 }
 
 impl Handler<UpdateProblemMessage> for DbExecutor {
     type Result = Result<OutProblem, String>;
 
     fn handle(&mut self, msg: UpdateProblemMessage, _: &mut Self::Context) -> Self::Result {
+        let allowed_languages = match msg.new_allowed_languages {
+            Some(languages) => Some(check_allowed_languages(languages)?),
+            None => None,
+        };
+        let language_limits = match msg.new_language_limits {
+            Some(limits) => Some(check_language_limits(&limits)?),
+            None => None,
+        };
+        let (comparison_mode, comparison_epsilon) = match msg.new_comparison_mode {
+            Some(mode) => {
+                let (mode, epsilon) = check_comparison(&mode, msg.new_comparison_epsilon)?;
+                (Some(mode), Some(epsilon))
+            },
+            None if msg.new_comparison_epsilon.is_some() => {
+                return Err("Give new_comparison_mode along with new_comparison_epsilon.".to_owned());
+            },
+            None => (None, None),
+        };
 
         let target_id = 
             if msg.new_id.is_some() {
@@ -103,6 +134,10 @@ impl Handler<UpdateProblemMessage> for DbExecutor {
                 test_case: msg.new_test_case,
                 max_score: msg.new_max_score,
                 opaque_output: msg.new_opaque_output,
+                allowed_languages: allowed_languages,
+                language_limits: language_limits,
+                comparison_mode: comparison_mode,
+                comparison_epsilon: comparison_epsilon,
             })
             .get_result::<Problem>(&self.0);
 
@@ -0,0 +1,11 @@
+use crate::judge_server::model::Comparison;
+
+/// Checks a comparison mode and its epsilon, and returns them as stored.
+/// The exact comparison is stored as NULL, and the epsilon only with the float one.
+pub fn check_comparison(mode: &str, epsilon: Option<f64>) -> Result<(Option<String>, Option<f64>), String> {
+    match Comparison::from_mode(mode, epsilon)? {
+        None => Ok((None, None)),
+        Some(Comparison::Float { epsilon }) => Ok((Some(mode.to_owned()), Some(epsilon))),
+        Some(_) => Ok((Some(mode.to_owned()), None)),
+    }
+}
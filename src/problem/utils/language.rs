@@ -0,0 +1,74 @@
+use crate::problem::model::LanguageLimit;
+use crate::statics::LANGUAGES;
+use std::collections::HashSet;
+
+/// An empty list means every language is allowed, and is stored as NULL.
+pub fn check_allowed_languages(languages: Vec<String>) -> Result<Option<Vec<String>>, String> {
+    if languages.is_empty() { return Ok(None); }
+
+    for language in languages.iter() {
+        if LANGUAGES.get(language).is_none() {
+            return Err(format!("Language \"{}\" doesn't support.", language));
+        }
+    }
+
+    Ok(Some(languages))
+}
+
+/// Parses limits given like `[{"language": "py3", "max_cpu_time": 3000}]`,
+/// and returns them normalized for storing. An empty list is stored as NULL.
+pub fn check_language_limits(limits: &str) -> Result<Option<String>, String> {
+    if limits.trim().is_empty() { return Ok(None); }
+
+    let limits: Vec<LanguageLimit> = serde_json::from_str(limits)
+        .map_err(|e| format!("Invalid language limits: {}", e))?;
+    if limits.is_empty() { return Ok(None); }
+
+    let mut languages = HashSet::new();
+    for limit in limits.iter() {
+        if LANGUAGES.get(&limit.language).is_none() {
+            return Err(format!("Language \"{}\" doesn't support.", limit.language));
+        }
+        if !languages.insert(limit.language.as_str()) {
+            return Err(format!("Language \"{}\" has more than one limit.", limit.language));
+        }
+        if limit.max_cpu_time.map(|time| time <= 0).unwrap_or(false)
+            || limit.max_memory.map(|memory| memory <= 0).unwrap_or(false) {
+            return Err(format!("Limits of language \"{}\" must be positive.", limit.language));
+        }
+    }
+
+    Ok(Some(serde_json::to_string(&limits).unwrap()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_allowed_language_means_all_of_them() {
+        assert_eq!(check_allowed_languages(Vec::new()), Ok(None));
+        assert_eq!(check_allowed_languages(vec!["c".to_owned()]), Ok(Some(vec!["c".to_owned()])));
+        assert!(check_allowed_languages(vec!["c".to_owned(), "cobol".to_owned()]).is_err());
+    }
+
+    #[test]
+    fn language_limits_are_normalized() {
+        assert_eq!(check_language_limits(" "), Ok(None));
+        assert_eq!(check_language_limits("[]"), Ok(None));
+
+        let limits = check_language_limits(r#"[{ "language": "py3", "max_cpu_time": 3000 }]"#).unwrap().unwrap();
+        let limits: Vec<LanguageLimit> = serde_json::from_str(&limits).unwrap();
+        assert_eq!(limits[0].language, "py3");
+        assert_eq!(limits[0].max_cpu_time, Some(3000));
+        assert_eq!(limits[0].max_memory, None);
+    }
+
+    #[test]
+    fn bad_language_limits_are_rejected() {
+        assert!(check_language_limits("not json").is_err());
+        assert!(check_language_limits(r#"[{ "language": "cobol", "max_cpu_time": 1000 }]"#).is_err());
+        assert!(check_language_limits(r#"[{ "language": "c", "max_cpu_time": 0 }]"#).is_err());
+        assert!(check_language_limits(r#"[{ "language": "c", "max_memory": 1 }, { "language": "c" }]"#).is_err());
+    }
+}
@@ -0,0 +1,2 @@
+pub mod language;
+pub mod comparison;
\ No newline at end of file
@@ -19,6 +19,17 @@ table! {
     }
 }
 
+table! {
+    judge_server_rejections (id) {
+        id -> Int4,
+        service_url -> Nullable<Text>,
+        hostname -> Nullable<Text>,
+        remote_addr -> Nullable<Text>,
+        reason -> Text,
+        create_time -> Timestamp,
+    }
+}
+
 table! {
     problems (region, id) {
         id -> Int4,
@@ -40,6 +51,10 @@ table! {
         test_case -> Nullable<Text>,
         max_score -> Int4,
         opaque_output -> Bool,
+        allowed_languages -> Nullable<Array<Text>>,
+        language_limits -> Nullable<Text>,
+        comparison_mode -> Nullable<Text>,
+        comparison_epsilon -> Nullable<Float8>,
     }
 }
 
@@ -54,6 +69,25 @@ table! {
     }
 }
 
+table! {
+    rejudge_items (rejudge_id, status_id) {
+        rejudge_id -> Uuid,
+        status_id -> Uuid,
+        old_result -> Nullable<Text>,
+        old_score -> Nullable<Float8>,
+        old_verdict -> Nullable<Text>,
+    }
+}
+
+table! {
+    rejudges (id) {
+        id -> Uuid,
+        target -> Text,
+        operator_id -> Int4,
+        create_time -> Timestamp,
+    }
+}
+
 table! {
     status (id) {
         id -> Uuid,
@@ -72,6 +106,12 @@ table! {
         finish_time -> Nullable<Timestamp>,
         language -> Text,
         host_name -> Nullable<Text>,
+        retry_times -> Int4,
+        verdict -> Nullable<Text>,
+        verdict_test_case -> Nullable<Text>,
+        max_time -> Nullable<Int4>,
+        max_memory -> Nullable<Int4>,
+        judge_server_url -> Nullable<Text>,
     }
 }
 
@@ -80,6 +120,8 @@ table! {
         name -> Text,
         is_spj -> Bool,
         count -> Int4,
+        spj_language -> Nullable<Text>,
+        is_interactive -> Bool,
     }
 }
 
@@ -102,14 +144,20 @@ joinable!(contest_register_lists -> contests (contest_region));
 joinable!(contest_register_lists -> users (user_id));
 joinable!(problems -> regions (region));
 joinable!(problems -> test_cases (test_case));
+joinable!(rejudge_items -> rejudges (rejudge_id));
+joinable!(rejudge_items -> status (status_id));
+joinable!(rejudges -> users (operator_id));
 joinable!(status -> regions (problem_region));
 joinable!(status -> users (owner_id));
 
 allow_tables_to_appear_in_same_query!(
     contest_register_lists,
     contests,
+    judge_server_rejections,
     problems,
     regions,
+    rejudge_items,
+    rejudges,
     status,
     test_cases,
     users,
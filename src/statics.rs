@@ -1,16 +1,23 @@
 use std::{
     sync::RwLock,
-    collections::{ BTreeMap, HashMap, VecDeque },
+    collections::{ BTreeMap, HashMap },
     time::SystemTime,
+    fs,
 };
-use crate::judge_server::model::JudgeServerInfo;
+use crate::judge_server::model::{ JudgeServerInfo, JudgeServerControl };
+use crate::judge_server::config::LanguageRegistry;
+use crate::judge_manager::utils::scheduler::{ SchedulingPolicy, get_scheduling_policy };
+use crate::judge_manager::utils::queue::JudgeQueue;
+use crate::judge_manager::utils::progress::ProgressHub;
+use crate::judge_manager::utils::feed::StatusFeed;
+use crate::judge_manager::utils::run::RunRegistry;
+use crate::judge_manager::backend::{ JudgeBackend, get_judge_backend };
 use regex::Regex;
-use uuid::Uuid;
 use dotenv::dotenv;
 use std::env;
 
 lazy_static! {
-    pub static ref  WAITING_QUEUE: RwLock<VecDeque::<Uuid>> = RwLock::new(VecDeque::new());
+    pub static ref  WAITING_QUEUE: RwLock<JudgeQueue> = RwLock::new(JudgeQueue::new());
     pub static ref ACCESS_KEY_ID: String = {
         dotenv().ok();
         env::var("ACCESS_KEY_ID").expect("ACCESS_KEY_ID must be set")
@@ -23,7 +30,123 @@ lazy_static! {
         dotenv().ok();
         env::var("DATABASE_URL").expect("DATABASE_URL must be set")  
     };
+    pub static ref BIND_ADDRESS: String = {
+        dotenv().ok();
+        env::var("BIND_ADDRESS").unwrap_or("0.0.0.0:8080".to_owned())
+    };
+    pub static ref JUDGE_SERVER_SECRET: Option<String> = {
+        dotenv().ok();
+        env::var("JUDGE_SERVER_SECRET").ok().filter(|secret| !secret.is_empty())
+    };
+    // json file mapping each judge server's service url to its own token
+    pub static ref JUDGE_SERVER_CREDENTIALS: HashMap<String, String> = {
+        dotenv().ok();
+        match env::var("JUDGE_SERVER_CREDENTIALS") {
+            Err(_) => HashMap::new(),
+            Ok(path) => {
+                let content = fs::read_to_string(&path).expect("Error reading JUDGE_SERVER_CREDENTIALS");
+                serde_json::from_str(&content).expect("JUDGE_SERVER_CREDENTIALS must map service urls to tokens")
+            },
+        }
+    };
+    pub static ref LANGUAGES: LanguageRegistry = {
+        dotenv().ok();
+        let path = env::var("LANGUAGE_CONFIG").unwrap_or("config/languages.json".to_owned());
+        LanguageRegistry::load(&path).unwrap_or_else(|e| panic!("{}", e))
+    };
+    pub static ref JUDGE_REQUEST_TIMEOUT: u64 = {
+        dotenv().ok();
+        env::var("JUDGE_REQUEST_TIMEOUT").ok().and_then(|v| v.parse().ok()).unwrap_or(120)
+    };
+    pub static ref JUDGE_CONNECT_TIMEOUT: u64 = {
+        dotenv().ok();
+        env::var("JUDGE_CONNECT_TIMEOUT").ok().and_then(|v| v.parse().ok()).unwrap_or(5)
+    };
+    pub static ref JUDGE_MAX_RETRY_TIMES: i32 = {
+        dotenv().ok();
+        env::var("JUDGE_MAX_RETRY_TIMES").ok().and_then(|v| v.parse().ok()).unwrap_or(3)
+    };
+    // in seconds, doubled after every failed attempt
+    pub static ref JUDGE_RETRY_BASE_DELAY: u64 = {
+        dotenv().ok();
+        env::var("JUDGE_RETRY_BASE_DELAY").ok().and_then(|v| v.parse().ok()).unwrap_or(2)
+    };
+    // in seconds, a judge server silent for longer is pinged and evicted if it doesn't answer
+    pub static ref JUDGE_HEARTBEAT_TIMEOUT: u64 = {
+        dotenv().ok();
+        env::var("JUDGE_HEARTBEAT_TIMEOUT").ok().and_then(|v| v.parse().ok()).unwrap_or(15)
+    };
+    // in seconds
+    pub static ref JUDGE_SUPERVISOR_INTERVAL: u64 = {
+        dotenv().ok();
+        env::var("JUDGE_SUPERVISOR_INTERVAL").ok().and_then(|v| v.parse().ok()).unwrap_or(5)
+    };
+    // 0 means no limit
+    pub static ref MAX_IN_FLIGHT_PER_USER: i64 = {
+        dotenv().ok();
+        env::var("MAX_IN_FLIGHT_PER_USER").ok().and_then(|v| v.parse().ok()).unwrap_or(8)
+    };
+    pub static ref JUDGE_SCHEDULING_POLICY: String = {
+        dotenv().ok();
+        env::var("JUDGE_SCHEDULING_POLICY").unwrap_or("least_loaded".to_owned())
+    };
+    // JUDGE_SCHEDULING_POLICY and JUDGE_BACKEND are checked at startup
+    pub static ref SCHEDULING_POLICY: Box<dyn SchedulingPolicy> = {
+        let policy = get_scheduling_policy(&JUDGE_SCHEDULING_POLICY).unwrap_or_else(|msg| panic!("{}", msg));
+        info!("using judge scheduling policy: {}", policy.name());
+        policy
+    };
+    pub static ref JUDGE_BACKEND_NAME: String = {
+        dotenv().ok();
+        env::var("JUDGE_BACKEND").unwrap_or("remote".to_owned())
+    };
+    pub static ref JUDGE_BACKEND: Box<dyn JudgeBackend> = {
+        let backend = get_judge_backend(&JUDGE_BACKEND_NAME).unwrap_or_else(|msg| panic!("{}", msg));
+        info!("using judge backend: {}", backend.name());
+        backend
+    };
+    pub static ref LOCAL_JUDGE_MAX_TASKS: i32 = {
+        dotenv().ok();
+        env::var("LOCAL_JUDGE_MAX_TASKS").ok().and_then(|v| v.parse().ok()).unwrap_or(2)
+    };
+    pub static ref LOCAL_JUDGE_WORK_DIR: String = {
+        dotenv().ok();
+        env::var("LOCAL_JUDGE_WORK_DIR").unwrap_or("data/judge_run".to_owned())
+    };
+    // runs submissions as the server user with the whole file system in reach, for development only
+    pub static ref LOCAL_JUDGE_INSECURE: bool = {
+        dotenv().ok();
+        env::var("LOCAL_JUDGE_INSECURE").map_or(false, |v| v == "1" || v == "true")
+    };
+    // nobody, unless configured
+    pub static ref LOCAL_JUDGE_RUN_UID: u32 = {
+        dotenv().ok();
+        env::var("LOCAL_JUDGE_RUN_UID").ok().and_then(|v| v.parse().ok()).unwrap_or(65534)
+    };
+    pub static ref LOCAL_JUDGE_RUN_GID: u32 = {
+        dotenv().ok();
+        env::var("LOCAL_JUDGE_RUN_GID").ok().and_then(|v| v.parse().ok()).unwrap_or(65534)
+    };
+    // what submissions and compilers see of the host besides their work directory, read-only
+    pub static ref LOCAL_JUDGE_VISIBLE_PATHS: Vec<String> = {
+        dotenv().ok();
+        env::var("LOCAL_JUDGE_VISIBLE_PATHS").unwrap_or("/bin:/lib:/lib32:/lib64:/usr:/etc".to_owned())
+            .split(':')
+            .filter(|path| !path.is_empty())
+            .map(|path| path.to_owned())
+            .collect()
+    };
+    pub static ref JUDGE_PROGRESS: ProgressHub = ProgressHub::new();
+    pub static ref STATUS_FEED: StatusFeed = StatusFeed::new();
+    pub static ref PENDING_RUNS: RunRegistry = RunRegistry::new();
+    // seconds a custom input run may wait in the queue and run
+    pub static ref JUDGE_RUN_TIMEOUT: u64 = {
+        dotenv().ok();
+        env::var("JUDGE_RUN_TIMEOUT").ok().and_then(|v| v.parse().ok()).unwrap_or(60)
+    };
     pub static ref JUDGE_SERVER_INFOS: RwLock<HashMap<String, JudgeServerInfo>> = RwLock::new(HashMap::new());
+    // by service url, only ever locked after JUDGE_SERVER_INFOS
+    pub static ref JUDGE_SERVER_CONTROLS: RwLock<HashMap<String, JudgeServerControl>> = RwLock::new(HashMap::new());
     pub static ref VERIFICATION_MAP: RwLock<BTreeMap<String, (String, SystemTime)>> = RwLock::new(BTreeMap::new());
     pub static ref RE_EMAIL: Regex = Regex::new(r"^\w+([-+.]\w+)*@\w+([-.]\w+)*\.\w+([-.]\w+)*$").unwrap();
     pub static ref RE_MOBILE: Regex = Regex::new(r"^((13[0-9])|(14[5|7])|(15([0-3]|[5-9]))|(18[0,5-9]))\d{8}$").unwrap();
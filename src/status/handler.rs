@@ -1,13 +1,23 @@
 use crate::{
     database::*,
+    judge_manager::*,
     status::service::{
         catalog::{ GetStatusCatalogMessage, get_status_catalog_service },
-        get::{ get_status_service, GetStatusMessage }
+        get::{ get_status_service, GetStatusMessage },
+        rejudge::{
+            rejudge_service, RejudgeTarget, RejudgeStatusForm, RejudgeProblemForm, RejudgeRegionForm,
+            get_rejudge_report_service, GetRejudgeReportMessage,
+        },
+        progress::{
+            watch_progress_service, WatchProgressMessage,
+            watch_status_feed_service, WatchStatusFeedMessage,
+        },
     },
     errors::ServiceError,
 };
 use actix_web::{HttpResponse, web};
 use actix_identity::Identity;
+use futures::StreamExt;
 
 pub async fn get_status_catalog(
     data: web::Data<DBState>, 
@@ -18,7 +28,7 @@ pub async fn get_status_catalog(
         data, form.region.clone(), form.count_per_page, 
         form.problem_id, form.problem_title.clone(), form.user_id,
         form.username.clone(), form.language.clone(),
-        form.page_number, id
+        form.verdict.clone(), form.page_number, id
     ).await
     .map(|res| HttpResponse::Ok().json(&res))
 }
@@ -30,4 +40,76 @@ pub async fn get_status(
 ) -> Result<HttpResponse, ServiceError> {
     get_status_service(data, form.to_owned(), id).await
     .map(|res| HttpResponse::Ok().json(&res))
-}
\ No newline at end of file
+}
+
+pub async fn rejudge_status(
+    data: web::Data<DBState>,
+    judge_manager: web::Data<JMState>,
+    form: web::Form<RejudgeStatusForm>,
+    id: Identity,
+) -> Result<HttpResponse, ServiceError> {
+    rejudge_service(data, judge_manager, RejudgeTarget::Status(form.id), id).await
+    .map(|res| HttpResponse::Ok().json(&res))
+}
+
+pub async fn rejudge_problem(
+    data: web::Data<DBState>,
+    judge_manager: web::Data<JMState>,
+    form: web::Form<RejudgeProblemForm>,
+    id: Identity,
+) -> Result<HttpResponse, ServiceError> {
+    rejudge_service(
+        data, judge_manager,
+        RejudgeTarget::Problem(form.region.clone(), form.problem_id),
+        id
+    ).await
+    .map(|res| HttpResponse::Ok().json(&res))
+}
+
+pub async fn rejudge_region(
+    data: web::Data<DBState>,
+    judge_manager: web::Data<JMState>,
+    form: web::Form<RejudgeRegionForm>,
+    id: Identity,
+) -> Result<HttpResponse, ServiceError> {
+    rejudge_service(data, judge_manager, RejudgeTarget::Region(form.region.clone()), id).await
+    .map(|res| HttpResponse::Ok().json(&res))
+}
+
+pub async fn get_rejudge_report(
+    data: web::Data<DBState>,
+    form: web::Form<GetRejudgeReportMessage>,
+    id: Identity,
+) -> Result<HttpResponse, ServiceError> {
+    get_rejudge_report_service(data, form.to_owned(), id).await
+    .map(|res| HttpResponse::Ok().json(&res))
+}
+
+fn to_server_sent_event<T: serde::Serialize>(name: &str, data: &T) -> Result<web::Bytes, actix_web::Error> {
+    Ok(web::Bytes::from(format!("event: {}\ndata: {}\n\n", name, serde_json::to_string(data).unwrap_or_default())))
+}
+
+/// Server-sent events, one for each step of judging until the status is finished.
+pub async fn watch_progress(
+    data: web::Data<DBState>,
+    query: web::Query<WatchProgressMessage>,
+) -> Result<HttpResponse, ServiceError> {
+    let events = watch_progress_service(data, query.into_inner()).await?;
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .set_header("Cache-Control", "no-cache")
+        .streaming(events.map(|event| to_server_sent_event(event.name(), &event))))
+}
+
+/// Server-sent events of status changes, kept open until the client goes away.
+pub async fn watch_status_feed(
+    data: web::Data<DBState>,
+    query: web::Query<WatchStatusFeedMessage>,
+    id: Identity,
+) -> Result<HttpResponse, ServiceError> {
+    let changes = watch_status_feed_service(data, query.into_inner(), id).await?;
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .set_header("Cache-Control", "no-cache")
+        .streaming(changes.map(|change| to_server_sent_event("status_change", &change))))
+}
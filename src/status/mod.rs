@@ -11,5 +11,11 @@ pub fn route(cfg: &mut web::ServiceConfig) {
         web::scope("/status")
             .service(web::resource("/get_catalog").route(web::post().to(get_status_catalog)))
             .service(web::resource("/get").route(web::post().to(get_status)))
+            .service(web::resource("/progress").route(web::get().to(watch_progress)))
+            .service(web::resource("/feed").route(web::get().to(watch_status_feed)))
+            .service(web::resource("/rejudge").route(web::post().to(rejudge_status)))
+            .service(web::resource("/rejudge_problem").route(web::post().to(rejudge_problem)))
+            .service(web::resource("/rejudge_region").route(web::post().to(rejudge_region)))
+            .service(web::resource("/get_rejudge_report").route(web::post().to(get_rejudge_report)))
     );
 }
\ No newline at end of file
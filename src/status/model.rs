@@ -20,6 +20,72 @@ pub struct Status {
     pub finish_time: Option<NaiveDateTime>,
     pub language: String,
     pub host_name: Option<String>,
+    pub retry_times: i32,
+    pub verdict: Option<String>,
+    pub verdict_test_case: Option<String>,
+    pub max_time: Option<i32>,
+    pub max_memory: Option<i32>,
+    /// the judge server a pending status was sent to
+    pub judge_server_url: Option<String>,
+}
+
+/// Verdict of a whole submission, stored as text in `status.verdict`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, juniper::GraphQLEnum)]
+pub enum Verdict {
+    AC,
+    WA,
+    TLE,
+    MLE,
+    RE,
+    CE,
+    SE,
+    PE,
+}
+
+impl Verdict {
+    /// Maps a test case result code, real time limit exceeded counts as TLE.
+    pub fn from_result(result: i32) -> Verdict {
+        match result {
+            0 => Verdict::AC,
+            -1 => Verdict::WA,
+            -2 => Verdict::PE,
+            1 | 2 => Verdict::TLE,
+            3 => Verdict::MLE,
+            4 => Verdict::RE,
+            _ => Verdict::SE,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Verdict::AC => "AC",
+            Verdict::WA => "WA",
+            Verdict::TLE => "TLE",
+            Verdict::MLE => "MLE",
+            Verdict::RE => "RE",
+            Verdict::CE => "CE",
+            Verdict::SE => "SE",
+            Verdict::PE => "PE",
+        }
+    }
+}
+
+impl std::str::FromStr for Verdict {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Verdict, String> {
+        match s {
+            "AC" => Ok(Verdict::AC),
+            "WA" => Ok(Verdict::WA),
+            "TLE" => Ok(Verdict::TLE),
+            "MLE" => Ok(Verdict::MLE),
+            "RE" => Ok(Verdict::RE),
+            "CE" => Ok(Verdict::CE),
+            "SE" => Ok(Verdict::SE),
+            "PE" => Ok(Verdict::PE),
+            _ => Err(format!("Unknown verdict \"{}\".", s)),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Insertable, Queryable)]
@@ -51,12 +117,27 @@ pub struct JudgeResultData {
     pub test_case: String,
     pub output_md5: Option<String>,
     pub output: Option<String>,
+    /// share of the test case earned, set by checkers giving partial scores
+    pub score: Option<f64>,
+    /// only asked for by custom input runs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stderr: Option<String>,
+}
+
+/// Points earned in one subtask, on the scale of the problem's max_score.
+#[derive(Debug, Clone, Serialize, Deserialize, juniper::GraphQLObject)]
+pub struct SubtaskResult {
+    pub id: i32,
+    pub score: f64,
+    pub full_score: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JudgeResult {
     pub err: Option<String>,
     pub data: Vec<JudgeResultData>,
+    /// filled in when the result is saved, if the test case has subtasks
+    pub subtasks: Option<Vec<SubtaskResult>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, juniper::GraphQLObject)]
@@ -71,12 +152,14 @@ pub struct MappedJudgeResultData {
     pub test_case: String,
     pub output_md5: Option<String>,
     pub output: Option<String>,
+    pub score: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, juniper::GraphQLObject)]
 pub struct MappedJudgeResult {
     pub err: Option<String>,
     pub data: Vec<MappedJudgeResultData>,
+    pub subtasks: Option<Vec<SubtaskResult>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, juniper::GraphQLObject)]
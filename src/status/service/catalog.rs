@@ -1,6 +1,7 @@
 use crate::{
     database::*,
     errors::{ServiceError, ServiceResult},
+    status::model::Verdict,
 };
 use chrono::*;
 use diesel::prelude::*;
@@ -32,6 +33,11 @@ pub struct StatusCatalogElement {
     pub judge_type: String,
     pub result: Option<String>,
     pub score: Option<f64>,
+    pub verdict: Option<Verdict>,
+    /// the first failing test case
+    pub verdict_test_case: Option<String>,
+    pub max_time: Option<i32>,
+    pub max_memory: Option<i32>,
     pub submit_time: String,
     pub start_pend_time: Option<String>,
     pub finish_time: Option<String>,
@@ -52,6 +58,7 @@ pub struct GetStatusCatalogMessage {
     pub user_id: Option<i32>,
     pub username: Option<String>,
     pub language: Option<String>,
+    pub verdict: Option<String>,
     pub page_number: i32,
 }
 
@@ -77,6 +84,7 @@ impl Handler<GetStatusCatalogMessage> for DbExecutor {
             .filter(status::language.ilike(
                 "%".to_owned() + &msg.language.clone().unwrap_or("".to_owned()) + "%"
             ).or(msg.language.is_none()))
+            .filter(status::verdict.eq(msg.verdict.clone()).or(msg.verdict.is_none()))
             .inner_join(problems::table.on(status::problem_id.eq(problems::id)
                 .and(status::problem_region.eq(problems::region))
                 .and(problems::title.ilike(
@@ -97,6 +105,7 @@ impl Handler<GetStatusCatalogMessage> for DbExecutor {
             .filter(status::language.ilike(
                 "%".to_owned() + &msg.language.clone().unwrap_or("".to_owned()) + "%"
             ).or(msg.language.is_none()))
+            .filter(status::verdict.eq(msg.verdict.clone()).or(msg.verdict.is_none()))
             .inner_join(problems::table.on(status::problem_id.eq(problems::id)
                 .and(status::problem_region.eq(problems::region))
                 .and(problems::title.ilike(
@@ -119,6 +128,10 @@ impl Handler<GetStatusCatalogMessage> for DbExecutor {
                 status::judge_type,
                 status::result,
                 status::score,
+                status::verdict,
+                status::verdict_test_case,
+                status::max_time,
+                status::max_memory,
                 status::submit_time,
                 status::start_pend_time,
                 status::finish_time,
@@ -137,6 +150,10 @@ impl Handler<GetStatusCatalogMessage> for DbExecutor {
                 String,
                 Option<String>,
                 Option<f64>,
+                Option<String>,
+                Option<String>,
+                Option<i32>,
+                Option<i32>,
                 NaiveDateTime,
                 Option<NaiveDateTime>,
                 Option<NaiveDateTime>,
@@ -160,6 +177,10 @@ impl Handler<GetStatusCatalogMessage> for DbExecutor {
             t_judge_type,
             t_result,
             t_score,
+            t_verdict,
+            t_verdict_test_case,
+            t_max_time,
+            t_max_memory,
             t_submit_time,
             t_start_pend_time,
             t_finish_time,
@@ -180,6 +201,10 @@ impl Handler<GetStatusCatalogMessage> for DbExecutor {
                 judge_type: t_judge_type,
                 result: t_result,
                 score: t_score,
+                verdict: t_verdict.and_then(|verdict| verdict.parse().ok()),
+                verdict_test_case: t_verdict_test_case,
+                max_time: t_max_time,
+                max_memory: t_max_memory,
                 submit_time: t_submit_time.to_string(),
                 start_pend_time: if t_start_pend_time.is_none() { None } else { Some(t_start_pend_time.unwrap().to_string()) },
                 finish_time: if t_finish_time.is_none() { None } else { Some(t_finish_time.unwrap().to_string()) },
@@ -199,11 +224,15 @@ pub async fn get_status_catalog_service(
     user_id: Option<i32>,
     username: Option<String>,
     language: Option<String>,
+    verdict: Option<String>,
     page_number: i32,
     _id: Identity,
 ) -> ServiceResult<StatusCatalog> {
     if count_per_page <= 0 { return Err(ServiceError::BadRequest("Count per page should be larger than 0.".to_owned())); }
     if page_number <= 0 { return Err(ServiceError::BadRequest("Page number should be larger than 0.".to_owned())); }
+    if let Some(verdict) = &verdict {
+        verdict.parse::<Verdict>().map_err(ServiceError::BadRequest)?;
+    }
 
     let db_result = data.db.send(GetStatusCatalogMessage {
         region: region,
@@ -214,6 +243,7 @@ pub async fn get_status_catalog_service(
         username: username,
         page_number: page_number,
         language: language,
+        verdict: verdict,
     }).await;
 
     match db_result {
@@ -4,6 +4,7 @@ use crate::{
     status::model::*,
     status::utils::mapper::*,
     judge_server::model::*,
+    user::service::me::auth_check,
 };
 use diesel::prelude::*;
 use actix::prelude::*;
@@ -25,8 +26,15 @@ pub struct ProblemPreview {
 
 #[derive(Debug, Clone, Serialize, juniper::GraphQLObject)]
 pub struct DetailedStatus {
+    pub owner_id: i32,
     pub language: String,
     pub src: String,
+    pub verdict: Option<Verdict>,
+    pub verdict_test_case: Option<String>,
+    pub max_time: Option<i32>,
+    pub max_memory: Option<i32>,
+    /// only shown to the owner and staff
+    pub compile_error: Option<String>,
     pub judge_result: Option<MappedJudgeResult>,
     pub err_result: Option<ErrResult>,
 }
@@ -66,14 +74,27 @@ impl Handler<GetStatusMessage> for DbExecutor {
             }
         }
         
+        let compile_error = if status.verdict == Some(Verdict::CE.as_str().to_owned()) {
+            status.err_reason.clone()
+        } else {
+            None
+        };
+
         Ok(DetailedStatus{
+            owner_id: status.owner_id,
             language: status.language,
             src: judge_setting.src,
+            verdict: status.verdict.and_then(|verdict| verdict.parse().ok()),
+            verdict_test_case: status.verdict_test_case,
+            max_time: status.max_time,
+            max_memory: status.max_memory,
+            compile_error: compile_error,
             judge_result: if judge_result.is_none() { None } else {
                 let inner_result = judge_result.unwrap();
                 let mut final_output = MappedJudgeResult {
                     err: inner_result.err,
                     data: Vec::new(),
+                    subtasks: inner_result.subtasks.clone(),
                 };
                 for data in inner_result.data.iter() {
                     final_output.data.push(MappedJudgeResultData {
@@ -87,6 +108,7 @@ impl Handler<GetStatusMessage> for DbExecutor {
                         test_case: data.test_case.clone(),
                         output_md5: data.output_md5.clone(),
                         output: data.output.clone(),
+                        score: data.score,
                     });
                 }
                 Some(final_output)
@@ -99,17 +121,29 @@ impl Handler<GetStatusMessage> for DbExecutor {
 pub async fn get_status_service(
     data: web::Data<DBState>,
     msg: GetStatusMessage,
-    _id: Identity,
+    id: Identity,
 ) -> ServiceResult<DetailedStatus> {
     let db_result = data.db.send(msg).await;
 
-    match db_result {
-        Err(_) => Err(ServiceError::InternalServerError),
+    let mut result = match db_result {
+        Err(_) => { return Err(ServiceError::InternalServerError); },
         Ok(inner_result) => {
             match inner_result {
-                Err(msg) => Err(ServiceError::BadRequest(msg)),
-                Ok(result) => Ok(result),
+                Err(msg) => { return Err(ServiceError::BadRequest(msg)); },
+                Ok(result) => result,
+            }
+        }
+    };
+
+    if result.compile_error.is_some() {
+        let is_owner = id.identity().map(|user_id| user_id == result.owner_id.to_string()).unwrap_or(false);
+        if !is_owner && auth_check(data.clone(), id, "teacher".to_owned()).await.is_err() {
+            result.compile_error = None;
+            if let Some(err_result) = result.err_result.as_mut() {
+                err_result.data = String::new();
             }
         }
     }
+
+    Ok(result)
 }
\ No newline at end of file
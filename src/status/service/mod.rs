@@ -1,2 +1,4 @@
 pub mod catalog;
-pub mod get;
\ No newline at end of file
+pub mod get;
+pub mod rejudge;
+pub mod progress;
@@ -0,0 +1,157 @@
+use crate::{
+    database::*,
+    errors::{ServiceError, ServiceResult},
+    statics::{ JUDGE_PROGRESS, STATUS_FEED },
+    judge_manager::utils::progress::ProgressEvent,
+    judge_manager::utils::feed::{ StatusChange, StatusFilter },
+    status::model::Verdict,
+    utils::time::get_cur_naive_date_time,
+};
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use actix::prelude::*;
+use actix_web::web;
+use actix_identity::Identity;
+use futures::stream::{ self, BoxStream, StreamExt };
+use uuid::Uuid;
+use atoi::atoi;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WatchProgressMessage {
+    pub id: Uuid,
+}
+
+impl Message for WatchProgressMessage {
+    type Result = Result<(String, Option<String>, Option<String>), String>;
+}
+
+impl Handler<WatchProgressMessage> for DbExecutor {
+    type Result = Result<(String, Option<String>, Option<String>), String>;
+
+    fn handle(&mut self, msg: WatchProgressMessage, _: &mut Self::Context) -> Self::Result {
+        use crate::schema::status;
+
+        status::table
+            .filter(status::id.eq(msg.id))
+            .select((status::state, status::host_name, status::verdict))
+            .first::<(String, Option<String>, Option<String>)>(&self.0)
+            .map_err(|_| "Status not found.".to_owned())
+    }
+}
+
+/// Events of one status from now on, starting with the state it is in.
+/// A finished status only gives its verdict.
+pub async fn watch_progress_service(
+    data: web::Data<DBState>,
+    msg: WatchProgressMessage,
+) -> ServiceResult<BoxStream<'static, ProgressEvent>> {
+    let status_id = msg.id;
+    // subscribe before looking, so the finished event can't slip through in between
+    let receiver = JUDGE_PROGRESS.subscribe(status_id);
+    let db_result = data.db.send(msg).await;
+    // nothing is published for these anymore
+    let give_up = |receiver| {
+        drop(receiver);
+        JUDGE_PROGRESS.unsubscribe_closed(status_id);
+    };
+
+    let (state, host_name, verdict) = match db_result {
+        Err(_) => {
+            give_up(receiver);
+            return Err(ServiceError::InternalServerError);
+        },
+        Ok(inner_result) => {
+            match inner_result {
+                Err(msg) => {
+                    give_up(receiver);
+                    return Err(ServiceError::BadRequest(msg));
+                },
+                Ok(status) => status,
+            }
+        }
+    };
+
+    let current = match state.as_str() {
+        "Finished" => {
+            give_up(receiver);
+            let verdict = verdict.and_then(|verdict| verdict.parse().ok()).unwrap_or(Verdict::SE);
+            return Ok(stream::iter(vec![ProgressEvent::Finished { verdict: verdict }]).boxed());
+        },
+        "Pending" => ProgressEvent::Pending { host_name: host_name.unwrap_or_default() },
+        _ => ProgressEvent::Waiting,
+    };
+    Ok(stream::iter(vec![current]).chain(receiver).boxed())
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WatchStatusFeedMessage {
+    /// finished statuses of this region, or changes of the viewer's own statuses if not given
+    pub region: Option<String>,
+}
+
+/// When the contest of a region ends and for how long before that it is sealed.
+struct GetSealMessage {
+    region: String,
+}
+
+impl Message for GetSealMessage {
+    type Result = Result<Option<(NaiveDateTime, i32)>, String>;
+}
+
+impl Handler<GetSealMessage> for DbExecutor {
+    type Result = Result<Option<(NaiveDateTime, i32)>, String>;
+
+    fn handle(&mut self, msg: GetSealMessage, _: &mut Self::Context) -> Self::Result {
+        use crate::schema::contests;
+
+        let seal = contests::table
+            .filter(contests::region.eq(msg.region))
+            .select((contests::end_time, contests::seal_before_end))
+            .first::<(NaiveDateTime, Option<i32>)>(&self.0)
+            .optional()
+            .map_err(|_| "Error while getting contest.".to_owned())?;
+
+        Ok(seal.and_then(|(end_time, seal_before_end)| seal_before_end.map(|seal_before_end| (end_time, seal_before_end))))
+    }
+}
+
+/// Same window as the ACM rank, where verdicts aren't shown.
+fn is_sealed(end_time: NaiveDateTime, seal_before_end: i32) -> bool {
+    let cur_time = get_cur_naive_date_time();
+    cur_time < end_time && cur_time.timestamp() + seal_before_end as i64 >= end_time.timestamp()
+}
+
+pub async fn watch_status_feed_service(
+    data: web::Data<DBState>,
+    msg: WatchStatusFeedMessage,
+    id: Identity,
+) -> ServiceResult<BoxStream<'static, StatusChange>> {
+    let user_id = match id.identity() {
+        Some(user_id) => atoi::<i32>(user_id.as_bytes()).ok_or(ServiceError::Unauthorized)?,
+        None => { return Err(ServiceError::NotLogined); },
+    };
+
+    let region = match msg.region {
+        Some(region) => region,
+        None => { return Ok(STATUS_FEED.subscribe(StatusFilter::Owner(user_id)).boxed()); },
+    };
+
+    let db_result = data.db.send(GetSealMessage { region: region.clone() }).await;
+    let seal = match db_result {
+        Err(_) => { return Err(ServiceError::InternalServerError); },
+        Ok(inner_result) => inner_result.map_err(|msg| ServiceError::BadRequest(msg))?,
+    };
+
+    let changes = STATUS_FEED.subscribe(StatusFilter::FinishedInRegion(region));
+    Ok(match seal {
+        None => changes.boxed(),
+        Some((end_time, seal_before_end)) => changes
+            .map(move |mut change| {
+                if is_sealed(end_time, seal_before_end) {
+                    change.verdict = None;
+                }
+                change
+            })
+            .boxed(),
+    })
+}
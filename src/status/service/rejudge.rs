@@ -0,0 +1,490 @@
+use crate::{
+    database::*,
+    judge_manager::*,
+    judge_manager::handler::StartJudge,
+    judge_manager::utils::queue::{ JudgePriority, QueuedTask },
+    judge_server::model::{ JudgeSetting, Comparison },
+    judge_server::service::submit::{ GetSettingMessage, ProblemSetting },
+    judge_server::utils::builder::get_judge_setting,
+    errors::{ServiceError, ServiceResult},
+    schema::{ status, problems, regions, rejudges, rejudge_items },
+    statics::WAITING_QUEUE,
+    status::model::{ Status, Verdict },
+    user::service::me::auth_check,
+    utils::time::get_cur_naive_date_time,
+};
+use diesel::prelude::*;
+use actix::prelude::*;
+use actix_web::web;
+use actix_identity::Identity;
+use chrono::*;
+use uuid::Uuid;
+use atoi::atoi;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Deserialize)]
+pub enum RejudgeTarget {
+    Status(Uuid),
+    Problem(String, i32),
+    Region(String),
+}
+
+impl RejudgeTarget {
+    fn describe(&self) -> String {
+        match self {
+            RejudgeTarget::Status(id) => format!("status {}", id),
+            RejudgeTarget::Problem(region, problem_id) => format!("problem {}/{}", region, problem_id),
+            RejudgeTarget::Region(region) => format!("region {}", region),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RejudgeStatusForm {
+    pub id: Uuid,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RejudgeProblemForm {
+    pub region: String,
+    pub problem_id: i32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RejudgeRegionForm {
+    pub region: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Insertable)]
+#[table_name = "rejudges"]
+struct InsertableRejudge {
+    id: Uuid,
+    target: String,
+    operator_id: i32,
+    create_time: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Deserialize, Insertable)]
+#[table_name = "rejudge_items"]
+struct InsertableRejudgeItem {
+    rejudge_id: Uuid,
+    status_id: Uuid,
+    old_result: Option<String>,
+    old_score: Option<f64>,
+    old_verdict: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, juniper::GraphQLObject)]
+pub struct RejudgeResult {
+    pub rejudge_id: Uuid,
+    pub status_count: i32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GetRejudgeTargetsMessage {
+    pub target: RejudgeTarget,
+}
+
+impl Message for GetRejudgeTargetsMessage {
+    type Result = Result<Vec<Status>, String>;
+}
+
+impl Handler<GetRejudgeTargetsMessage> for DbExecutor {
+    type Result = Result<Vec<Status>, String>;
+
+    fn handle(&mut self, msg: GetRejudgeTargetsMessage, _: &mut Self::Context) -> Self::Result {
+        // a whole problem set is too much to requeue at once
+        if let RejudgeTarget::Region(region) = &msg.target {
+            let self_type = regions::table
+                .filter(regions::name.eq(region.clone()))
+                .select(regions::self_type)
+                .first::<String>(&self.0)
+                .optional()
+                .map_err(|_| "Error loading region.".to_owned())?;
+            if self_type.as_deref() != Some("contest") {
+                return Err(format!("Region {} is not a contest.", region));
+            }
+        }
+
+        // only finished status can be rejudged, the others are still in the queue
+        let targets = match &msg.target {
+            RejudgeTarget::Status(status_id) => status::table
+                .filter(status::id.eq(*status_id))
+                .filter(status::state.eq("Finished".to_owned()))
+                .load::<Status>(&self.0),
+            RejudgeTarget::Problem(region, problem_id) => status::table
+                .filter(status::problem_region.eq(region.clone()))
+                .filter(status::problem_id.eq(*problem_id))
+                .filter(status::state.eq("Finished".to_owned()))
+                .load::<Status>(&self.0),
+            RejudgeTarget::Region(region) => status::table
+                .filter(status::problem_region.eq(region.clone()))
+                .filter(status::state.eq("Finished".to_owned()))
+                .load::<Status>(&self.0),
+        }.map_err(|_| "Error loading status.".to_owned())?;
+
+        if targets.is_empty() {
+            return Err("No finished status to rejudge.".to_owned());
+        }
+
+        Ok(targets)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RejudgeMessage {
+    pub target: RejudgeTarget,
+    pub operator_id: i32,
+    /// the new setting of each status to rejudge
+    pub settings: HashMap<Uuid, String>,
+}
+
+impl Message for RejudgeMessage {
+    type Result = Result<(RejudgeResult, Vec<(Uuid, i32)>), String>;
+}
+
+impl Handler<RejudgeMessage> for DbExecutor {
+    type Result = Result<(RejudgeResult, Vec<(Uuid, i32)>), String>;
+
+    fn handle(&mut self, msg: RejudgeMessage, _: &mut Self::Context) -> Self::Result {
+        let rejudge_id = Uuid::new_v4();
+        let ids = msg.settings.keys().cloned().collect::<Vec<Uuid>>();
+        let result = self.0.transaction::<_, diesel::result::Error, _>(|| {
+            // locked, so a concurrent rejudge of the same status waits and then finds it unfinished
+            let targets = status::table
+                .filter(status::id.eq_any(ids))
+                .filter(status::state.eq("Finished".to_owned()))
+                .order_by(status::submit_time.asc())
+                .for_update()
+                .load::<Status>(&self.0)?;
+
+            diesel::insert_into(rejudges::table)
+                .values(&InsertableRejudge {
+                    id: rejudge_id,
+                    target: msg.target.describe(),
+                    operator_id: msg.operator_id,
+                    create_time: get_cur_naive_date_time(),
+                })
+                .execute(&self.0)?;
+
+            let mut requeued = Vec::new();
+            for target in targets {
+                let setting_data = match msg.settings.get(&target.id) {
+                    Some(setting_data) => setting_data.clone(),
+                    None => continue,
+                };
+                let updated = diesel::update(status::table
+                    .filter(status::id.eq(target.id))
+                    .filter(status::state.eq("Finished".to_owned())))
+                    .set((
+                        status::state.eq("Waiting".to_owned()),
+                        status::setting_data.eq(setting_data),
+                        status::result.eq(None::<String>),
+                        status::score.eq(None::<f64>),
+                        status::result_data.eq(None::<String>),
+                        status::err_reason.eq(None::<String>),
+                        status::start_pend_time.eq(None::<NaiveDateTime>),
+                        status::finish_time.eq(None::<NaiveDateTime>),
+                        status::host_name.eq(None::<String>),
+                        status::judge_server_url.eq(None::<String>),
+                        status::retry_times.eq(0),
+                        status::verdict.eq(None::<String>),
+                        status::verdict_test_case.eq(None::<String>),
+                        status::max_time.eq(None::<i32>),
+                        status::max_memory.eq(None::<i32>),
+                    ))
+                    .execute(&self.0)?;
+                if updated == 0 { continue; }
+
+                diesel::insert_into(rejudge_items::table)
+                    .values(&InsertableRejudgeItem {
+                        rejudge_id: rejudge_id,
+                        status_id: target.id,
+                        old_result: target.result.clone(),
+                        old_score: target.score,
+                        old_verdict: target.verdict.clone(),
+                    })
+                    .execute(&self.0)?;
+
+                // take back what the judge manager counted when the status finished
+                let is_accepted = target.result == Some("Accepted".to_owned());
+                let is_effective = is_accepted || target.result == Some("Unaccepted".to_owned());
+                if is_effective {
+                    diesel::update(problems::table
+                        .filter(problems::region.eq(target.problem_region.clone()))
+                        .filter(problems::id.eq(target.problem_id)))
+                        .set((
+                            problems::submit_times.eq(problems::submit_times - 1),
+                            problems::accept_times.eq(problems::accept_times - if is_accepted { 1 } else { 0 }),
+                        ))
+                        .execute(&self.0)?;
+                }
+
+                requeued.push((target.id, target.owner_id));
+            }
+
+            // someone else rejudged them meanwhile, so there is nothing to record
+            if requeued.is_empty() {
+                return Err(diesel::result::Error::RollbackTransaction);
+            }
+            Ok(requeued)
+        });
+
+        match result {
+            Err(diesel::result::Error::RollbackTransaction) => Err("No finished status to rejudge.".to_owned()),
+            Err(_) => Err("Error while resetting status for rejudge.".to_owned()),
+            Ok(requeued) => Ok((
+                RejudgeResult {
+                    rejudge_id: rejudge_id,
+                    status_count: requeued.len() as i32,
+                },
+                requeued,
+            )),
+        }
+    }
+}
+
+/// Makes the settings again from the problems as they are now,
+/// so fixed test data, checkers, limits and comparison modes take effect.
+async fn build_rejudge_settings(
+    data: web::Data<DBState>,
+    targets: Vec<Status>,
+) -> Result<HashMap<Uuid, String>, String> {
+    let mut problem_settings: HashMap<(String, i32), (ProblemSetting, Option<Comparison>)> = HashMap::new();
+    let mut settings = HashMap::new();
+
+    for target in targets {
+        let key = (target.problem_region.clone(), target.problem_id);
+        if !problem_settings.contains_key(&key) {
+            let problem_setting = data.db.send(GetSettingMessage {
+                region: target.problem_region.clone(),
+                problem_id: target.problem_id,
+            }).await.map_err(|_| "Error loading problem setting.".to_owned())??;
+            let comparison = match &problem_setting.comparison_mode {
+                Some(mode) => Comparison::from_mode(mode, problem_setting.comparison_epsilon)?,
+                None => None,
+            };
+            problem_settings.insert(key.clone(), (problem_setting, comparison));
+        }
+        let (problem_setting, comparison) = &problem_settings[&key];
+
+        let old_setting = serde_json::from_str::<JudgeSetting>(&target.setting_data)
+            .map_err(|_| format!("Setting of status {} is broken.", target.id))?;
+        let show_output = old_setting.shows_output();
+        let status_id = target.id;
+        let setting = get_judge_setting(
+            data.clone(),
+            target.problem_region,
+            target.problem_id,
+            target.language,
+            old_setting.src,
+            problem_setting.spj_language.clone(),
+            problem_setting.is_interactive,
+            problem_setting.default_max_cpu_time,
+            problem_setting.default_max_memory,
+            &problem_setting.language_limits,
+            comparison.clone(),
+            problem_setting.opaque_output || show_output,
+        ).await.map_err(|msg| format!("Status {} can't be rejudged: {}", status_id, msg))?;
+        settings.insert(status_id, serde_json::to_string(&setting).unwrap());
+    }
+
+    Ok(settings)
+}
+
+pub async fn rejudge_service(
+    data: web::Data<DBState>,
+    judge_manager: web::Data<JMState>,
+    target: RejudgeTarget,
+    id: Identity,
+) -> ServiceResult<RejudgeResult> {
+    auth_check(data.clone(), id.clone(), "admin".to_owned()).await?;
+    let operator_id = atoi::<i32>(id.identity().unwrap().as_bytes()).unwrap();
+
+    let targets = match data.db.send(GetRejudgeTargetsMessage { target: target.clone() }).await {
+        Err(_) => { return Err(ServiceError::InternalServerError); },
+        Ok(Err(msg)) => { return Err(ServiceError::BadRequest(msg)); },
+        Ok(Ok(targets)) => targets,
+    };
+    let settings = build_rejudge_settings(data.clone(), targets).await
+        .map_err(ServiceError::BadRequest)?;
+
+    let db_result = data.db.send(RejudgeMessage {
+        target: target,
+        operator_id: operator_id,
+        settings: settings,
+    }).await;
+
+    match db_result {
+        Err(_) => Err(ServiceError::InternalServerError),
+        Ok(inner_result) => {
+            match inner_result {
+                Err(msg) => Err(ServiceError::BadRequest(msg)),
+                Ok((result, targets)) => {
+                    {
+                        let mut lock = WAITING_QUEUE.write().unwrap();
+                        for (status_id, owner_id) in targets {
+                            lock.push_back(QueuedTask {
+                                id: status_id,
+                                owner_id: owner_id,
+                                priority: JudgePriority::Bulk,
+                            });
+                        }
+                    }
+                    judge_manager.jm.do_send(StartJudge());
+                    Ok(result)
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, juniper::GraphQLObject)]
+pub struct RejudgeChange {
+    pub status_id: Uuid,
+    pub owner_id: i32,
+    pub problem_region: String,
+    pub problem_id: i32,
+    pub old_result: Option<String>,
+    pub new_result: Option<String>,
+    pub old_score: Option<f64>,
+    pub new_score: Option<f64>,
+    /// `None` for rejudges from before verdicts were recorded
+    pub old_verdict: Option<Verdict>,
+    pub new_verdict: Option<Verdict>,
+}
+
+#[derive(Debug, Clone, Serialize, juniper::GraphQLObject)]
+pub struct RejudgeReport {
+    pub id: Uuid,
+    pub target: String,
+    pub operator_id: i32,
+    pub create_time: NaiveDateTime,
+    pub total_count: i32,
+    pub finished_count: i32,
+    pub is_finished: bool,
+    pub changes: Vec<RejudgeChange>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GetRejudgeReportMessage {
+    pub id: Uuid,
+}
+
+impl Message for GetRejudgeReportMessage {
+    type Result = Result<RejudgeReport, String>;
+}
+
+impl Handler<GetRejudgeReportMessage> for DbExecutor {
+    type Result = Result<RejudgeReport, String>;
+
+    fn handle(&mut self, msg: GetRejudgeReportMessage, _: &mut Self::Context) -> Self::Result {
+        let (rejudge_id, target, operator_id, create_time) = match rejudges::table
+            .filter(rejudges::id.eq(msg.id))
+            .first::<(Uuid, String, i32, NaiveDateTime)>(&self.0)
+        {
+            Err(_) => { return Err("Rejudge not found.".to_owned()); },
+            Ok(rejudge) => rejudge,
+        };
+
+        let items = rejudge_items::table
+            .filter(rejudge_items::rejudge_id.eq(rejudge_id))
+            .inner_join(status::table.on(rejudge_items::status_id.eq(status::id)))
+            .order_by(status::submit_time.asc())
+            .select((
+                status::id,
+                status::owner_id,
+                status::problem_region,
+                status::problem_id,
+                status::state,
+                rejudge_items::old_result,
+                status::result,
+                rejudge_items::old_score,
+                status::score,
+                rejudge_items::old_verdict,
+                status::verdict,
+            ))
+            .load::<(
+                Uuid,
+                i32,
+                String,
+                i32,
+                String,
+                Option<String>,
+                Option<String>,
+                Option<f64>,
+                Option<f64>,
+                Option<String>,
+                Option<String>,
+            )>(&self.0)
+            .map_err(|_| "Error loading rejudge items.".to_owned())?;
+
+        let mut report = RejudgeReport {
+            id: rejudge_id,
+            target: target,
+            operator_id: operator_id,
+            create_time: create_time,
+            total_count: items.len() as i32,
+            finished_count: 0,
+            is_finished: false,
+            changes: Vec::new(),
+        };
+
+        for (
+            status_id,
+            owner_id,
+            problem_region,
+            problem_id,
+            state,
+            old_result,
+            new_result,
+            old_score,
+            new_score,
+            old_verdict,
+            new_verdict,
+        ) in items {
+            if state != "Finished".to_owned() { continue; }
+            report.finished_count += 1;
+            let old_verdict = old_verdict.and_then(|verdict| verdict.parse::<Verdict>().ok());
+            let new_verdict = new_verdict.and_then(|verdict| verdict.parse::<Verdict>().ok());
+            let is_verdict_changed = old_verdict.is_some() && old_verdict != new_verdict;
+            if old_result != new_result || old_score != new_score || is_verdict_changed {
+                report.changes.push(RejudgeChange {
+                    status_id: status_id,
+                    owner_id: owner_id,
+                    problem_region: problem_region,
+                    problem_id: problem_id,
+                    old_result: old_result,
+                    new_result: new_result,
+                    old_score: old_score,
+                    new_score: new_score,
+                    old_verdict: old_verdict,
+                    new_verdict: new_verdict,
+                });
+            }
+        }
+        report.is_finished = report.finished_count == report.total_count;
+
+        Ok(report)
+    }
+}
+
+pub async fn get_rejudge_report_service(
+    data: web::Data<DBState>,
+    msg: GetRejudgeReportMessage,
+    id: Identity,
+) -> ServiceResult<RejudgeReport> {
+    auth_check(data.clone(), id, "admin".to_owned()).await?;
+
+    let db_result = data.db.send(msg).await;
+
+    match db_result {
+        Err(_) => Err(ServiceError::InternalServerError),
+        Ok(inner_result) => {
+            match inner_result {
+                Err(msg) => Err(ServiceError::BadRequest(msg)),
+                Ok(report) => Ok(report),
+            }
+        }
+    }
+}
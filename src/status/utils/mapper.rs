@@ -1,5 +1,6 @@
 pub fn result_mapper(value: i32) -> String {
     match value {
+        -2 => "PRESENTATION_ERROR".to_owned(),
         -1 => "WRONG_ANSWER".to_owned(),
         0 => "SUCCESS".to_owned(),
         1 => "CPU_TIME_LIMIT_EXCEEDED".to_owned(),
@@ -25,6 +26,8 @@ pub fn err_mapper(value: i32) -> String {
         -9 => "SETUID_FAILED".to_owned(),
         -10 => "EXECVE_FAILED".to_owned(),
         -11 => "SPJ_ERROR".to_owned(),
+        -12 => "INTERACTOR_ERROR".to_owned(),
+        -13 => "INTERACTOR_REJECTED".to_owned(),
         _ => "UNKNOWN_ERROR".to_owned(),
     }
 }
\ No newline at end of file
@@ -6,10 +6,11 @@ use crate::{
         new::new_test_case_service,
         update::update_test_case_service,
         delete::{ delete_test_case_service, DeleteTestCaseMessage },
+        archive::{ get_test_case_archive_service, GetArchiveQuery },
     },
     errors::ServiceError,
 };
-use actix_web::{HttpResponse, web};
+use actix_web::{HttpRequest, HttpResponse, web};
 use actix_identity::Identity;
 use actix_multipart::Multipart;
 use futures::{StreamExt, TryStreamExt};
@@ -31,9 +32,17 @@ pub struct UploadTestCaseInfo {
     pub is_spj: bool,
 }
 
+#[derive(Deserialize)]
+pub struct UploadSpjInfo {
+    /// the language of `spj_src.*` or `interactor.*` in the archive, C if not given
+    pub spj_language: Option<String>,
+    pub is_interactive: Option<bool>,
+}
+
 pub async fn new_test_case(
     data: web::Data<DBState>,
     info: web::Path<UploadTestCaseInfo>,
+    spj_info: web::Query<UploadSpjInfo>,
     mut payload: Multipart,
     id: Identity,
 ) -> Result<HttpResponse, ServiceError> {
@@ -56,7 +65,7 @@ pub async fn new_test_case(
             bytes.extend_from_slice(&data);
         }
     }
-    new_test_case_service(data, &bytes, info.test_case_name.clone(), info.is_spj, id)
+    new_test_case_service(data, &bytes, info.test_case_name.clone(), info.is_spj, spj_info.spj_language.clone(), spj_info.is_interactive.unwrap_or(false), id)
     .await
     .map(|res| HttpResponse::Ok().json(&res))
 }
@@ -64,6 +73,7 @@ pub async fn new_test_case(
 pub async fn update_test_case(
     data: web::Data<DBState>,
     info: web::Path<UploadTestCaseInfo>,
+    spj_info: web::Query<UploadSpjInfo>,
     mut payload: Multipart,
     id: Identity,
 ) -> Result<HttpResponse, ServiceError> {
@@ -86,7 +96,7 @@ pub async fn update_test_case(
             bytes.extend_from_slice(&data);
         }
     }
-    update_test_case_service(data, &bytes, info.test_case_name.clone(), info.is_spj, id)
+    update_test_case_service(data, &bytes, info.test_case_name.clone(), info.is_spj, spj_info.spj_language.clone(), spj_info.is_interactive.unwrap_or(false), id)
     .await
     .map(|res| HttpResponse::Ok().json(&res))
 }
@@ -98,4 +108,26 @@ pub async fn delete_test_case(
 ) -> Result<HttpResponse, ServiceError> {
     delete_test_case_service(data, form.to_owned(), id).await
         .map(|res| HttpResponse::Ok().json(&res))
-}
\ No newline at end of file
+}
+
+pub async fn get_test_case_archive(
+    version: web::Path<String>,
+    query: web::Query<GetArchiveQuery>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ServiceError> {
+    let token = req.headers().get("x-judge-server-token")
+        .and_then(|token| token.to_str().ok())
+        .map(|token| token.to_owned());
+
+    let file = get_test_case_archive_service(version.into_inner(), query.service_url.clone(), token).await?;
+    let mut response = file
+        .set_content_type("application/gzip".parse().unwrap())
+        .into_response(&req)
+        .map_err(|_| ServiceError::InternalServerError)?;
+    // an archive never changes under its version
+    response.headers_mut().insert(
+        http::header::CACHE_CONTROL,
+        http::HeaderValue::from_static("private, max-age=31536000, immutable"),
+    );
+    Ok(response)
+}
@@ -13,5 +13,6 @@ pub fn route(cfg: &mut web::ServiceConfig) {
             .service(web::resource("/new_test_case/{test_case_name}/{is_spj}").route(web::get().to(new_test_case)))
             .service(web::resource("/update_test_case/{test_case_name}/{is_spj}").route(web::get().to(update_test_case)))
             .service(web::resource("/delete_test_case").route(web::post().to(delete_test_case)))
+            .service(web::resource("/archive/{version}").route(web::get().to(get_test_case_archive)))
     );
 }
\ No newline at end of file
@@ -6,4 +6,7 @@ pub struct TestCase {
     pub name: String,
     pub is_spj: bool,
     pub count: i32,
+    /// language of the checker, or of the interactor for interactive test cases
+    pub spj_language: Option<String>,
+    pub is_interactive: bool,
 }
\ No newline at end of file
@@ -0,0 +1,31 @@
+use crate::{
+    errors::{ ServiceError, ServiceResult },
+    judge_server::utils::auth::authenticate_judge_server,
+    test_case::utils::archive::get_archive_path,
+};
+use actix_files::NamedFile;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GetArchiveQuery {
+    /// needed for judge servers with their own credentials
+    pub service_url: Option<String>,
+}
+
+/// Only judge servers may download test data, which holds the expected outputs.
+pub async fn get_test_case_archive_service(
+    version: String,
+    service_url: Option<String>,
+    token: Option<String>,
+) -> ServiceResult<NamedFile> {
+    let token = token.ok_or(ServiceError::Unauthorized)?;
+    if let Err(reason) = authenticate_judge_server(&service_url.clone().unwrap_or_default(), &token) {
+        warn!("rejected archive download from {:?}: {}", service_url, reason);
+        return Err(ServiceError::Unauthorized);
+    }
+
+    let path = get_archive_path(&version)
+        .ok_or(ServiceError::BadRequest("Test case archive not found.".to_owned()))?;
+
+    // streamed from disk, archives can be hundreds of megabytes
+    NamedFile::open(path).map_err(|_| ServiceError::InternalServerError)
+}
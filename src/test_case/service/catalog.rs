@@ -18,6 +18,8 @@ pub struct RelatedProblem {
 pub struct TestCaseCatalogElement {
     pub name: String,
     pub is_spj: bool,
+    pub spj_language: Option<String>,
+    pub is_interactive: bool,
     pub count: i32,
     pub related_problems: Vec<RelatedProblem>,
 }
@@ -68,6 +70,8 @@ impl Handler<GetTestCaseCatalogMessage> for DbExecutor {
                 TestCaseCatalogElement {
                     name: test_case.name.clone(),
                     is_spj: test_case.is_spj,
+                    spj_language: test_case.spj_language.clone(),
+                    is_interactive: test_case.is_interactive,
                     count: test_case.count,
                     related_problems: {
                         let result = problems::table.filter(problems::test_case.nullable().eq(test_case.name))
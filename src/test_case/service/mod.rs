@@ -2,4 +2,5 @@ pub mod catalog;
 pub mod new;
 pub mod get;
 pub mod update;
-pub mod delete;
\ No newline at end of file
+pub mod delete;
+pub mod archive;
\ No newline at end of file
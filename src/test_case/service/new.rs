@@ -3,6 +3,8 @@ use crate::{
     database::*,
     test_case::model::TestCase,
     errors::{ ServiceError, ServiceResult },
+    statics::JUDGE_BACKEND,
+    judge_server::utils::builder::INTERACTIVE_UNSUPPORTED,
 };
 use diesel::prelude::*;
 use actix::prelude::*;
@@ -19,6 +21,8 @@ pub struct NewTestCaseMessage {
     pub name: String,
     pub is_spj: bool,
     pub count: i32,
+    pub spj_language: Option<String>,
+    pub is_interactive: bool,
 }
 
 impl Handler<NewTestCaseMessage> for DbExecutor {
@@ -32,6 +36,8 @@ impl Handler<NewTestCaseMessage> for DbExecutor {
                 name: msg.name,
                 is_spj: msg.is_spj,
                 count: msg.count,
+                spj_language: msg.spj_language,
+                is_interactive: msg.is_interactive,
             })
             .get_result::<TestCase>(&self.0);
 
@@ -47,6 +53,8 @@ pub async fn new_test_case_service(
     bytes: &[u8],
     name: String,
     is_spj: bool,
+    spj_language: Option<String>,
+    is_interactive: bool,
     _id: Identity,
 ) -> ServiceResult<TestCase> {
     let db_result = data.db.send(GetTestCaseMessage {
@@ -63,11 +71,16 @@ pub async fn new_test_case_service(
         }
     }
 
-    let count = if is_spj { 
-        make::make_spj_info(name.clone(), &bytes)
-    } else {
-        make::make_normal_info(name.clone(), &bytes)
-    };
+    if is_spj && is_interactive {
+        return Err(ServiceError::BadRequest("Interactive test_case can't be spj.".to_owned()));
+    }
+    if is_interactive && !JUDGE_BACKEND.supports_interactive() {
+        return Err(ServiceError::BadRequest(INTERACTIVE_UNSUPPORTED.to_owned()));
+    }
+    // C unless the uploader tells otherwise, like before the language could be chosen
+    let spj_language = if is_spj || is_interactive { Some(spj_language.unwrap_or("c".to_owned())) } else { None };
+    let count = make::make_test_case(name.clone(), bytes.to_vec(), spj_language.clone(), is_interactive).await
+        .map_err(|msg| ServiceError::BadRequest(msg))?;
     if count == 0 {
         return Err(ServiceError::BadRequest("Less than one effective test case is found.".to_owned()));
     }
@@ -76,6 +89,8 @@ pub async fn new_test_case_service(
         name: name,
         is_spj: is_spj,
         count: count,
+        spj_language: spj_language,
+        is_interactive: is_interactive,
     }).await;
 
     match db_result {
@@ -0,0 +1,90 @@
+use sha2::{ Digest, Sha256 };
+use hex::ToHex;
+use std::fs;
+use std::path::{ Path, PathBuf };
+use std::process::Command;
+use uuid::Uuid;
+
+pub const ARCHIVE_DIR: &str = "data/test_case_archive";
+/// Written next to `info`, so judges with the data at hand can tell which version they have.
+pub const VERSION_FILE: &str = "version";
+
+/// Files sent to judge servers, `raw.zip` is only kept for the record.
+fn archived_files(dir: &Path) -> Result<Vec<String>, String> {
+    let mut names = fs::read_dir(dir)
+        .map_err(|e| format!("Error reading {}: {}", dir.display(), e))?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_file())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| name != "raw.zip" && name != VERSION_FILE)
+        .collect::<Vec<String>>();
+    names.sort();
+    Ok(names)
+}
+
+/// The sha256 over names and contents of the archived files,
+/// so the same data always gets the same version.
+fn hash_test_case_dir(dir: &Path, names: &[String]) -> Result<String, String> {
+    let mut hasher = Sha256::new();
+    for name in names {
+        let content = fs::read(dir.join(name)).map_err(|e| format!("Error reading {}: {}", name, e))?;
+        hasher.update(name.as_bytes());
+        hasher.update(&[0u8]);
+        hasher.update(&(content.len() as u64).to_le_bytes());
+        hasher.update(&content);
+    }
+    Ok(hasher.finalize().encode_hex::<String>())
+}
+
+fn is_version(version: &str) -> bool {
+    version.len() == 64 && version.bytes().all(|c| c.is_ascii_hexdigit() && !c.is_ascii_uppercase())
+}
+
+pub fn get_archive_path(version: &str) -> Option<PathBuf> {
+    if !is_version(version) { return None; }
+    let path = Path::new(ARCHIVE_DIR).join(format!("{}.tar.gz", version));
+    if path.is_file() { Some(path) } else { None }
+}
+
+/// Packs the test case into an archive named by its version and records the version.
+/// Archives of older versions are kept for judges which still run them.
+pub fn pack_test_case(name: &str) -> Result<String, String> {
+    let dir = Path::new("data/test_case").join(name);
+    let names = archived_files(&dir)?;
+    if names.is_empty() {
+        return Err(format!("Test case {} has no data.", name));
+    }
+    let version = hash_test_case_dir(&dir, &names)?;
+
+    if get_archive_path(&version).is_none() {
+        fs::create_dir_all(ARCHIVE_DIR).map_err(|e| format!("Error creating {}: {}", ARCHIVE_DIR, e))?;
+        // written aside first, so a half written archive is never served
+        let tmp_path = Path::new(ARCHIVE_DIR).join(format!("{}.tmp", Uuid::new_v4().to_simple()));
+        let status = Command::new("tar")
+            .arg("-czf").arg(&tmp_path)
+            .arg("-C").arg(&dir)
+            .args(&names)
+            .status()
+            .map_err(|e| format!("Failed to run tar: {}", e))?;
+        if !status.success() {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(format!("Error packing test case {}.", name));
+        }
+        fs::rename(&tmp_path, Path::new(ARCHIVE_DIR).join(format!("{}.tar.gz", version)))
+            .map_err(|e| format!("Error saving archive: {}", e))?;
+    }
+
+    fs::write(dir.join(VERSION_FILE), &version).map_err(|e| format!("Error writing version: {}", e))?;
+    info!("packed test case {} as {}", name, version);
+
+    Ok(version)
+}
+
+/// The recorded version, packing test cases uploaded before there were versions.
+pub fn get_test_case_version(name: &str) -> Result<String, String> {
+    let version_path = Path::new("data/test_case").join(name).join(VERSION_FILE);
+    match fs::read_to_string(&version_path) {
+        Ok(version) if get_archive_path(version.trim()).is_some() => Ok(version.trim().to_owned()),
+        _ => pack_test_case(name),
+    }
+}
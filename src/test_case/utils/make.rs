@@ -5,9 +5,25 @@ use serde_json::json;
 use std::collections::BTreeMap;
 use std::fs;
 use std::fs::File;
+use std::path::Path;
 use std::io::prelude::*;
 use uuid::Uuid;
 use std::process::Command;
+use crate::judge_server::config::get_spj_definition;
+use crate::judge_manager::backend::local::check_spj;
+use actix_web::{ web, error::BlockingError };
+use crate::utils::encryption::encode::get_sha256;
+use super::subtask::{ Subtask, check_subtasks };
+use super::archive::pack_test_case;
+
+/// Packing again happens when the test case is first judged, so a failure here doesn't stop the upload.
+fn pack_uploaded(name: &str, test_case_number: i32) {
+    if test_case_number > 0 {
+        if let Err(e) = pack_test_case(name) {
+            warn!("{}", e);
+        }
+    }
+}
 
 fn hash_token<D: Digest>(key: &str, output: &mut [u8]) {
     let mut hasher = D::new();
@@ -22,6 +38,9 @@ fn get_stripped_md5_output(output: &str) -> String {
     (&buf[..]).to_vec().encode_hex::<String>()
 }
 
+pub const SPJ_SRC_STEM: &str = "spj_src";
+pub const INTERACTOR_SRC_STEM: &str = "interactor";
+
 #[derive(Debug, Clone, Serialize)]
 struct NormalTestCaseInfo {
     input_name: String,
@@ -35,9 +54,24 @@ struct NormalTestCaseInfo {
 struct SpjTestCaseInfo {
     input_name: String, 
     input_size: i32,
+    /// the answer handed to testlib checkers, if one is uploaded
+    output_name: Option<String>,
 }
 
-pub fn make_normal_info(name: String, zip_buf: &[u8]) -> i32 {
+/// Reads `subtasks.json` of an unpacked archive, if there is one.
+fn read_subtasks(path: &str, test_case_names: Vec<String>) -> Result<Option<Vec<Subtask>>, String> {
+    let content = match fs::read_to_string(path.to_owned() + "/subtasks.json") {
+        Ok(content) => content,
+        Err(_) => { return Ok(None); },
+    };
+    let subtasks: Vec<Subtask> = serde_json::from_str(&content)
+        .map_err(|e| format!("Malformed subtasks.json: {}", e))?;
+    check_subtasks(&subtasks, &test_case_names)?;
+
+    Ok(Some(subtasks))
+}
+
+pub fn make_normal_info(name: String, zip_buf: &[u8]) -> Result<i32, String> {
     let mut test_case_number = 0;
     let mut test_cases: BTreeMap::<String, NormalTestCaseInfo> = BTreeMap::new();
     let path = "data/test_case/".to_owned() + &name;
@@ -97,15 +131,22 @@ pub fn make_normal_info(name: String, zip_buf: &[u8]) -> i32 {
 
         test_case_number += 1;
     }
-    
-    let info = json!({
-        "test_case_number": test_case_number,
-        "spj": false,
-        "test_cases": test_cases,
-    });
 
-    let mut file = File::create(&(path.clone() + "/" + "info")).expect("Error creating info");
-    file.write_all(info.to_string().as_bytes()).expect("Error writing info");
+    let subtasks = read_subtasks(&path, test_cases.keys().cloned().collect());
+    match &subtasks {
+        Ok(subtasks) => {
+            let info = json!({
+                "test_case_number": test_case_number,
+                "spj": false,
+                "test_cases": test_cases,
+                "subtasks": subtasks,
+            });
+
+            let mut file = File::create(&(path.clone() + "/" + "info")).expect("Error creating info");
+            file.write_all(info.to_string().as_bytes()).expect("Error writing info");
+        },
+        Err(_) => { test_case_number = 0; },
+    }
 
     if test_case_number == 0 {
         // if is_backuped recover from backup
@@ -122,10 +163,45 @@ pub fn make_normal_info(name: String, zip_buf: &[u8]) -> i32 {
         }
     }
 
-    test_case_number
+    pack_uploaded(&name, test_case_number);
+    subtasks.map(|_| test_case_number)
+}
+
+/// Unzips and checks an uploaded archive on the blocking thread pool.
+/// Returns the number of test cases found.
+pub async fn make_test_case(
+    name: String,
+    zip_buf: Vec<u8>,
+    spj_language: Option<String>,
+    is_interactive: bool,
+) -> Result<i32, String> {
+    let made = web::block(move || match &spj_language {
+        Some(spj_language) if is_interactive => make_interactive_info(name, &zip_buf, spj_language),
+        Some(spj_language) => make_spj_info(name, &zip_buf, spj_language),
+        None => make_normal_info(name, &zip_buf),
+    }).await;
+
+    match made {
+        Ok(count) => Ok(count),
+        Err(BlockingError::Error(msg)) => Err(msg),
+        Err(BlockingError::Canceled) => Err("Making test case was canceled.".to_owned()),
+    }
+}
+
+pub fn make_spj_info(name: String, zip_buf: &[u8], spj_language: &str) -> Result<i32, String> {
+    make_checked_info(name, zip_buf, spj_language, SPJ_SRC_STEM)
+}
+
+pub fn make_interactive_info(name: String, zip_buf: &[u8], interactor_language: &str) -> Result<i32, String> {
+    make_checked_info(name, zip_buf, interactor_language, INTERACTOR_SRC_STEM)
 }
 
-pub fn make_spj_info(name: String, zip_buf: &[u8]) -> i32 {
+/// Test cases without outputs, judged by a program uploaded along with the inputs.
+fn make_checked_info(name: String, zip_buf: &[u8], language: &str, src_stem: &str) -> Result<i32, String> {
+    let spj = get_spj_definition(language)
+        .ok_or(format!("Special judge in \"{}\" doesn't support.", language))?;
+    let spj_src_name = spj.upload_name(src_stem);
+
     let mut test_case_number = 0;
     let mut test_cases: BTreeMap::<String, SpjTestCaseInfo> = BTreeMap::new();
     let path = "data/test_case/".to_owned() + &name;
@@ -150,44 +226,56 @@ pub fn make_spj_info(name: String, zip_buf: &[u8]) -> i32 {
         .spawn()
         .expect("Failed to unzip.");
     p.wait().unwrap();
-    
-    loop {
-        // check if spj_src.c exists
-        if test_case_number == 0 {
-            match File::open(path.clone() + "/spj_src.c") {
+
+    // the checker or interactor must be there and compile before anything is replaced,
+    // with the compiler of this host whichever backend judges
+    let mut checked = match fs::read_to_string(path.clone() + "/" + &spj_src_name) {
+        Ok(spj_src) => check_spj(&spj_src, &spj.compile_config(), &get_sha256(&spj_src)),
+        Err(_) => Err(format!("Can't find {} in the archive.", spj_src_name)),
+    };
+
+    if checked.is_ok() {
+        loop {
+            let name = (test_case_number + 1).to_string() + ".in";
+            let mut file = match File::open(path.clone() + "/" + &name) {
                 Ok(file) => file,
                 Err(_) => {
-                    info!("Can't find file {}", path.clone() + "/spj_src.c");
+                    info!("Can't find file {}", path.clone() + "/" + &name);
                     break;
                 }
             };
+            let mut content = String::new();
+            file.read_to_string(&mut content).unwrap();
+            let output_name = (test_case_number + 1).to_string() + ".out";
+            let has_output = Path::new(&(path.clone() + "/" + &output_name)).is_file();
+            test_cases.insert(test_case_number.to_string(), SpjTestCaseInfo{
+                input_name: name,
+                input_size: content.len() as i32,
+                output_name: if has_output { Some(output_name) } else { None },
+            });
+
+            test_case_number += 1;
         }
-        let name = (test_case_number + 1).to_string() + ".in";
-        let mut file = match File::open(path.clone() + "/" + &name) {
-            Ok(file) => file,
-            Err(_) => {
-                info!("Can't find file {}", path.clone() + "/" + &name);
-                break;
-            }
-        };
-        let mut content = String::new();
-        file.read_to_string(&mut content).unwrap();
-        test_cases.insert(test_case_number.to_string(), SpjTestCaseInfo{
-            input_name: name,
-            input_size: content.len() as i32,
-        });
 
-        test_case_number += 1;
+        match read_subtasks(&path, test_cases.keys().cloned().collect()) {
+            Ok(subtasks) => {
+                let info = json!({
+                    "test_case_number": test_case_number,
+                    "spj": src_stem == SPJ_SRC_STEM,
+                    "interactive": src_stem == INTERACTOR_SRC_STEM,
+                    "test_cases": test_cases,
+                    "subtasks": subtasks,
+                });
+
+                let mut file = File::create(path.clone() + "/" + "info").expect("Error creating info");
+                file.write_all(info.to_string().as_bytes()).expect("Error writing info");
+            },
+            Err(e) => {
+                checked = Err(e);
+                test_case_number = 0;
+            },
+        }
     }
-    
-    let info = json!({
-        "test_case_number": test_case_number,
-        "spj": true,
-        "test_cases": test_cases,
-    });
-    
-    let mut file = File::create(path.clone() + "/" + "info").expect("Error creating info");
-    file.write_all(info.to_string().as_bytes()).expect("Error writing info");
 
     if test_case_number == 0 {
         // if is_backuped recover from backup
@@ -204,5 +292,6 @@ pub fn make_spj_info(name: String, zip_buf: &[u8]) -> i32 {
         }
     }
 
-    test_case_number
-}
\ No newline at end of file
+    pack_uploaded(&name, test_case_number);
+    checked.map(|_| test_case_number)
+}
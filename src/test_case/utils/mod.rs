@@ -1 +1,3 @@
-pub mod make;
\ No newline at end of file
+pub mod make;
+pub mod subtask;
+pub mod archive;
\ No newline at end of file
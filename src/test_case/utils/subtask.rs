@@ -0,0 +1,117 @@
+use std::collections::HashSet;
+use std::fs;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Aggregation {
+    /// the worst test case decides, as in IOI-style subtasks
+    Min,
+    /// each test case is worth the same part of the subtask
+    Sum,
+}
+
+fn default_aggregation() -> Aggregation { Aggregation::Min }
+
+/// A group of test cases scored together, listed in `subtasks.json` of an uploaded archive.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Subtask {
+    pub id: i32,
+    /// weight of the subtask, the weights are scaled to the problem's max_score
+    pub score: f64,
+    #[serde(default = "default_aggregation")]
+    pub aggregation: Aggregation,
+    /// names of test cases as in the info file
+    pub test_cases: Vec<String>,
+    /// ids of earlier subtasks, this one earns no larger share than any of them
+    #[serde(default)]
+    pub dependencies: Vec<i32>,
+}
+
+pub fn check_subtasks(subtasks: &[Subtask], test_case_names: &[String]) -> Result<(), String> {
+    let mut ids = HashSet::new();
+    for subtask in subtasks.iter() {
+        if !(subtask.score > 0.0 && subtask.score.is_finite()) {
+            return Err(format!("Subtask {} must have a positive score.", subtask.id));
+        }
+        if subtask.test_cases.is_empty() {
+            return Err(format!("Subtask {} has no test case.", subtask.id));
+        }
+        if let Some(name) = subtask.test_cases.iter().find(|name| !test_case_names.contains(name)) {
+            return Err(format!("Subtask {} has unknown test case \"{}\".", subtask.id, name));
+        }
+        // only depending on earlier subtasks keeps the dependencies acyclic
+        if let Some(dependency) = subtask.dependencies.iter().find(|dependency| !ids.contains(*dependency)) {
+            return Err(format!("Subtask {} depends on {}, which is not an earlier subtask.", subtask.id, dependency));
+        }
+        if !ids.insert(subtask.id) {
+            return Err(format!("Subtask {} is defined twice.", subtask.id));
+        }
+    }
+    Ok(())
+}
+
+/// Reads the subtasks of an uploaded test case, none if it has no subtasks.
+pub fn load_subtasks(test_case: &str) -> Result<Vec<Subtask>, String> {
+    let path = "data/test_case/".to_owned() + test_case + "/info";
+    let content = fs::read_to_string(&path).map_err(|e| format!("Error reading {}: {}", path, e))?;
+    let info: serde_json::Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Malformed test case info {}: {}", path, e))?;
+
+    match info.get("subtasks") {
+        Some(subtasks) if !subtasks.is_null() => serde_json::from_value(subtasks.clone())
+            .map_err(|e| format!("Malformed subtasks in {}: {}", path, e)),
+        _ => Ok(Vec::new()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn subtask(id: i32, score: f64, test_cases: &[&str], dependencies: &[i32]) -> Subtask {
+        Subtask {
+            id: id,
+            score: score,
+            aggregation: Aggregation::Min,
+            test_cases: test_cases.iter().map(|name| name.to_string()).collect(),
+            dependencies: dependencies.to_vec(),
+        }
+    }
+
+    fn names() -> Vec<String> {
+        vec!["1".to_owned(), "2".to_owned()]
+    }
+
+    #[test]
+    fn accepts_subtasks_depending_on_earlier_ones() {
+        let subtasks = [subtask(1, 30.0, &["1"], &[]), subtask(2, 70.0, &["1", "2"], &[1])];
+        assert_eq!(check_subtasks(&subtasks, &names()), Ok(()));
+    }
+
+    #[test]
+    fn rejects_scores_that_are_not_positive_and_finite() {
+        for score in &[0.0, -1.0, std::f64::NAN, std::f64::INFINITY] {
+            assert!(check_subtasks(&[subtask(1, *score, &["1"], &[])], &names()).is_err());
+        }
+    }
+
+    #[test]
+    fn rejects_empty_subtasks_and_unknown_test_cases() {
+        assert!(check_subtasks(&[subtask(1, 1.0, &[], &[])], &names()).is_err());
+        assert!(check_subtasks(&[subtask(1, 1.0, &["3"], &[])], &names()).is_err());
+    }
+
+    #[test]
+    fn rejects_later_or_own_dependencies_and_duplicate_ids() {
+        assert!(check_subtasks(&[subtask(1, 1.0, &["1"], &[2]), subtask(2, 1.0, &["2"], &[])], &names()).is_err());
+        assert!(check_subtasks(&[subtask(1, 1.0, &["1"], &[1])], &names()).is_err());
+        assert!(check_subtasks(&[subtask(1, 1.0, &["1"], &[]), subtask(1, 1.0, &["2"], &[])], &names()).is_err());
+    }
+
+    #[test]
+    fn aggregation_defaults_to_min() {
+        let subtask: Subtask = serde_json::from_str(r#"{ "id": 1, "score": 10, "test_cases": ["1"] }"#).unwrap();
+        assert_eq!(subtask.aggregation, Aggregation::Min);
+        assert!(subtask.dependencies.is_empty());
+    }
+}
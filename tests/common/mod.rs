@@ -0,0 +1,310 @@
+use actix_web::HttpMessage;
+use actix_web::client::Client;
+use diesel::prelude::*;
+use diesel::sql_query;
+use sha2::{ Digest, Sha256 };
+use diesel::sql_types::{ Integer, Text, Nullable, Uuid as SqlUuid };
+use std::fs;
+use std::net::{ TcpListener, TcpStream };
+use std::process::{ Child, Command };
+use std::sync::{ Mutex, MutexGuard };
+use std::thread;
+use std::time::{ Duration, Instant };
+use uuid::Uuid;
+
+pub const JUDGE_SERVER_SECRET: &str = "mock_secret";
+pub const PASSWORD: &str = "mock_password";
+
+lazy_static::lazy_static! {
+    // every online judge recovers the whole status table when it starts,
+    // which would take over the tasks of a test running alongside
+    static ref SERIAL: Mutex<()> = Mutex::new(());
+}
+
+/// What judge servers send, the sha256 of the shared secret.
+pub fn judge_server_token() -> String {
+    hex::encode(Sha256::digest(JUDGE_SERVER_SECRET.as_bytes()))
+}
+
+/// Kills the child process when dropped, so a failed test doesn't leave servers behind.
+pub struct Process(Child);
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+}
+
+fn wait_for_port(port: u16) {
+    let start_time = Instant::now();
+    while TcpStream::connect(("127.0.0.1", port)).is_err() {
+        if start_time.elapsed() > Duration::from_secs(30) {
+            panic!("Nothing is listening on port {}", port);
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+}
+
+#[derive(Debug, QueryableByName)]
+pub struct StatusRow {
+    #[sql_type = "Text"]
+    pub state: String,
+    #[sql_type = "Nullable<Text>"]
+    pub result: Option<String>,
+    #[sql_type = "Nullable<diesel::sql_types::Double>"]
+    pub score: Option<f64>,
+    #[sql_type = "Nullable<Text>"]
+    pub err_reason: Option<String>,
+    #[sql_type = "Nullable<Text>"]
+    pub verdict: Option<String>,
+    #[sql_type = "Nullable<Text>"]
+    pub verdict_test_case: Option<String>,
+    #[sql_type = "Nullable<Integer>"]
+    pub max_time: Option<i32>,
+}
+
+#[derive(Debug, QueryableByName)]
+pub struct ProblemCounter {
+    #[sql_type = "Integer"]
+    pub submit_times: i32,
+    #[sql_type = "Integer"]
+    pub accept_times: i32,
+}
+
+#[derive(Debug, QueryableByName)]
+struct SettingRow {
+    #[sql_type = "Text"]
+    setting_data: String,
+}
+
+/// An online judge and a mock judge server running against the local Postgres,
+/// with a user, a region and a problem made just for one test.
+pub struct TestEnv {
+    pub conn: PgConnection,
+    pub base_url: String,
+    pub region: String,
+    pub test_case: String,
+    pub username: String,
+    _online_judge: Process,
+    mock_judge_server: Option<Process>,
+    // dropped last, after the servers are gone
+    _serial: MutexGuard<'static, ()>,
+}
+
+impl TestEnv {
+    pub fn start(judge_type: Option<&str>) -> TestEnv {
+        // a failed test must not stop the ones after it
+        let serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+        dotenv::dotenv().ok();
+        let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+        let conn = PgConnection::establish(&database_url).expect("Error connecting to the database");
+
+        let suffix = Uuid::new_v4().to_simple().to_string()[..8].to_owned();
+        let region = format!("mock_region_{}", suffix);
+        let test_case = format!("mock_test_case_{}", suffix);
+        let username = format!("mock_user_{}", suffix);
+
+        sql_query("INSERT INTO regions (name, need_pass, self_type, judge_type) VALUES ($1, false, 'problem_set', $2)")
+            .bind::<Text, _>(&region)
+            .bind::<Nullable<Text>, _>(judge_type)
+            .execute(&conn).unwrap();
+        sql_query("INSERT INTO test_cases (name, is_spj, count) VALUES ($1, false, 2)")
+            .bind::<Text, _>(&test_case)
+            .execute(&conn).unwrap();
+        sql_query("INSERT INTO problems (id, region, title, input_examples, output_examples, tags, sources, \
+            difficulty, default_max_cpu_time, default_max_memory, test_case, max_score, opaque_output) \
+            VALUES (1, $1, 'A + B', '{}', '{}', '{}', '{}', 'Easy', 1000, 268435456, $2, 100, false)")
+            .bind::<Text, _>(&region)
+            .bind::<Text, _>(&test_case)
+            .execute(&conn).unwrap();
+
+        let port = free_port();
+        let mock_port = free_port();
+        let base_url = format!("http://127.0.0.1:{}", port);
+
+        let online_judge = Process(Command::new(env!("CARGO_BIN_EXE_online_judge"))
+            .env("BIND_ADDRESS", format!("127.0.0.1:{}", port))
+            .env("JUDGE_BACKEND", "remote")
+            .env("JUDGE_SERVER_SECRET", JUDGE_SERVER_SECRET)
+            .env("JUDGE_MAX_RETRY_TIMES", "1")
+            .env("JUDGE_RETRY_BASE_DELAY", "1")
+            .env("JUDGE_HEARTBEAT_TIMEOUT", "2")
+            .env("JUDGE_SUPERVISOR_INTERVAL", "1")
+            .spawn()
+            .expect("Error starting online_judge"));
+        wait_for_port(port);
+
+        let mock_judge_server = Process(Command::new(env!("CARGO_BIN_EXE_mock_judge_server"))
+            .env("MOCK_JUDGE_BIND", format!("127.0.0.1:{}", mock_port))
+            .env("MOCK_JUDGE_BACKEND_URL", &base_url)
+            .env("MOCK_JUDGE_TOKEN", judge_server_token())
+            .spawn()
+            .expect("Error starting mock_judge_server"));
+        wait_for_port(mock_port);
+
+        TestEnv {
+            conn: conn,
+            base_url: base_url,
+            region: region,
+            test_case: test_case,
+            username: username,
+            _online_judge: online_judge,
+            mock_judge_server: Some(mock_judge_server),
+            _serial: serial,
+        }
+    }
+
+    /// Kills the mock judge server, as if its machine went down.
+    pub fn stop_mock_judge_server(&mut self) {
+        self.mock_judge_server = None;
+    }
+
+    pub async fn judge_servers(&self) -> Vec<serde_json::Value> {
+        let mut response = Client::new()
+            .post(format!("{}/judge_server/info", self.base_url))
+            .send()
+            .await
+            .unwrap();
+        response.json().await.unwrap()
+    }
+
+    /// Registers the test user and returns its identity cookie.
+    pub async fn login(&self) -> String {
+        let client = Client::new();
+        let response = client.post(format!("{}/user/register", self.base_url))
+            .send_form(&[
+                ("username", self.username.as_str()),
+                ("password", PASSWORD),
+                ("role", "student"),
+            ])
+            .await
+            .unwrap();
+        assert!(response.status().is_success(), "register failed: {}", response.status());
+
+        let response = client.post(format!("{}/user/login", self.base_url))
+            .send_form(&[
+                ("identity_info", self.username.as_str()),
+                ("password", PASSWORD),
+            ])
+            .await
+            .unwrap();
+        assert!(response.status().is_success(), "login failed: {}", response.status());
+
+        let cookie = response.cookie("auth-cookie").expect("No identity cookie after login");
+        format!("{}={}", cookie.name(), cookie.value())
+    }
+
+    /// Makes the test user an admin, after `login`.
+    pub fn make_admin(&self) {
+        sql_query("UPDATE users SET role = 'admin' WHERE username = $1")
+            .bind::<Text, _>(&self.username)
+            .execute(&self.conn)
+            .unwrap();
+    }
+
+    /// Posts a form and returns the status code with the json body, null if there is none.
+    pub async fn post_form(&self, cookie: &str, path: &str, form: &[(&str, &str)]) -> (u16, serde_json::Value) {
+        let mut response = Client::new()
+            .post(format!("{}{}", self.base_url, path))
+            .header("Cookie", cookie)
+            .send_form(&form)
+            .await
+            .unwrap();
+        let body = response.json().await.unwrap_or(serde_json::Value::Null);
+        (response.status().as_u16(), body)
+    }
+
+    pub async fn submit(&self, cookie: &str, judge_type: &str, src: &str) -> Uuid {
+        let mut response = Client::new()
+            .post(format!("{}/judge_server/submit", self.base_url))
+            .header("Cookie", cookie)
+            .send_form(&[
+                ("problem_id", "1"),
+                ("problem_region", self.region.as_str()),
+                ("src", src),
+                ("language", "c"),
+                ("judge_type", judge_type),
+                ("output", "false"),
+            ])
+            .await
+            .unwrap();
+        let body: serde_json::Value = response.json().await.unwrap();
+        body["status_id"].as_str()
+            .unwrap_or_else(|| panic!("submit failed: {}", body))
+            .parse()
+            .unwrap()
+    }
+
+    pub async fn get_status(&self, cookie: Option<&str>, status_id: Uuid) -> serde_json::Value {
+        let mut request = Client::new().post(format!("{}/status/get", self.base_url));
+        if let Some(cookie) = cookie {
+            request = request.header("Cookie", cookie);
+        }
+        let mut response = request
+            .send_form(&[("id", status_id.to_string())])
+            .await
+            .unwrap();
+        response.json().await.unwrap()
+    }
+
+    /// Polls the status until it is finished.
+    pub fn wait_for_status(&self, status_id: Uuid, timeout: Duration) -> StatusRow {
+        let start_time = Instant::now();
+        loop {
+            let status = sql_query("SELECT state, result, score, err_reason, verdict, verdict_test_case, max_time \
+                FROM status WHERE id = $1")
+                .bind::<SqlUuid, _>(status_id)
+                .get_result::<StatusRow>(&self.conn)
+                .unwrap();
+            if status.state == "Finished" { return status; }
+            if start_time.elapsed() > timeout {
+                panic!("status {} is still {} after {:?}", status_id, status.state, timeout);
+            }
+            thread::sleep(Duration::from_millis(200));
+        }
+    }
+
+    /// The judge setting stored with the status, as sent to judge servers.
+    pub fn judge_setting(&self, status_id: Uuid) -> serde_json::Value {
+        let setting = sql_query("SELECT setting_data FROM status WHERE id = $1")
+            .bind::<SqlUuid, _>(status_id)
+            .get_result::<SettingRow>(&self.conn)
+            .unwrap();
+        serde_json::from_str(&setting.setting_data).unwrap()
+    }
+
+    /// Writes the test case info file, which the online judge reads subtasks from.
+    pub fn write_test_case_info(&self, info: &serde_json::Value) {
+        let dir = format!("data/test_case/{}", self.test_case);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(format!("{}/info", dir), info.to_string()).unwrap();
+    }
+
+    pub fn problem_counter(&self) -> ProblemCounter {
+        sql_query("SELECT submit_times, accept_times FROM problems WHERE region = $1 AND id = 1")
+            .bind::<Text, _>(&self.region)
+            .get_result::<ProblemCounter>(&self.conn)
+            .unwrap()
+    }
+}
+
+impl Drop for TestEnv {
+    fn drop(&mut self) {
+        let _ = sql_query("DELETE FROM status WHERE problem_region = $1")
+            .bind::<Text, _>(&self.region).execute(&self.conn);
+        let _ = sql_query("DELETE FROM problems WHERE region = $1")
+            .bind::<Text, _>(&self.region).execute(&self.conn);
+        let _ = sql_query("DELETE FROM test_cases WHERE name = $1")
+            .bind::<Text, _>(&self.test_case).execute(&self.conn);
+        let _ = sql_query("DELETE FROM regions WHERE name = $1")
+            .bind::<Text, _>(&self.region).execute(&self.conn);
+        let _ = sql_query("DELETE FROM users WHERE username = $1")
+            .bind::<Text, _>(&self.username).execute(&self.conn);
+        let _ = fs::remove_dir_all(format!("data/test_case/{}", self.test_case));
+    }
+}
@@ -0,0 +1,448 @@
+//! End-to-end tests of the submit-to-verdict path through the mock judge server.
+//! They need a local Postgres at `DATABASE_URL` with the migrations applied,
+//! by `diesel migration run` or `tests/setup_db.sh` on an empty database,
+//! so they are ignored by default: run them with `cargo test -- --ignored`.
+//! Each test starts its own online judge, and they run one at a time.
+
+#[macro_use] extern crate diesel;
+
+mod common;
+
+use actix_web::client::Client;
+use common::TestEnv;
+use diesel::prelude::*;
+use diesel::sql_query;
+use diesel::sql_types::{ BigInt, Text, Uuid as SqlUuid };
+use serde_json::json;
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+#[actix_rt::test]
+#[ignore]
+async fn accepted_submission_counts_for_problem() {
+    let env = TestEnv::start(Some("ACM"));
+    let cookie = env.login().await;
+
+    let status_id = env.submit(&cookie, "ACM", "// MOCK_VERDICT: SUCCESS SUCCESS\nint main() {}").await;
+    let status = env.wait_for_status(status_id, TIMEOUT);
+
+    assert_eq!(status.result, Some("Accepted".to_owned()));
+    let counter = env.problem_counter();
+    assert_eq!((counter.submit_times, counter.accept_times), (1, 1));
+}
+
+#[actix_rt::test]
+#[ignore]
+async fn failed_test_case_is_unaccepted() {
+    let env = TestEnv::start(Some("ACM"));
+    let cookie = env.login().await;
+
+    let status_id = env.submit(&cookie, "ACM", "// MOCK_VERDICT: SUCCESS WRONG_ANSWER\nint main() {}").await;
+    let status = env.wait_for_status(status_id, TIMEOUT);
+
+    assert_eq!(status.result, Some("Unaccepted".to_owned()));
+    let counter = env.problem_counter();
+    assert_eq!((counter.submit_times, counter.accept_times), (1, 0));
+}
+
+#[actix_rt::test]
+#[ignore]
+async fn verdict_comes_from_first_failing_test_case() {
+    let env = TestEnv::start(Some("ACM"));
+    let cookie = env.login().await;
+
+    let status_id = env.submit(&cookie, "ACM",
+        "// MOCK_VERDICT: SUCCESS REAL_TIME_LIMIT_EXCEEDED WRONG_ANSWER\nint main() {}").await;
+    let status = env.wait_for_status(status_id, TIMEOUT);
+
+    assert_eq!(status.verdict, Some("TLE".to_owned()));
+    assert_eq!(status.verdict_test_case, Some("2".to_owned()));
+    assert_eq!(status.max_time, Some(1));
+}
+
+#[actix_rt::test]
+#[ignore]
+async fn oi_score_follows_passed_test_cases() {
+    let env = TestEnv::start(None);
+    let cookie = env.login().await;
+
+    let status_id = env.submit(&cookie, "OI", "// MOCK_VERDICT: SUCCESS WRONG_ANSWER SUCCESS RUNTIME_ERROR\nint main() {}").await;
+    let status = env.wait_for_status(status_id, TIMEOUT);
+
+    assert_eq!(status.result, None);
+    assert_eq!(status.score, Some(50.0));
+}
+
+#[actix_rt::test]
+#[ignore]
+async fn oi_score_follows_subtasks() {
+    let env = TestEnv::start(None);
+    env.write_test_case_info(&json!({
+        "test_case_number": 3,
+        "spj": false,
+        "test_cases": {},
+        "subtasks": [
+            { "id": 1, "score": 40, "test_cases": ["1"] },
+            { "id": 2, "score": 60, "aggregation": "sum", "test_cases": ["2", "3"], "dependencies": [1] },
+        ],
+    }));
+    let cookie = env.login().await;
+
+    let status_id = env.submit(&cookie, "OI", "// MOCK_VERDICT: SUCCESS SUCCESS WRONG_ANSWER\nint main() {}").await;
+    let status = env.wait_for_status(status_id, TIMEOUT);
+
+    assert_eq!(status.score, Some(70.0));
+}
+
+#[actix_rt::test]
+#[ignore]
+async fn progress_of_finished_status_is_its_verdict() {
+    let env = TestEnv::start(Some("ACM"));
+    let cookie = env.login().await;
+
+    let status_id = env.submit(&cookie, "ACM", "// MOCK_VERDICT: SUCCESS WRONG_ANSWER\nint main() {}").await;
+    env.wait_for_status(status_id, TIMEOUT);
+
+    let mut response = Client::new()
+        .get(format!("{}/status/progress?id={}", env.base_url, status_id))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.headers().get("Content-Type").unwrap(), "text/event-stream");
+    let body = response.body().await.unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&body),
+        "event: finished\ndata: {\"event\":\"finished\",\"verdict\":\"WA\"}\n\n"
+    );
+}
+
+#[actix_rt::test]
+#[ignore]
+async fn compile_error_keeps_the_message() {
+    let env = TestEnv::start(Some("ACM"));
+    let cookie = env.login().await;
+
+    let status_id = env.submit(&cookie, "ACM", "// MOCK_VERDICT: CompileError\nint main() {").await;
+    let status = env.wait_for_status(status_id, TIMEOUT);
+
+    assert_eq!(status.result, Some("CompileError".to_owned()));
+    assert_eq!(status.verdict, Some("CE".to_owned()));
+    assert!(status.err_reason.unwrap().contains("scripted compile error"));
+    let counter = env.problem_counter();
+    assert_eq!((counter.submit_times, counter.accept_times), (0, 0));
+}
+
+#[actix_rt::test]
+#[ignore]
+async fn compile_error_is_only_shown_to_owner() {
+    let env = TestEnv::start(Some("ACM"));
+    let cookie = env.login().await;
+
+    let status_id = env.submit(&cookie, "ACM", "// MOCK_VERDICT: CompileError\nint main() {").await;
+    env.wait_for_status(status_id, TIMEOUT);
+
+    let owner_view = env.get_status(Some(&cookie), status_id).await;
+    assert!(owner_view["compile_error"].as_str().unwrap().contains("scripted compile error"));
+    let anonymous_view = env.get_status(None, status_id).await;
+    assert!(anonymous_view["compile_error"].is_null());
+    assert_eq!(anonymous_view["err_result"]["data"], json!(""));
+}
+
+#[actix_rt::test]
+#[ignore]
+async fn broken_judge_server_ends_in_system_error() {
+    let env = TestEnv::start(Some("ACM"));
+    let cookie = env.login().await;
+
+    let status_id = env.submit(&cookie, "ACM", "// MOCK_VERDICT: HTTP_500\nint main() {}").await;
+    let status = env.wait_for_status(status_id, TIMEOUT);
+
+    assert_eq!(status.result, Some("System Error".to_owned()));
+    assert_eq!(status.verdict, Some("SE".to_owned()));
+    assert!(status.err_reason.is_some());
+}
+
+#[actix_rt::test]
+#[ignore]
+async fn malformed_response_does_not_stop_the_judge_loop() {
+    let env = TestEnv::start(Some("ACM"));
+    let cookie = env.login().await;
+
+    let broken_id = env.submit(&cookie, "ACM", "// MOCK_VERDICT: MALFORMED\nint main() {}").await;
+    let accepted_id = env.submit(&cookie, "ACM", "// MOCK_VERDICT: SUCCESS\nint main() {}").await;
+
+    assert_eq!(env.wait_for_status(broken_id, TIMEOUT).result, Some("System Error".to_owned()));
+    assert_eq!(env.wait_for_status(accepted_id, TIMEOUT).result, Some("Accepted".to_owned()));
+}
+
+#[derive(Debug, QueryableByName)]
+struct Count {
+    #[sql_type = "BigInt"]
+    count: i64,
+}
+
+#[actix_rt::test]
+#[ignore]
+async fn heartbeat_with_wrong_token_is_rejected_and_recorded() {
+    let env = TestEnv::start(Some("ACM"));
+    let service_url = format!("http://{}.invalid", env.region);
+
+    let response = Client::new()
+        .post(format!("{}/judge_server/heartbeat", env.base_url))
+        .header("X-Judge-Server-Token", "not_the_secret")
+        .send_json(&json!({
+            "judger_version": "mock",
+            "hostname": "intruder",
+            "cpu_core": 64,
+            "memory": 0.0,
+            "cpu": 0.0,
+            "service_url": service_url,
+        }))
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 401);
+    assert!(response.headers().get("X-Judge-Server-Token").is_none());
+
+    let recorded = sql_query("SELECT COUNT(*) AS count FROM judge_server_rejections WHERE service_url = $1")
+        .bind::<Text, _>(&service_url)
+        .get_result::<Count>(&env.conn)
+        .unwrap();
+    assert_eq!(recorded.count, 1);
+    sql_query("DELETE FROM judge_server_rejections WHERE service_url = $1")
+        .bind::<Text, _>(&service_url)
+        .execute(&env.conn)
+        .unwrap();
+}
+
+#[actix_rt::test]
+#[ignore]
+async fn silent_judge_server_is_evicted() {
+    let mut env = TestEnv::start(Some("ACM"));
+    // the first heartbeat registers the server
+    actix_rt::time::delay_for(Duration::from_secs(2)).await;
+    assert_eq!(env.judge_servers().await.len(), 1);
+
+    env.stop_mock_judge_server();
+    actix_rt::time::delay_for(Duration::from_secs(6)).await;
+    assert!(env.judge_servers().await.is_empty());
+}
+
+#[actix_rt::test]
+#[ignore]
+async fn test_case_archive_is_served_to_judge_servers_by_version() {
+    let env = TestEnv::start(Some("ACM"));
+    env.write_test_case_info(&json!({ "test_case_number": 0, "spj": false, "test_cases": {} }));
+    let cookie = env.login().await;
+
+    // test cases uploaded before versions are packed when first judged
+    let status_id = env.submit(&cookie, "ACM", "// MOCK_VERDICT: SUCCESS\nint main() {}").await;
+    env.wait_for_status(status_id, TIMEOUT);
+    let version = std::fs::read_to_string(format!("data/test_case/{}/version", env.test_case)).unwrap();
+    let url = format!("{}/test_case/archive/{}", env.base_url, version);
+
+    let response = Client::new().get(&url).send().await.unwrap();
+    assert_eq!(response.status().as_u16(), 401);
+
+    let mut response = Client::new()
+        .get(&url)
+        .header("X-Judge-Server-Token", common::judge_server_token())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(!response.body().await.unwrap().is_empty());
+
+    let _ = std::fs::remove_file(format!("data/test_case_archive/{}.tar.gz", version));
+}
+
+#[actix_rt::test]
+#[ignore]
+async fn custom_input_run_leaves_no_status() {
+    let env = TestEnv::start(Some("ACM"));
+    let cookie = env.login().await;
+
+    let mut response = Client::new()
+        .post(format!("{}/judge_server/run", env.base_url))
+        .header("Cookie", cookie.as_str())
+        .send_form(&[
+            ("src", "int main() {}"),
+            ("language", "c"),
+            ("stdin", "1 2\n"),
+        ])
+        .await
+        .unwrap();
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["verdict"], "AC");
+    // the mock judge server echoes inline inputs
+    assert_eq!(body["stdout"], "1 2\n");
+
+    let statuses = sql_query("SELECT COUNT(*) AS count FROM status \
+        WHERE owner_id = (SELECT id FROM users WHERE username = $1)")
+        .bind::<Text, _>(&env.username)
+        .get_result::<Count>(&env.conn)
+        .unwrap();
+    assert_eq!(statuses.count, 0);
+    let counter = env.problem_counter();
+    assert_eq!((counter.submit_times, counter.accept_times), (0, 0));
+}
+
+#[actix_rt::test]
+#[ignore]
+async fn rejudge_reports_the_changed_verdicts() {
+    let env = TestEnv::start(Some("ACM"));
+    let cookie = env.login().await;
+
+    let status_id = env.submit(&cookie, "ACM", "// MOCK_VERDICT: SUCCESS WRONG_ANSWER\nint main() {}").await;
+    env.wait_for_status(status_id, TIMEOUT);
+
+    let (code, _) = env.post_form(&cookie, "/status/rejudge", &[("id", &status_id.to_string())]).await;
+    assert_eq!(code, 401);
+    env.make_admin();
+    // whole problem sets are too much to rejudge at once
+    let (code, _) = env.post_form(&cookie, "/status/rejudge_region", &[("region", &env.region)]).await;
+    assert_eq!(code, 400);
+
+    // as if the test cases were fixed, the mock judge server reads its verdicts from the source
+    sql_query("UPDATE status SET setting_data = replace(setting_data, 'WRONG_ANSWER', 'SUCCESS') WHERE id = $1")
+        .bind::<SqlUuid, _>(status_id)
+        .execute(&env.conn)
+        .unwrap();
+    let (code, rejudge) = env.post_form(&cookie, "/status/rejudge", &[("id", &status_id.to_string())]).await;
+    assert_eq!(code, 200, "{}", rejudge);
+    assert_eq!(rejudge["status_count"], 1);
+    let status = env.wait_for_status(status_id, TIMEOUT);
+    assert_eq!(status.result, Some("Accepted".to_owned()));
+
+    let rejudge_id = rejudge["rejudge_id"].as_str().unwrap();
+    let (code, report) = env.post_form(&cookie, "/status/get_rejudge_report", &[("id", rejudge_id)]).await;
+    assert_eq!(code, 200, "{}", report);
+    assert_eq!(report["is_finished"], true);
+    assert_eq!(report["changes"].as_array().unwrap().len(), 1);
+    assert_eq!(report["changes"][0]["old_verdict"], "WA");
+    assert_eq!(report["changes"][0]["new_verdict"], "AC");
+}
+
+#[actix_rt::test]
+#[ignore]
+async fn problem_language_whitelist_and_limits_apply_to_submissions() {
+    let env = TestEnv::start(Some("ACM"));
+    let cookie = env.login().await;
+    sql_query("UPDATE problems SET allowed_languages = '{c}', \
+        language_limits = '[{\"language\":\"c\",\"max_cpu_time\":3000,\"max_memory\":null}]' \
+        WHERE region = $1")
+        .bind::<Text, _>(&env.region)
+        .execute(&env.conn)
+        .unwrap();
+
+    let (code, _) = env.post_form(&cookie, "/judge_server/submit", &[
+        ("problem_id", "1"),
+        ("problem_region", &env.region),
+        ("src", "int main() {}"),
+        ("language", "cpp"),
+        ("judge_type", "ACM"),
+        ("output", "false"),
+    ]).await;
+    assert_eq!(code, 400);
+
+    let status_id = env.submit(&cookie, "ACM", "// MOCK_VERDICT: SUCCESS\nint main() {}").await;
+    let setting = env.judge_setting(status_id);
+    assert_eq!(setting["max_cpu_time"], 3000);
+    // the problem default, as no memory limit is given for c
+    assert_eq!(setting["max_memory"], 268435456);
+}
+
+#[actix_rt::test]
+#[ignore]
+async fn drained_judge_server_takes_no_new_tasks_until_enabled() {
+    let env = TestEnv::start(Some("ACM"));
+    let cookie = env.login().await;
+    env.make_admin();
+    // the first heartbeat registers the server
+    actix_rt::time::delay_for(Duration::from_secs(2)).await;
+    let servers = env.judge_servers().await;
+    let service_url = servers[0]["service_url"].as_str().unwrap();
+
+    let (code, _) = env.post_form(&cookie, "/judge_server/set_max_tasks", &[
+        ("service_url", service_url),
+        ("max_tasks", "0"),
+    ]).await;
+    assert_eq!(code, 400);
+
+    let (code, info) = env.post_form(&cookie, "/judge_server/drain", &[("service_url", service_url)]).await;
+    assert_eq!(code, 200, "{}", info);
+    assert_eq!(info["is_draining"], true);
+
+    let status_id = env.submit(&cookie, "ACM", "// MOCK_VERDICT: SUCCESS\nint main() {}").await;
+    actix_rt::time::delay_for(Duration::from_secs(2)).await;
+    let state: Vec<String> = sql_query("SELECT state FROM status WHERE id = $1")
+        .bind::<SqlUuid, _>(status_id)
+        .load::<State>(&env.conn)
+        .unwrap()
+        .into_iter()
+        .map(|row| row.state)
+        .collect();
+    assert_eq!(state, vec!["Waiting".to_owned()]);
+
+    let (code, info) = env.post_form(&cookie, "/judge_server/enable", &[("service_url", service_url)]).await;
+    assert_eq!(code, 200, "{}", info);
+    assert_eq!(info["is_draining"], false);
+    let status = env.wait_for_status(status_id, TIMEOUT);
+    assert_eq!(status.result, Some("Accepted".to_owned()));
+}
+
+#[derive(Debug, QueryableByName)]
+struct State {
+    #[sql_type = "Text"]
+    state: String,
+}
+
+#[actix_rt::test]
+#[ignore]
+async fn judge_progress_subscription_ends_with_the_verdict() {
+    use actix_http::ws::{ Frame, Message };
+    use futures::{ SinkExt, StreamExt };
+
+    let env = TestEnv::start(Some("ACM"));
+    let cookie = env.login().await;
+    let status_id = env.submit(&cookie, "ACM", "// MOCK_VERDICT: SUCCESS WRONG_ANSWER\nint main() {}").await;
+    env.wait_for_status(status_id, TIMEOUT);
+
+    let (response, mut socket) = Client::new()
+        .ws(format!("{}/graphql", env.base_url))
+        .protocols(["graphql-ws"])
+        .header("Cookie", cookie.as_str())
+        .connect()
+        .await
+        .unwrap();
+    assert_eq!(response.headers().get("sec-websocket-protocol").unwrap(), "graphql-ws");
+
+    let init = json!({ "type": "connection_init", "payload": {} });
+    socket.send(Message::Text(init.to_string())).await.unwrap();
+    let start = json!({
+        "type": "start",
+        "id": "1",
+        "payload": {
+            "query": "subscription($id: Uuid!) { judgeProgress(statusId: $id) { event verdict } }",
+            "variables": { "id": status_id },
+        },
+    });
+    socket.send(Message::Text(start.to_string())).await.unwrap();
+
+    let mut messages = Vec::new();
+    while let Some(frame) = socket.next().await {
+        if let Frame::Text(text) = frame.unwrap() {
+            let message: serde_json::Value = serde_json::from_slice(&text).unwrap();
+            if message["type"] == "ka" { continue; }
+            let is_complete = message["type"] == "complete";
+            messages.push(message);
+            if is_complete { break; }
+        }
+    }
+
+    assert_eq!(messages, vec![
+        json!({ "type": "connection_ack" }),
+        json!({ "type": "data", "id": "1", "payload": { "data": { "judgeProgress": { "event": "finished", "verdict": "WA" } } } }),
+        json!({ "type": "complete", "id": "1" }),
+    ]);
+}
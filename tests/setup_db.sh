@@ -0,0 +1,9 @@
+#!/bin/sh
+# Runs the migrations for the ignored tests on hosts without diesel_cli,
+# against an empty database: DATABASE_URL=postgres://... tests/setup_db.sh
+set -e
+: "${DATABASE_URL:?DATABASE_URL must be set}"
+cd "$(dirname "$0")/.."
+for up in migrations/*/up.sql; do
+    psql "$DATABASE_URL" -v ON_ERROR_STOP=1 -q -f "$up"
+done